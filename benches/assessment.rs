@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use fdk_mqa_property_checker::{
+    parse_rdf_graph_and_calculate_metrics, post_processing::SourceContext, testdata,
+    AssessmentOptions,
+};
+use oxigraph::store::Store;
+
+/// Generated datasets, by name and number of distributions.
+const FIXTURES: [(&str, usize); 3] = [("small", 1), ("medium", 20), ("large", 200)];
+
+fn assessment(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let options = AssessmentOptions {
+        reference_data: Some(testdata::reference_data()),
+        ..Default::default()
+    };
+    // Stores are reused between graphs, like by the Kafka workers.
+    let input_store = Store::new().unwrap();
+    let output_store = Store::new().unwrap();
+
+    let mut group = c.benchmark_group("assessment");
+    group
+        .throughput(Throughput::Elements(1))
+        .sample_size(10)
+        .warm_up_time(Duration::from_secs(1))
+        .measurement_time(Duration::from_secs(5));
+
+    for (name, distributions) in FIXTURES {
+        let graph = testdata::dataset_graph(distributions);
+        group.bench_with_input(BenchmarkId::from_parameter(name), &graph, |b, graph| {
+            b.to_async(&runtime).iter(|| async {
+                parse_rdf_graph_and_calculate_metrics(
+                    &input_store,
+                    &output_store,
+                    graph.clone(),
+                    None,
+                    &SourceContext::default(),
+                    &options,
+                )
+                .await
+                .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, assessment);
+criterion_main!(benches);
@@ -0,0 +1,171 @@
+use std::env;
+
+use actix_web::{
+    http::{header, StatusCode},
+    web, HttpRequest, HttpResponse,
+};
+use lazy_static::lazy_static;
+use oxigraph::store::Store;
+
+use crate::{
+    error::Error,
+    metrics::{parse_rdf_graph_and_calculate_metrics, AssessmentOptions, GraphLayout},
+    post_processing::SourceContext,
+    prometheus_metrics::ASSESS_REQUESTS,
+};
+
+lazy_static! {
+    pub static ref ASSESS_API_PORT: Option<u16> = env::var("ASSESS_API_PORT").ok().map(|v| {
+        v.parse().unwrap_or_else(|e| {
+            tracing::error!(error = e.to_string(), "invalid assess API port");
+            std::process::exit(1);
+        })
+    });
+    pub static ref ASSESS_MAX_BODY_BYTES: usize = env::var("ASSESS_MAX_BODY_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5 * 1024 * 1024);
+}
+
+const TURTLE: &str = "text/turtle";
+const TRIG: &str = "application/trig";
+
+/// Maximum size of graphs accepted for assessment, and the options they are
+/// assessed with.
+#[derive(Debug, Clone)]
+struct AssessConfig {
+    max_body_bytes: usize,
+    options: AssessmentOptions,
+}
+
+/// Registers `POST /assess`, assessing Turtle graphs of at most
+/// `max_body_bytes` bytes with the given options.
+pub fn configure(cfg: &mut web::ServiceConfig, max_body_bytes: usize, options: AssessmentOptions) {
+    cfg.service(
+        web::resource("/assess")
+            .app_data(web::Data::new(AssessConfig {
+                max_body_bytes,
+                options,
+            }))
+            .route(web::post().to(assess)),
+    );
+}
+
+/// Media type of assessments serialized with the given layout.
+fn output_media_type(layout: GraphLayout) -> &'static str {
+    match layout {
+        GraphLayout::Flat => TURTLE,
+        GraphLayout::PerAssessmentNamedGraphs => TRIG,
+    }
+}
+
+async fn assess(
+    request: HttpRequest,
+    payload: web::Payload,
+    config: web::Data<AssessConfig>,
+) -> HttpResponse {
+    let media_type = output_media_type(config.options.graph_layout);
+    let response = match assess_payload(&request, payload, &config, media_type).await {
+        Ok(graph) => HttpResponse::Ok().content_type(media_type).body(graph),
+        Err((status, message)) => HttpResponse::build(status).body(message),
+    };
+    ASSESS_REQUESTS
+        .with_label_values(&[response.status().as_str()])
+        .inc();
+    response
+}
+
+async fn assess_payload(
+    request: &HttpRequest,
+    payload: web::Payload,
+    config: &AssessConfig,
+    media_type: &str,
+) -> Result<String, (StatusCode, String)> {
+    if !accepts_content_type(request) {
+        return Err((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("only {} graphs are supported", TURTLE),
+        ));
+    }
+    if !accepts(request, media_type) {
+        return Err((
+            StatusCode::NOT_ACCEPTABLE,
+            format!("assessments are only available as {}", media_type),
+        ));
+    }
+
+    let limit = config.max_body_bytes;
+    let body = match payload.to_bytes_limited(limit).await {
+        Ok(Ok(body)) => body,
+        Ok(Err(e)) => return Err((StatusCode::BAD_REQUEST, e.to_string())),
+        Err(_) => {
+            return Err((
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("graph exceeds {} bytes", limit),
+            ))
+        }
+    };
+    let graph = String::from_utf8(body.to_vec()).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("graph is not UTF-8: {}", e),
+        )
+    })?;
+
+    let input_store = Store::new().map_err(internal_error)?;
+    let output_store = Store::new().map_err(internal_error)?;
+    parse_rdf_graph_and_calculate_metrics(
+        &input_store,
+        &output_store,
+        graph,
+        None,
+        &SourceContext::default(),
+        &config.options,
+    )
+    .await
+    .map(|(graph, _)| graph)
+    .map_err(|e| (error_status(&e), e.to_string()))
+}
+
+/// Whether the request body is Turtle, assumed when no content type is given.
+fn accepts_content_type(request: &HttpRequest) -> bool {
+    match request.headers().get(header::CONTENT_TYPE) {
+        Some(content_type) => content_type
+            .to_str()
+            .is_ok_and(|v| v.split(';').next().unwrap_or_default().trim() == TURTLE),
+        None => true,
+    }
+}
+
+/// Whether the client accepts a response of the given media type.
+fn accepts(request: &HttpRequest, media_type: &str) -> bool {
+    let top_level_type = media_type.split('/').next().unwrap_or_default();
+    match request.headers().get(header::ACCEPT) {
+        Some(accept) => accept.to_str().is_ok_and(|v| {
+            v.split(',')
+                .map(|media_range| media_range.split(';').next().unwrap_or_default().trim())
+                .any(|media_range| {
+                    media_range == media_type
+                        || media_range == "*/*"
+                        || media_range.strip_suffix("/*") == Some(top_level_type)
+                })
+        }),
+        None => true,
+    }
+}
+
+fn error_status(error: &Error) -> StatusCode {
+    match error {
+        Error::LoaderError(_) | Error::InvalidTurtle { .. } => StatusCode::BAD_REQUEST,
+        Error::EmptyGraph
+        | Error::DatasetNodeNotFound
+        | Error::AssessmentNotFound { .. }
+        | Error::InvalidAssessmentNode { .. }
+        | Error::InvalidAssessmentIri { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn internal_error(error: impl Into<Error>) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, error.into().to_string())
+}
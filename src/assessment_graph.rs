@@ -0,0 +1,271 @@
+use oxigraph::{
+    io::RdfFormat,
+    model::{vocab::rdf, NamedNode, NamedNodeRef, Subject, SubjectRef, Term},
+    store::Store,
+};
+
+use crate::{
+    error::Error,
+    metrics::Measurement,
+    rdf::boolean_literal_value,
+    vocab::{dcat_mqa, dqv, oa},
+};
+
+/// Ratings of the five-star annotations of distribution assessments.
+const FIVE_STAR_RATINGS: [NamedNodeRef<'static>; 6] = [
+    dcat_mqa::ZERO_STARS,
+    dcat_mqa::ONE_STAR,
+    dcat_mqa::TWO_STARS,
+    dcat_mqa::THREE_STARS,
+    dcat_mqa::FOUR_STARS,
+    dcat_mqa::FIVE_STARS,
+];
+
+/// MQA graph, with accessors for its assessments and their measurements.
+/// Accessors look in all graphs of the store, so graphs with each assessment
+/// in a named graph are read the same as graphs with all assessments in the
+/// default graph.
+#[derive(Clone)]
+pub struct AssessmentGraph {
+    store: Store,
+}
+
+impl AssessmentGraph {
+    pub fn new(store: Store) -> Self {
+        AssessmentGraph { store }
+    }
+
+    /// Parses an MQA graph, Turtle or TriG.
+    pub fn parse(graph: &str) -> Result<Self, Error> {
+        let store = Store::new()?;
+        store.load_from_reader(RdfFormat::TriG, graph.as_bytes())?;
+        Ok(AssessmentGraph { store })
+    }
+
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// Dataset assessments, in IRI order.
+    pub fn dataset_assessments(&self) -> Vec<NamedNode> {
+        let mut assessments = self
+            .store
+            .quads_for_pattern(
+                None,
+                Some(rdf::TYPE),
+                Some(dcat_mqa::DATASET_ASSESSMENT_CLASS.into()),
+                None,
+            )
+            .filter_map(Result::ok)
+            .filter_map(|quad| match quad.subject {
+                Subject::NamedNode(assessment) => Some(assessment),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assessments.sort();
+        assessments.dedup();
+        assessments
+    }
+
+    /// Distribution assessments of a dataset assessment, in IRI order.
+    pub fn distribution_assessments(&self, dataset_assessment: NamedNodeRef) -> Vec<NamedNode> {
+        let mut assessments = self
+            .objects(
+                dataset_assessment.into(),
+                dcat_mqa::HAS_DISTRIBUTION_ASSESSMENT,
+            )
+            .into_iter()
+            .filter_map(|object| match object {
+                Term::NamedNode(assessment) => Some(assessment),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assessments.sort();
+        assessments.dedup();
+        assessments
+    }
+
+    /// Assessment of a dataset or distribution, if it has one.
+    pub fn assessment_of(&self, node: NamedNodeRef) -> Option<NamedNode> {
+        self.store
+            .quads_for_pattern(None, Some(dcat_mqa::ASSESSMENT_OF), Some(node.into()), None)
+            .filter_map(Result::ok)
+            .find_map(|quad| match quad.subject {
+                Subject::NamedNode(assessment) => Some(assessment),
+                _ => None,
+            })
+    }
+
+    /// Measurements of an assessment with a metric, node and boolean value,
+    /// in order of their metrics.
+    pub fn measurements_for(&self, assessment: NamedNodeRef) -> Vec<Measurement> {
+        let mut measurements = self
+            .objects(assessment.into(), dcat_mqa::CONTAINS_QUALITY_MEASUREMENT)
+            .into_iter()
+            .filter_map(|measurement| {
+                let measurement = match measurement {
+                    Term::NamedNode(node) => Subject::NamedNode(node),
+                    Term::BlankNode(node) => Subject::BlankNode(node),
+                    _ => return None,
+                };
+                let metric = self.named_object(measurement.as_ref(), dqv::IS_MEASUREMENT_OF)?;
+                let computed_on = self.named_object(measurement.as_ref(), dqv::COMPUTED_ON)?;
+                let value = self
+                    .objects(measurement.as_ref(), dqv::VALUE)
+                    .iter()
+                    .find_map(boolean_literal_value)?;
+                Some(Measurement {
+                    metric: metric.into_string(),
+                    computed_on: computed_on.into_string(),
+                    value,
+                })
+            })
+            .collect::<Vec<_>>();
+        measurements.sort_by(|a, b| a.metric.cmp(&b.metric));
+        measurements
+    }
+
+    /// Value of the measurement of a metric of an assessment, if measured.
+    pub fn measurement_value(
+        &self,
+        assessment: NamedNodeRef,
+        metric: NamedNodeRef,
+    ) -> Option<bool> {
+        self.measurements_for(assessment)
+            .into_iter()
+            .find(|measurement| measurement.metric == metric.as_str())
+            .map(|measurement| measurement.value)
+    }
+
+    /// Rating of the five-star annotation targeting an assessment, like
+    /// `dcatno-mqa:threeStars`.
+    pub fn five_star_rating(&self, assessment: NamedNodeRef) -> Option<NamedNode> {
+        self.store
+            .quads_for_pattern(None, Some(oa::HAS_TARGET), Some(assessment.into()), None)
+            .filter_map(Result::ok)
+            .find_map(|quad| {
+                self.named_object(quad.subject.as_ref(), oa::HAS_BODY)
+                    .filter(|body| FIVE_STAR_RATINGS.contains(&body.as_ref()))
+            })
+    }
+
+    fn objects(&self, subject: SubjectRef, predicate: NamedNodeRef) -> Vec<Term> {
+        self.store
+            .quads_for_pattern(Some(subject), Some(predicate), None, None)
+            .filter_map(Result::ok)
+            .map(|quad| quad.object)
+            .collect()
+    }
+
+    fn named_object(&self, subject: SubjectRef, predicate: NamedNodeRef) -> Option<NamedNode> {
+        self.objects(subject, predicate)
+            .into_iter()
+            .find_map(|object| match object {
+                Term::NamedNode(node) => Some(node),
+                _ => None,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATASET_ASSESSMENT: NamedNodeRef =
+        NamedNodeRef::new_unchecked("http://dataset.assessment.no");
+    const DISTRIBUTION_ASSESSMENT: NamedNodeRef =
+        NamedNodeRef::new_unchecked("http://dist.foo.assessment.no");
+    const DATASET: &str = "https://registrering.fellesdatakatalog.digdir.no/catalogs/971277882/datasets/29a2bf37-5867-4c90-bc74-5a8c4e118572";
+
+    fn graph() -> AssessmentGraph {
+        AssessmentGraph::parse(include_str!("../tests/data/mqa_event.ttl")).unwrap()
+    }
+
+    #[test]
+    fn test_dataset_assessments() {
+        assert_eq!(
+            vec![DATASET_ASSESSMENT.into_owned()],
+            graph().dataset_assessments()
+        );
+    }
+
+    #[test]
+    fn test_distribution_assessments() {
+        let graph = graph();
+        assert_eq!(
+            vec![DISTRIBUTION_ASSESSMENT.into_owned()],
+            graph.distribution_assessments(DATASET_ASSESSMENT)
+        );
+        assert!(graph
+            .distribution_assessments(DISTRIBUTION_ASSESSMENT)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_assessment_of() {
+        let graph = graph();
+        assert_eq!(
+            Some(DATASET_ASSESSMENT.into_owned()),
+            graph.assessment_of(NamedNodeRef::new_unchecked(DATASET))
+        );
+        assert_eq!(
+            Some(DISTRIBUTION_ASSESSMENT.into_owned()),
+            graph.assessment_of(NamedNodeRef::new_unchecked(&format!(
+                "{}/.well-known/skolem/1",
+                DATASET
+            )))
+        );
+        assert_eq!(
+            None,
+            graph.assessment_of(NamedNodeRef::new_unchecked("https://dataset.foo"))
+        );
+    }
+
+    #[test]
+    fn test_measurements_for() {
+        let graph = graph();
+        let measurements = graph.measurements_for(DATASET_ASSESSMENT);
+        assert_eq!(20, measurements.len());
+        assert!(measurements
+            .iter()
+            .all(|measurement| measurement.computed_on == DATASET));
+        assert!(measurements.contains(&Measurement {
+            metric: dcat_mqa::KEYWORD_AVAILABILITY.as_str().to_string(),
+            computed_on: DATASET.to_string(),
+            value: true,
+        }));
+
+        assert_eq!(19, graph.measurements_for(DISTRIBUTION_ASSESSMENT).len());
+        assert!(graph
+            .measurements_for(NamedNodeRef::new_unchecked("https://assessment.foo"))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_measurement_value() {
+        let graph = graph();
+        assert_eq!(
+            Some(true),
+            graph.measurement_value(DATASET_ASSESSMENT, dcat_mqa::KEYWORD_AVAILABILITY)
+        );
+        assert_eq!(
+            Some(false),
+            graph.measurement_value(DISTRIBUTION_ASSESSMENT, dcat_mqa::DOWNLOAD_URL_AVAILABILITY)
+        );
+        // Measured on the distribution, not the dataset.
+        assert_eq!(
+            None,
+            graph.measurement_value(DATASET_ASSESSMENT, dcat_mqa::DOWNLOAD_URL_AVAILABILITY)
+        );
+    }
+
+    #[test]
+    fn test_five_star_rating() {
+        let graph = graph();
+        assert_eq!(
+            Some(dcat_mqa::ZERO_STARS.into_owned()),
+            graph.five_star_rating(DISTRIBUTION_ASSESSMENT)
+        );
+        assert_eq!(None, graph.five_star_rating(DATASET_ASSESSMENT));
+    }
+}
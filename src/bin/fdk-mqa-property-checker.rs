@@ -1,14 +1,26 @@
-use actix_web::{get, App, HttpServer, Responder};
+use actix_web::{get, App, HttpResponse, HttpServer, Responder};
 use futures::{stream::FuturesUnordered, FutureExt, StreamExt};
 
 use fdk_mqa_property_checker::{
+    assess_api::{self, ASSESS_API_PORT, ASSESS_MAX_BODY_BYTES},
+    config_check::{validate_config, VALIDATE_CONFIG},
     kafka::{
-        create_sr_settings, run_async_processor, BROKERS, INPUT_TOPIC, OUTPUT_TOPIC,
-        SCHEMA_REGISTRY,
+        context::PARTITION_ASSIGNMENTS,
+        create_sr_settings,
+        inputs::{format_input_specs, INPUTS},
+        pipeline::ProcessingPipeline,
+        router::{output_topics, OUTPUT_ROUTES},
+        topics::ensure_topics,
+        ASSESSMENT_OPTIONS, BROKERS, DRY_RUN, GROUP_ID, OUTPUT_TOPIC, REGISTER_SCHEMAS,
+        SCHEMA_REGISTRY, SUBJECT_NAME_STRATEGY,
     },
+    log_level::{self, init_tracing, ADMIN_TOKEN},
+    metrics_catalog::{self, format_catalog, metrics_catalog, LIST_METRICS},
     prometheus_metrics::{get_metrics, register_metrics},
     schemas::setup_schemas,
+    REFERENCE_DATA_PROFILES,
 };
+use tokio::sync::watch;
 
 #[get("/ping")]
 async fn ping() -> impl Responder {
@@ -17,7 +29,11 @@ async fn ping() -> impl Responder {
 
 #[get("/ready")]
 async fn ready() -> impl Responder {
-    "ok"
+    if PARTITION_ASSIGNMENTS.any_assigned() {
+        HttpResponse::Ok().body("ok")
+    } else {
+        HttpResponse::ServiceUnavailable().body("no partitions assigned")
+    }
 }
 
 #[get("/metrics")]
@@ -33,49 +49,136 @@ async fn metrics() -> impl Responder {
 
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt()
-        .json()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .with_target(false)
-        .with_current_span(false)
-        .init();
+    let log_level_handle = init_tracing();
     
     tracing::debug!("Tracing initialized");
 
     register_metrics();
 
+    if *VALIDATE_CONFIG {
+        let report = validate_config().await;
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).unwrap_or_else(|e| e.to_string())
+        );
+        std::process::exit(if report.valid { 0 } else { 1 });
+    }
+
+    if let Some(format) = *LIST_METRICS {
+        match format_catalog(&metrics_catalog(&ASSESSMENT_OPTIONS), format) {
+            Ok(catalog) => println!("{}", catalog),
+            Err(e) => {
+                tracing::error!(error = e.to_string(), "unable to list metrics");
+                std::process::exit(1);
+            }
+        }
+        std::process::exit(0);
+    }
+
+    // Exit on invalid reference data profiles before consuming any events.
+    lazy_static::initialize(&REFERENCE_DATA_PROFILES);
+
     tracing::info!(
         brokers = BROKERS.to_string(),
         schema_registry = SCHEMA_REGISTRY.to_string(),
-        input_topic = INPUT_TOPIC.to_string(),
+        inputs = format_input_specs(&INPUTS),
         output_topic = OUTPUT_TOPIC.to_string(),
+        output_routes = OUTPUT_ROUTES
+            .iter()
+            .map(|route| format!("{}={}", route.topic, route.publisher_prefix))
+            .collect::<Vec<String>>()
+            .join(","),
+        group_id = GROUP_ID.to_string(),
+        assessment_base_iri = ASSESSMENT_OPTIONS.assessment_base_iri.as_deref(),
+        default_base_iri = ASSESSMENT_OPTIONS.default_base_iri.as_deref(),
         "starting service"
     );
 
+    if *DRY_RUN {
+        tracing::warn!(
+            "DRY RUN ENABLED: assessments are calculated but nothing is produced to {}",
+            OUTPUT_TOPIC.as_str()
+        );
+    }
+
     let sr_settings = create_sr_settings().unwrap_or_else(|e| {
         tracing::error!(error = e.to_string(), "sr settings creation error");
         std::process::exit(1);
     });
 
-    setup_schemas(&sr_settings).await.unwrap_or_else(|e| {
-        tracing::error!(error = e.to_string(), "schema registration error");
+    setup_schemas(
+        &sr_settings,
+        *SUBJECT_NAME_STRATEGY,
+        &output_topics(&OUTPUT_TOPIC, &OUTPUT_ROUTES),
+        *REGISTER_SCHEMAS,
+    )
+    .await
+    .unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "schema setup error");
+        std::process::exit(1);
+    });
+
+    ensure_topics().await.unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "topic check failed");
         std::process::exit(1);
     });
 
     let http_server = tokio::spawn(
-        HttpServer::new(|| App::new().service(ping).service(ready).service(metrics))
-            .bind(("0.0.0.0", 8080))
+        HttpServer::new(move || {
+            let log_level_handle = log_level_handle.clone();
+            App::new()
+                .service(ping)
+                .service(ready)
+                .service(metrics)
+                .configure(|cfg| log_level::configure(cfg, log_level_handle, ADMIN_TOKEN.clone()))
+        })
+        .bind(("0.0.0.0", 8080))
+        .unwrap_or_else(|e| {
+            tracing::error!(error = e.to_string(), "metrics server error");
+            std::process::exit(1);
+        })
+        .run()
+        .map(|f| f.map_err(|e| e.into())),
+    );
+
+    let assess_api_server = ASSESS_API_PORT.map(|port| {
+        tracing::info!(port, "starting assess API");
+        tokio::spawn(
+            HttpServer::new(|| {
+                App::new()
+                    .configure(|cfg| {
+                        assess_api::configure(
+                            cfg,
+                            *ASSESS_MAX_BODY_BYTES,
+                            ASSESSMENT_OPTIONS.clone(),
+                        )
+                    })
+                    .configure(|cfg| {
+                        metrics_catalog::configure(cfg, metrics_catalog(&ASSESSMENT_OPTIONS))
+                    })
+            })
+            .bind(("0.0.0.0", port))
             .unwrap_or_else(|e| {
-                tracing::error!(error = e.to_string(), "metrics server error");
+                tracing::error!(error = e.to_string(), "assess API server error");
                 std::process::exit(1);
             })
             .run()
             .map(|f| f.map_err(|e| e.into())),
-    );
+        )
+    });
+
+    let pipeline = ProcessingPipeline::builder().build().unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "pipeline configuration error");
+        std::process::exit(1);
+    });
+    // The workers run for the lifetime of the service, so shutdown is never
+    // signalled.
+    let (_shutdown, shutdown_rx) = watch::channel(false);
+    let workers = tokio::spawn(pipeline.run(shutdown_rx));
 
-    (0..4)
-        .map(|i| tokio::spawn(run_async_processor(i, sr_settings.clone())))
+    std::iter::once(workers)
         .chain(std::iter::once(http_server))
+        .chain(assess_api_server)
         .collect::<FuturesUnordered<_>>()
         .for_each(|result| async {
             result
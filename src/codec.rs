@@ -0,0 +1,205 @@
+use std::borrow::Cow;
+
+use apache_avro::{from_avro_datum, types::Value, Schema};
+use schema_registry_converter::avro_common::DecodeResult;
+
+use crate::error::Error;
+
+const BOM: char = '\u{feff}';
+
+/// Keys of Avro schema objects whose values are names or data, not types.
+const NON_TYPE_KEYS: [&str; 6] = ["name", "namespace", "doc", "default", "symbols", "aliases"];
+
+/// Text of bytes expected to be UTF-8, with each invalid sequence replaced by
+/// U+FFFD, and the number of replacements.
+pub fn decode_utf8_lossy(bytes: &[u8]) -> (Cow<'_, str>, usize) {
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return (Cow::Borrowed(text), 0);
+    }
+
+    let mut text = String::with_capacity(bytes.len());
+    let mut replacements = 0;
+    for chunk in bytes.utf8_chunks() {
+        text.push_str(chunk.valid());
+        if !chunk.invalid().is_empty() {
+            text.push(char::REPLACEMENT_CHARACTER);
+            replacements += 1;
+        }
+    }
+    (Cow::Owned(text), replacements)
+}
+
+/// Text without a leading byte order mark, which RDF parsers reject.
+pub fn strip_bom(text: String) -> String {
+    match text.strip_prefix(BOM) {
+        Some(stripped) => stripped.to_string(),
+        None => text,
+    }
+}
+
+/// Decodes an Avro datum written with `writer_schema`, reading strings as
+/// bytes converted with [`decode_utf8_lossy`], and returns the number of
+/// replacements. Used for payloads rejected for invalid UTF-8.
+pub fn decode_avro_lossy(
+    writer_schema: &str,
+    datum: &[u8],
+) -> Result<(DecodeResult, usize), Error> {
+    let name = match Schema::parse_str(writer_schema)? {
+        Schema::Record(record) => Some(record.name),
+        _ => None,
+    };
+
+    let mut schema: serde_json::Value = serde_json::from_str(writer_schema)?;
+    strings_as_bytes(&mut schema);
+    let value = from_avro_datum(&Schema::parse(&schema)?, &mut &datum[..], None)?;
+
+    let mut replacements = 0;
+    let value = bytes_as_strings(value, &mut replacements);
+    Ok((DecodeResult { name, value }, replacements))
+}
+
+/// Replaces the `string` types of a schema by `bytes`, which are encoded the
+/// same way.
+fn strings_as_bytes(schema: &mut serde_json::Value) {
+    match schema {
+        serde_json::Value::String(schema_type) if schema_type == "string" => {
+            *schema_type = "bytes".to_string()
+        }
+        serde_json::Value::Array(schemas) => schemas.iter_mut().for_each(strings_as_bytes),
+        serde_json::Value::Object(object) => object
+            .iter_mut()
+            .filter(|(key, _)| !NON_TYPE_KEYS.contains(&key.as_str()))
+            .for_each(|(_, value)| strings_as_bytes(value)),
+        _ => {}
+    }
+}
+
+/// Converts the bytes of a value decoded with [`strings_as_bytes`] back to
+/// strings, counting the replaced invalid sequences.
+fn bytes_as_strings(value: Value, replacements: &mut usize) -> Value {
+    match value {
+        Value::Bytes(bytes) => {
+            let (text, replaced) = decode_utf8_lossy(&bytes);
+            *replacements += replaced;
+            Value::String(text.into_owned())
+        }
+        Value::Record(fields) => Value::Record(
+            fields
+                .into_iter()
+                .map(|(name, value)| (name, bytes_as_strings(value, replacements)))
+                .collect(),
+        ),
+        Value::Union(index, value) => {
+            Value::Union(index, Box::new(bytes_as_strings(*value, replacements)))
+        }
+        Value::Array(values) => Value::Array(
+            values
+                .into_iter()
+                .map(|value| bytes_as_strings(value, replacements))
+                .collect(),
+        ),
+        Value::Map(values) => Value::Map(
+            values
+                .into_iter()
+                .map(|(key, value)| (key, bytes_as_strings(value, replacements)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apache_avro::{from_value, to_avro_datum};
+    use oxigraph::store::Store;
+
+    use super::*;
+    use crate::{rdf::parse_turtle, schemas::DatasetEvent};
+
+    const TURTLE: &[u8] = b"<https://dataset.foo> <http://purl.org/dc/terms/title> \"";
+
+    /// Turtle with a title made of the given bytes.
+    fn turtle_with_title(title: &[u8]) -> Vec<u8> {
+        [TURTLE, title, b"\" ."].concat()
+    }
+
+    fn parses(text: String) -> bool {
+        parse_turtle(&Store::new().unwrap(), text).is_ok()
+    }
+
+    #[test]
+    fn test_valid_utf8_borrowed() {
+        let (text, replacements) = decode_utf8_lossy("Åpne data".as_bytes());
+        assert!(matches!(text, Cow::Borrowed("Åpne data")));
+        assert_eq!(0, replacements);
+    }
+
+    #[test]
+    fn test_invalid_sequences_replaced() {
+        let bytes = turtle_with_title(b"Data\xff fra \xe5pne kilder\xe2\x82");
+        let (text, replacements) = decode_utf8_lossy(&bytes);
+
+        assert_eq!(3, replacements);
+        assert!(text.contains("Data\u{fffd} fra \u{fffd}pne kilder\u{fffd}"));
+        assert!(parses(text.into_owned()));
+    }
+
+    #[test]
+    fn test_overlong_encoding_replaced() {
+        // '/' encoded in two and three bytes.
+        let bytes = turtle_with_title(b"a\xc0\xafb\xe0\x80\xafc");
+        let (text, replacements) = decode_utf8_lossy(&bytes);
+
+        assert!(replacements > 0);
+        assert!(!text.contains('/'));
+        assert!(parses(text.into_owned()));
+    }
+
+    #[test]
+    fn test_bom_stripped() {
+        let bytes = [b"\xef\xbb\xbf".as_slice(), &turtle_with_title(b"Data")].concat();
+        let (text, replacements) = decode_utf8_lossy(&bytes);
+
+        assert_eq!(0, replacements);
+        assert!(!parses(text.to_string()));
+        assert!(parses(strip_bom(text.into_owned())));
+        assert_eq!("foo", strip_bom("foo".to_string()));
+    }
+
+    #[test]
+    fn test_decode_avro_lossy() {
+        let writer_schema = include_str!("../kafka/schemas/no.fdk.mqa.DatasetEvent.json");
+        let mut bytes_schema: serde_json::Value = serde_json::from_str(writer_schema).unwrap();
+        strings_as_bytes(&mut bytes_schema);
+        let bytes_schema = Schema::parse(&bytes_schema).unwrap();
+
+        let graph = turtle_with_title(b"Data\xff");
+        let datum = to_avro_datum(
+            &bytes_schema,
+            Value::Record(vec![
+                (
+                    "type".to_string(),
+                    Value::Enum(0, "DATASET_HARVESTED".to_string()),
+                ),
+                ("fdkId".to_string(), Value::Bytes(b"fdk-id".to_vec())),
+                ("graph".to_string(), Value::Bytes(graph)),
+                ("timestamp".to_string(), Value::Long(1)),
+            ]),
+        )
+        .unwrap();
+        assert!(from_avro_datum(
+            &Schema::parse_str(writer_schema).unwrap(),
+            &mut &datum[..],
+            None
+        )
+        .is_err());
+
+        let (decoded, replacements) = decode_avro_lossy(writer_schema, &datum).unwrap();
+        assert_eq!(1, replacements);
+        assert_eq!("DatasetEvent", decoded.name.unwrap().name);
+
+        let event = from_value::<DatasetEvent>(&decoded.value).unwrap();
+        assert_eq!("fdk-id", event.fdk_id);
+        assert!(parses(event.graph));
+    }
+}
@@ -0,0 +1,190 @@
+use std::fs;
+
+use oxigraph::{
+    model::{NamedNode, NamedNodeRef},
+    store::{StorageError, Store},
+};
+use serde_derive::Deserialize;
+
+use crate::{
+    error::Error,
+    vocab::{dcat, dcterms},
+};
+
+/// Number of values a property of datasets must have, at least `min` and, if
+/// given, at most `max`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CardinalityRule {
+    pub predicate: NamedNode,
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl CardinalityRule {
+    fn new(predicate: NamedNodeRef, min: usize, max: Option<usize>) -> Self {
+        CardinalityRule {
+            predicate: predicate.into_owned(),
+            min,
+            max,
+        }
+    }
+
+    /// Whether a number of values satisfies the rule.
+    pub fn holds(&self, count: usize) -> bool {
+        count >= self.min && self.max.is_none_or(|max| count <= max)
+    }
+}
+
+/// Cardinalities of the mandatory properties of datasets in DCAT-AP-NO.
+pub fn default_rules() -> Vec<CardinalityRule> {
+    vec![
+        CardinalityRule::new(dcterms::TITLE, 1, None),
+        CardinalityRule::new(dcterms::DESCRIPTION, 1, None),
+        CardinalityRule::new(dcterms::PUBLISHER, 1, Some(1)),
+        CardinalityRule::new(dcat::CONTACT_POINT, 1, None),
+        CardinalityRule::new(dcat::THEME, 1, None),
+    ]
+}
+
+/// Rule of a dataset not satisfied, with the number of values it has.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation<'a> {
+    pub rule: &'a CardinalityRule,
+    pub count: usize,
+}
+
+/// Rules not satisfied by a dataset of the store.
+pub fn violations<'a>(
+    rules: &'a [CardinalityRule],
+    dataset: NamedNodeRef,
+    store: &Store,
+) -> Result<Vec<Violation<'a>>, StorageError> {
+    let mut violations = Vec::new();
+    for rule in rules {
+        let count = store
+            .quads_for_pattern(
+                Some(dataset.into()),
+                Some(rule.predicate.as_ref()),
+                None,
+                None,
+            )
+            .try_fold(0, |count, quad| quad.map(|_| count + 1))?;
+        if !rule.holds(count) {
+            violations.push(Violation { rule, count });
+        }
+    }
+    Ok(violations)
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CardinalityRuleEntry {
+    predicate: String,
+    min: usize,
+    max: Option<usize>,
+}
+
+/// Reads cardinality rules from a JSON file.
+pub fn load_compliance_rules(path: &str) -> Result<Vec<CardinalityRule>, Error> {
+    parse_compliance_rules(&fs::read_to_string(path)?)
+}
+
+/// Parses a JSON array of cardinality rules, objects with a `predicate` IRI,
+/// a `min` number of values and an optional `max` number of values.
+pub fn parse_compliance_rules(json: &str) -> Result<Vec<CardinalityRule>, Error> {
+    let entries: Vec<CardinalityRuleEntry> =
+        serde_json::from_str(json).map_err(|e| format!("invalid compliance rules: {}", e))?;
+    entries
+        .into_iter()
+        .map(|entry| {
+            let predicate = NamedNode::new(&entry.predicate)
+                .map_err(|e| format!("invalid compliance rule '{}': {}", entry.predicate, e))?;
+            if entry.max.is_some_and(|max| max < entry.min) {
+                return Err(format!(
+                    "invalid compliance rule '{}': max is less than min",
+                    entry.predicate
+                )
+                .into());
+            }
+            Ok(CardinalityRule {
+                predicate,
+                min: entry.min,
+                max: entry.max,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rdf::parse_turtle;
+
+    fn store(turtle: &str) -> Store {
+        let store = Store::new().unwrap();
+        parse_turtle(&store, turtle.to_string()).unwrap();
+        store
+    }
+
+    #[test]
+    fn test_violations() {
+        let store = store(
+            r#"
+            @prefix dct: <http://purl.org/dc/terms/> .
+            <https://dataset.foo> dct:title "Foo"@nb , "Foo"@en ;
+                dct:publisher <https://organization.foo> , <https://organization.bar> .
+            "#,
+        );
+        let rules = default_rules();
+        let violations = violations(
+            &rules,
+            NamedNodeRef::new_unchecked("https://dataset.foo"),
+            &store,
+        )
+        .unwrap();
+
+        let found = violations
+            .iter()
+            .map(|violation| (violation.rule.predicate.as_ref(), violation.count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (dcterms::DESCRIPTION, 0),
+                (dcterms::PUBLISHER, 2),
+                (dcat::CONTACT_POINT, 0),
+                (dcat::THEME, 0),
+            ],
+            found
+        );
+    }
+
+    #[test]
+    fn test_parse_compliance_rules() {
+        let rules = parse_compliance_rules(
+            r#"[
+                {"predicate": "http://purl.org/dc/terms/title", "min": 1},
+                {"predicate": "http://purl.org/dc/terms/publisher", "min": 1, "max": 1}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                CardinalityRule::new(dcterms::TITLE, 1, None),
+                CardinalityRule::new(dcterms::PUBLISHER, 1, Some(1)),
+            ],
+            rules
+        );
+        assert!(rules[1].holds(1));
+        assert!(!rules[1].holds(2));
+
+        assert!(parse_compliance_rules(r#"[{"predicate": "title", "min": 1}]"#).is_err());
+        assert!(parse_compliance_rules(
+            r#"[{"predicate": "http://purl.org/dc/terms/title", "min": 2, "max": 1}]"#
+        )
+        .is_err());
+        assert!(parse_compliance_rules(
+            r#"[{"predicate": "http://purl.org/dc/terms/title", "minimum": 1}]"#
+        )
+        .is_err());
+    }
+}
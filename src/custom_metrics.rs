@@ -0,0 +1,178 @@
+use std::fs;
+
+use oxigraph::{
+    model::{NamedNode, NamedNodeRef},
+    sparql::{EvaluationError, Query, QueryResults, SparqlSyntaxError},
+    store::Store,
+};
+use serde_derive::Deserialize;
+
+use crate::error::Error;
+
+/// Node a custom metric is computed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Target {
+    Dataset,
+    Distribution,
+}
+
+#[derive(Deserialize)]
+struct CustomMetricEntry {
+    metric: String,
+    target: Target,
+    query: String,
+}
+
+/// Metric defined by a SPARQL ASK query on the input graph, with `?node`
+/// bound to the dataset or distribution measured.
+#[derive(Debug, Clone)]
+pub struct CustomMetric {
+    pub metric: NamedNode,
+    pub target: Target,
+    query: String,
+}
+
+impl CustomMetric {
+    /// Whether the query of the metric holds for a node of the store.
+    pub fn evaluate(&self, node: NamedNodeRef, store: &Store) -> Result<bool, Error> {
+        let query = bound_query(&self.query, node).map_err(EvaluationError::from)?;
+        match store.query(query)? {
+            QueryResults::Boolean(value) => Ok(value),
+            _ => Err(format!("query of custom metric '{}' is not ASK", self.metric).into()),
+        }
+    }
+}
+
+/// Reads custom metrics from a JSON file.
+pub fn load_custom_metrics(path: &str) -> Result<Vec<CustomMetric>, Error> {
+    parse_custom_metrics(&fs::read_to_string(path)?)
+}
+
+/// Parses a JSON array of custom metrics, objects with a `metric` IRI, a
+/// `target` of `dataset` or `distribution`, and an ASK `query`. Errors name
+/// the line of the offending entry.
+pub fn parse_custom_metrics(json: &str) -> Result<Vec<CustomMetric>, Error> {
+    let entries: Vec<CustomMetricEntry> =
+        serde_json::from_str(json).map_err(|e| Error::InvalidCustomMetric {
+            line: e.line(),
+            message: e.to_string(),
+        })?;
+
+    // Entries are located by their `metric` keys, in order.
+    let lines = json
+        .match_indices("\"metric\"")
+        .map(|(offset, _)| json[..offset].matches('\n').count() + 1)
+        .chain(std::iter::repeat(1));
+
+    let validation_store = Store::new()?;
+    entries
+        .into_iter()
+        .zip(lines)
+        .map(|(entry, line)| {
+            let invalid = |message: String| Error::InvalidCustomMetric { line, message };
+
+            let metric = NamedNode::new(&entry.metric).map_err(|e| invalid(e.to_string()))?;
+            let query = bound_query(&entry.query, metric.as_ref())
+                .map_err(|e| invalid(format!("invalid query: {}", e)))?;
+            match validation_store.query(query) {
+                Ok(QueryResults::Boolean(_)) => Ok(CustomMetric {
+                    metric,
+                    target: entry.target,
+                    query: entry.query,
+                }),
+                Ok(_) => Err(invalid("query is not an ASK query".to_string())),
+                Err(e) => Err(invalid(format!("invalid query: {}", e))),
+            }
+        })
+        .collect()
+}
+
+/// Query with `?node` bound to a node by a trailing VALUES clause.
+fn bound_query(query: &str, node: NamedNodeRef) -> Result<Query, SparqlSyntaxError> {
+    Query::parse(&format!("{}\nVALUES ?node {{ {} }}", query, node), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rdf::parse_turtle;
+
+    const LANGUAGE_METRICS: &str = r#"[
+    {
+        "metric": "https://metrics.foo/languageAvailability",
+        "target": "dataset",
+        "query": "PREFIX dct: <http://purl.org/dc/terms/> ASK { ?node dct:language ?language }"
+    }
+]"#;
+
+    fn store(turtle: &str) -> Store {
+        let store = Store::new().unwrap();
+        parse_turtle(&store, turtle.to_string()).unwrap();
+        store
+    }
+
+    #[test]
+    fn test_language_availability() {
+        let metrics = parse_custom_metrics(LANGUAGE_METRICS).unwrap();
+        assert_eq!(1, metrics.len());
+        assert_eq!(Target::Dataset, metrics[0].target);
+
+        let store = store(
+            r#"
+            @prefix dct: <http://purl.org/dc/terms/> .
+            <https://dataset.foo> dct:language <http://publications.europa.eu/resource/authority/language/NOB> .
+            <https://dataset.bar> dct:title "Bar" .
+            "#,
+        );
+        let evaluate = |node| {
+            metrics[0]
+                .evaluate(NamedNodeRef::new_unchecked(node), &store)
+                .unwrap()
+        };
+        assert!(evaluate("https://dataset.foo"));
+        assert!(!evaluate("https://dataset.bar"));
+    }
+
+    #[test]
+    fn test_invalid_query_rejected_with_line() {
+        let json = format!(
+            "{},\n{}]",
+            LANGUAGE_METRICS.trim_end_matches(']').trim_end(),
+            r#"    {
+        "metric": "https://metrics.foo/broken",
+        "target": "distribution",
+        "query": "ASK { ?node ?p }"
+    }"#
+        );
+
+        match parse_custom_metrics(&json) {
+            Err(Error::InvalidCustomMetric { line, message }) => {
+                assert_eq!(8, line);
+                assert!(message.starts_with("invalid query"));
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_non_ask_query_rejected() {
+        let json = r#"[{"metric": "https://metrics.foo/select", "target": "dataset",
+            "query": "SELECT * WHERE { ?node ?p ?o }"}]"#;
+
+        assert!(matches!(
+            parse_custom_metrics(json),
+            Err(Error::InvalidCustomMetric { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_target_rejected_with_line() {
+        let json = LANGUAGE_METRICS.replace("\"dataset\"", "\"catalog\"");
+
+        assert!(matches!(
+            parse_custom_metrics(&json),
+            Err(Error::InvalidCustomMetric { line: 4, .. })
+        ));
+    }
+}
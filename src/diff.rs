@@ -0,0 +1,365 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use oxigraph::{
+    io::RdfFormat,
+    model::{vocab::rdf, Quad, Subject, Term},
+    store::Store,
+};
+
+use crate::{
+    error::Error,
+    rdf::boolean_literal_value,
+    vocab::{dcat_mqa, dqv},
+};
+
+/// A measurement that differs between two MQA graphs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeasurementChange {
+    pub metric: String,
+    pub computed_on: String,
+    pub old_value: Option<bool>,
+    pub new_value: Option<bool>,
+}
+
+/// Measurements added, removed and changed between two MQA graphs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AssessmentDiff {
+    pub added: Vec<MeasurementChange>,
+    pub removed: Vec<MeasurementChange>,
+    pub changed: Vec<MeasurementChange>,
+}
+
+impl AssessmentDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compare two MQA graphs. Measurements are matched by the metric they measure
+/// and the node they are computed on, since measurement nodes are blank nodes.
+/// Graphs are Turtle or TriG.
+pub fn compare_assessments(previous_ttl: &str, current_ttl: &str) -> Result<AssessmentDiff, Error> {
+    let previous = measurements(previous_ttl)?;
+    let current = measurements(current_ttl)?;
+
+    let mut diff = AssessmentDiff::default();
+    for ((metric, computed_on), old_value) in previous.iter() {
+        match current.get(&(metric.clone(), computed_on.clone())) {
+            Some(new_value) if new_value != old_value => diff.changed.push(MeasurementChange {
+                metric: metric.clone(),
+                computed_on: computed_on.clone(),
+                old_value: Some(*old_value),
+                new_value: Some(*new_value),
+            }),
+            Some(_) => (),
+            None => diff.removed.push(MeasurementChange {
+                metric: metric.clone(),
+                computed_on: computed_on.clone(),
+                old_value: Some(*old_value),
+                new_value: None,
+            }),
+        }
+    }
+    for ((metric, computed_on), new_value) in current.iter() {
+        if !previous.contains_key(&(metric.clone(), computed_on.clone())) {
+            diff.added.push(MeasurementChange {
+                metric: metric.clone(),
+                computed_on: computed_on.clone(),
+                old_value: None,
+                new_value: Some(*new_value),
+            });
+        }
+    }
+
+    Ok(diff)
+}
+
+/// IRIs of the dataset and distribution assessments of a previous MQA graph
+/// that are not in the current MQA graphs, so that consumers keeping
+/// assessments can remove them. Graphs are Turtle or TriG.
+pub fn removed_assessments(previous: &str, current: &[&str]) -> Result<Vec<String>, Error> {
+    let mut removed = assessments(previous)?;
+    for graph in current {
+        for assessment in assessments(graph)? {
+            removed.remove(&assessment);
+        }
+    }
+    Ok(removed.into_iter().collect())
+}
+
+/// IRIs of the dataset and distribution assessments of an MQA graph.
+fn assessments(graph: &str) -> Result<BTreeSet<String>, Error> {
+    let store = Store::new()?;
+    store.load_from_reader(RdfFormat::TriG, graph.as_bytes())?;
+
+    let mut assessments = BTreeSet::new();
+    for class in [
+        dcat_mqa::DATASET_ASSESSMENT_CLASS,
+        dcat_mqa::DISTRIBUTION_ASSESSMENT_CLASS,
+    ] {
+        for quad in store.quads_for_pattern(None, Some(rdf::TYPE), Some(class.into()), None) {
+            if let Subject::NamedNode(assessment) = quad?.subject {
+                assessments.insert(assessment.into_string());
+            }
+        }
+    }
+    Ok(assessments)
+}
+
+/// Parse an MQA graph and collect its measurement values keyed by metric and computed-on node.
+fn measurements(graph: &str) -> Result<BTreeMap<(String, String), bool>, Error> {
+    let store = Store::new()?;
+    store.load_from_reader(RdfFormat::TriG, graph.as_bytes())?;
+
+    let mut measurements = BTreeMap::new();
+    for quad in store.quads_for_pattern(
+        None,
+        Some(rdf::TYPE),
+        Some(dqv::QUALITY_MEASUREMENT_CLASS.into()),
+        None,
+    ) {
+        let measurement = quad?.subject;
+        let metric = object_of(&store, &measurement, dqv::IS_MEASUREMENT_OF)?;
+        let computed_on = object_of(&store, &measurement, dqv::COMPUTED_ON)?;
+        let value = object_of(&store, &measurement, dqv::VALUE)?
+            .as_ref()
+            .and_then(boolean_literal_value);
+
+        match (metric, computed_on, value) {
+            (Some(Term::NamedNode(metric)), Some(Term::NamedNode(computed_on)), Some(value)) => {
+                measurements.insert((metric.into_string(), computed_on.into_string()), value);
+            }
+            _ => tracing::warn!(
+                measurement = measurement.to_string(),
+                "skipping incomplete measurement"
+            ),
+        }
+    }
+
+    Ok(measurements)
+}
+
+fn object_of(
+    store: &Store,
+    subject: &Subject,
+    predicate: oxigraph::model::NamedNodeRef,
+) -> Result<Option<Term>, Error> {
+    Ok(store
+        .quads_for_pattern(Some(subject.as_ref()), Some(predicate), None, None)
+        .next()
+        .transpose()?
+        .map(|Quad { object, .. }| object))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measurement(assessment: &str, node: &str, metric: &str, value: bool) -> String {
+        format!(
+            r#"
+            <{assessment}> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> [
+                a <http://www.w3.org/ns/dqv#QualityMeasurement> ;
+                <http://www.w3.org/ns/dqv#isMeasurementOf> <https://data.norge.no/vocabulary/dcatno-mqa#{metric}> ;
+                <http://www.w3.org/ns/dqv#computedOn> <{node}> ;
+                <http://www.w3.org/ns/dqv#value> "{value}"^^<http://www.w3.org/2001/XMLSchema#boolean>
+            ] .
+            "#
+        )
+    }
+
+    #[test]
+    fn test_unchanged_assessment() {
+        let graph = measurement(
+            "https://a.foo",
+            "https://dataset.foo",
+            "keywordAvailability",
+            true,
+        );
+        assert!(compare_assessments(&graph, &graph).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_normalized_boolean_values_unchanged() {
+        let graph = measurement(
+            "https://a.foo",
+            "https://dataset.foo",
+            "keywordAvailability",
+            true,
+        );
+        for normalized in [
+            r#""1"^^<http://www.w3.org/2001/XMLSchema#boolean>"#,
+            r#""true""#,
+        ] {
+            let other = graph.replace(
+                r#""true"^^<http://www.w3.org/2001/XMLSchema#boolean>"#,
+                normalized,
+            );
+            assert!(compare_assessments(&graph, &other).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_changed_measurement() {
+        let previous = [
+            measurement(
+                "https://a.foo",
+                "https://dataset.foo",
+                "keywordAvailability",
+                false,
+            ),
+            measurement(
+                "https://a.foo",
+                "https://dataset.foo",
+                "spatialAvailability",
+                true,
+            ),
+        ]
+        .concat();
+        let current = [
+            measurement(
+                "https://a.foo",
+                "https://dataset.foo",
+                "keywordAvailability",
+                true,
+            ),
+            measurement(
+                "https://a.foo",
+                "https://dataset.foo",
+                "spatialAvailability",
+                true,
+            ),
+        ]
+        .concat();
+
+        let diff = compare_assessments(&previous, &current).unwrap();
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        assert_eq!(
+            diff.changed,
+            vec![MeasurementChange {
+                metric: "https://data.norge.no/vocabulary/dcatno-mqa#keywordAvailability"
+                    .to_string(),
+                computed_on: "https://dataset.foo".to_string(),
+                old_value: Some(false),
+                new_value: Some(true),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_changed_measurement_in_named_graphs() {
+        let named_graph = |graph: String| format!("<https://a.foo> {{ {} }}", graph);
+        let previous = named_graph(measurement(
+            "https://a.foo",
+            "https://dataset.foo",
+            "keywordAvailability",
+            false,
+        ));
+        let current = named_graph(measurement(
+            "https://a.foo",
+            "https://dataset.foo",
+            "keywordAvailability",
+            true,
+        ));
+
+        let diff = compare_assessments(&previous, &current).unwrap();
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        assert_eq!(1, diff.changed.len());
+        assert!(compare_assessments(&current, &current).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_removed_distribution() {
+        let dataset = measurement(
+            "https://a.foo",
+            "https://dataset.foo",
+            "keywordAvailability",
+            true,
+        );
+        let previous = [
+            dataset.clone(),
+            measurement(
+                "https://b.foo",
+                "https://distribution.foo",
+                "formatAvailability",
+                true,
+            ),
+            measurement(
+                "https://b.foo",
+                "https://distribution.foo",
+                "licenseAvailability",
+                false,
+            ),
+        ]
+        .concat();
+
+        let diff = compare_assessments(&previous, &dataset).unwrap();
+        assert!(diff.added.is_empty());
+        assert!(diff.changed.is_empty());
+        assert_eq!(2, diff.removed.len());
+        assert!(diff
+            .removed
+            .iter()
+            .all(|change| change.computed_on == "https://distribution.foo"
+                && change.new_value.is_none()));
+
+        let diff = compare_assessments(&dataset, &previous).unwrap();
+        assert_eq!(2, diff.added.len());
+        assert!(diff.removed.is_empty());
+    }
+
+    fn assessment(iri: &str, class: &str) -> String {
+        format!(
+            "<{}> a <https://data.norge.no/vocabulary/dcatno-mqa#{}> .\n",
+            iri, class
+        )
+    }
+
+    #[test]
+    fn test_removed_assessments_of_disappeared_distribution() {
+        let dataset = assessment("https://dataset.assessment.foo", "DatasetAssessment");
+        let kept = assessment(
+            "https://distribution.assessment.foo/1",
+            "DistributionAssessment",
+        );
+        let previous = [
+            dataset.clone(),
+            kept.clone(),
+            assessment(
+                "https://distribution.assessment.foo/2",
+                "DistributionAssessment",
+            ),
+        ]
+        .concat();
+        let current = [dataset, kept].concat();
+
+        assert_eq!(
+            vec!["https://distribution.assessment.foo/2".to_string()],
+            removed_assessments(&previous, &[&current]).unwrap()
+        );
+        assert!(removed_assessments(&current, &[&previous])
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_removed_assessments_across_graphs() {
+        let first = assessment("https://dataset.assessment.foo/1", "DatasetAssessment");
+        let second = format!(
+            "<https://dataset.assessment.foo/2> {{ {} }}",
+            assessment("https://dataset.assessment.foo/2", "DatasetAssessment")
+        );
+        let previous = [first.clone(), second.clone()].concat();
+
+        assert!(removed_assessments(&previous, &[&first, &second])
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            vec!["https://dataset.assessment.foo/2".to_string()],
+            removed_assessments(&previous, &[&first]).unwrap()
+        );
+        assert!(removed_assessments("not rdf", &[&first]).is_err());
+    }
+}
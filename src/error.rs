@@ -12,20 +12,95 @@ pub enum Error {
     LoaderError(#[from] LoaderError),
     #[error(transparent)]
     StorageError(#[from] StorageError),
+    #[error(
+        "storage failed writing{}{}: {source}",
+        .metric.as_ref().map(|metric| format!(" measurement of '{metric}'")).unwrap_or_default(),
+        .node.as_ref().map(|node| format!(" on '{node}'")).unwrap_or_default()
+    )]
+    Storage {
+        source: StorageError,
+        metric: Option<String>,
+        node: Option<String>,
+    },
     #[error(transparent)]
     IriParseError(#[from] IriParseError),
     #[error(transparent)]
     SerializerError(#[from] SerializerError),
     #[error(transparent)]
     KafkaError(#[from] rdkafka::error::KafkaError),
+    #[error("delivery failed: {0}")]
+    DeliveryFailed(rdkafka::error::KafkaError),
     #[error(transparent)]
     AvroError(#[from] apache_avro::Error),
     #[error(transparent)]
     SRCError(#[from] schema_registry_converter::error::SRCError),
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
+    #[error(transparent)]
+    SparqlError(#[from] oxigraph::sparql::EvaluationError),
+    #[error("invalid custom metric at line {line}: {message}")]
+    InvalidCustomMetric { line: usize, message: String },
+    #[error("graph is empty")]
+    EmptyGraph,
+    #[error("dataset node not found in graph")]
+    DatasetNodeNotFound,
+    #[error("assessment not found for node '{node}'")]
+    AssessmentNotFound { node: String },
+    #[error("assessment of node '{node}' is not a named node: '{object}'")]
+    InvalidAssessmentNode { node: String, object: String },
+    #[error("assessment of node '{node}' is not an absolute http(s) IRI: '{iri}'")]
+    InvalidAssessmentIri { node: String, iri: String },
+    #[error("unexpected subject '{subject}' in output graph")]
+    UnexpectedOutputSubject { subject: String },
+    #[error("graph of {bytes} bytes is too large")]
+    GraphTooLarge { bytes: usize },
+    #[error("{store} store is not empty, holding {quads} quads")]
+    StoreNotEmpty { store: &'static str, quads: usize },
+    #[error(
+        "invalid Turtle{}: {details}",
+        .fdk_id.as_ref().map(|id| format!(" in event '{id}'")).unwrap_or_default()
+    )]
+    InvalidTurtle {
+        fdk_id: Option<String>,
+        details: String,
+    },
+    #[error("post-processor '{processor}' failed: {message}")]
+    PostProcessorFailed { processor: String, message: String },
     #[error("{0}")]
     String(String),
 }
 
+impl Error {
+    /// Short, stable identifier of the error class, used as metric label.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::IoError(_) => "io",
+            Error::LoaderError(_) => "loader",
+            Error::StorageError(_) | Error::Storage { .. } => "storage",
+            Error::IriParseError(_) => "iri_parse",
+            Error::SerializerError(_) => "serializer",
+            Error::KafkaError(_) => "kafka",
+            Error::DeliveryFailed(_) => "delivery_failed",
+            Error::AvroError(_) => "avro",
+            Error::SRCError(_) => "schema_registry",
+            Error::JsonError(_) => "json",
+            Error::SparqlError(_) => "sparql",
+            Error::InvalidCustomMetric { .. } => "invalid_custom_metric",
+            Error::EmptyGraph => "empty_graph",
+            Error::DatasetNodeNotFound => "dataset_node_not_found",
+            Error::AssessmentNotFound { .. } => "assessment_not_found",
+            Error::InvalidAssessmentNode { .. } => "invalid_assessment_node",
+            Error::InvalidAssessmentIri { .. } => "invalid_assessment_iri",
+            Error::UnexpectedOutputSubject { .. } => "unexpected_output_subject",
+            Error::GraphTooLarge { .. } => "graph_too_large",
+            Error::StoreNotEmpty { .. } => "store_not_empty",
+            Error::InvalidTurtle { .. } => "invalid_turtle",
+            Error::PostProcessorFailed { .. } => "post_processor_failed",
+            Error::String(_) => "other",
+        }
+    }
+}
+
 impl From<&str> for Error {
     fn from(e: &str) -> Self {
         Self::String(e.to_string())
@@ -37,3 +112,143 @@ impl From<String> for Error {
         Self::String(e)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_codes() {
+        assert_eq!("empty_graph", Error::EmptyGraph.code());
+        assert_eq!("dataset_node_not_found", Error::DatasetNodeNotFound.code());
+        assert_eq!(
+            "assessment_not_found",
+            Error::AssessmentNotFound {
+                node: "https://dataset.foo".to_string()
+            }
+            .code()
+        );
+        assert_eq!(
+            "invalid_assessment_node",
+            Error::InvalidAssessmentNode {
+                node: "https://dataset.foo".to_string(),
+                object: "\"foo\"".to_string()
+            }
+            .code()
+        );
+        assert_eq!(
+            "invalid_assessment_iri",
+            Error::InvalidAssessmentIri {
+                node: "https://dataset.foo".to_string(),
+                iri: "urn:uuid:foo".to_string()
+            }
+            .code()
+        );
+        assert_eq!(
+            "unexpected_output_subject",
+            Error::UnexpectedOutputSubject {
+                subject: "<https://dataset.foo>".to_string()
+            }
+            .code()
+        );
+        assert_eq!("graph_too_large", Error::GraphTooLarge { bytes: 1 }.code());
+        assert_eq!(
+            "store_not_empty",
+            Error::StoreNotEmpty {
+                store: "output",
+                quads: 1
+            }
+            .code()
+        );
+        assert_eq!(
+            "invalid_turtle",
+            Error::InvalidTurtle {
+                fdk_id: None,
+                details: "foo".to_string()
+            }
+            .code()
+        );
+        assert_eq!(
+            "post_processor_failed",
+            Error::PostProcessorFailed {
+                processor: "foo".to_string(),
+                message: "bar".to_string()
+            }
+            .code()
+        );
+        assert_eq!(
+            "invalid_custom_metric",
+            Error::InvalidCustomMetric {
+                line: 1,
+                message: "foo".to_string()
+            }
+            .code()
+        );
+        assert_eq!(
+            "storage",
+            Error::Storage {
+                source: StorageError::Io(std::io::ErrorKind::Other.into()),
+                metric: None,
+                node: None
+            }
+            .code()
+        );
+        assert_eq!(
+            "delivery_failed",
+            Error::DeliveryFailed(rdkafka::error::KafkaError::Canceled).code()
+        );
+        assert_eq!("other", Error::from("foo").code());
+    }
+
+    #[test]
+    fn test_error_display() {
+        assert_eq!(
+            "dataset node not found in graph",
+            Error::DatasetNodeNotFound.to_string()
+        );
+        assert_eq!(
+            "assessment not found for node 'https://dataset.foo'",
+            Error::AssessmentNotFound {
+                node: "https://dataset.foo".to_string()
+            }
+            .to_string()
+        );
+        assert_eq!(
+            "assessment of node 'https://dataset.foo' is not a named node: '\"foo\"'",
+            Error::InvalidAssessmentNode {
+                node: "https://dataset.foo".to_string(),
+                object: "\"foo\"".to_string()
+            }
+            .to_string()
+        );
+        assert_eq!(
+            "graph of 1024 bytes is too large",
+            Error::GraphTooLarge { bytes: 1024 }.to_string()
+        );
+        assert_eq!(
+            "invalid Turtle in event 'fdk-id': foo",
+            Error::InvalidTurtle {
+                fdk_id: Some("fdk-id".to_string()),
+                details: "foo".to_string()
+            }
+            .to_string()
+        );
+        assert_eq!(
+            "storage failed writing measurement of 'https://metric.foo' on 'https://dataset.foo': disk full",
+            Error::Storage {
+                source: StorageError::Io(std::io::Error::other("disk full")),
+                metric: Some("https://metric.foo".to_string()),
+                node: Some("https://dataset.foo".to_string())
+            }
+            .to_string()
+        );
+        assert_eq!(
+            "invalid Turtle: foo",
+            Error::InvalidTurtle {
+                fdk_id: None,
+                details: "foo".to_string()
+            }
+            .to_string()
+        );
+    }
+}
@@ -0,0 +1,523 @@
+use std::collections::HashSet;
+
+use lazy_static::lazy_static;
+
+/// RDF serializations, by EU file type code, IANA media type and short name.
+const RDF_FORMATS: &[&str] = &[
+    // EU file types
+    "rdf",
+    "rdf_xml",
+    "rdf_turtle",
+    "rdf_n_triples",
+    "rdf_n_quads",
+    "rdf_trig",
+    "rdfa",
+    "json_ld",
+    "n3",
+    // IANA media types
+    "application/rdf+xml",
+    "text/turtle",
+    "application/n-triples",
+    "application/n-quads",
+    "application/trig",
+    "application/ld+json",
+    "text/n3",
+    // Short names and media type subtypes
+    "turtle",
+    "ntriples",
+    "n_triples",
+    "nq",
+    "n_quads",
+    "trig",
+    "json-ld",
+    "jsonld",
+    "rdf+xml",
+    "ld+json",
+    "n-triples",
+    "n-quads",
+];
+
+/// Formats that can be processed by machines without human interpretation,
+/// after the machine-readable formats of the EU MQA, the RDF formats
+/// included.
+const MACHINE_INTERPRETABLE_FORMATS: &[&str] = &[
+    // EU file types
+    "rdf",
+    "rdf_xml",
+    "rdf_turtle",
+    "rdf_n_triples",
+    "rdf_n_quads",
+    "rdf_trig",
+    "rdfa",
+    "json_ld",
+    "n3",
+    "csv",
+    "tsv",
+    "json",
+    "xml",
+    "geojson",
+    "gml",
+    "kml",
+    "kmz",
+    "gpkg",
+    "shp",
+    "xls",
+    "xlsx",
+    "ods",
+    "dbf",
+    "mdb",
+    "netcdf",
+    "hdf",
+    "grib",
+    "atom",
+    "rss",
+    "yaml",
+    "parquet",
+    "sparqlq",
+    "sparqlqres",
+    "wms_srvc",
+    "wfs_srvc",
+    "wcs_srvc",
+    "owl",
+    "skos_xml",
+    "xslt",
+    "ics",
+    "gmz",
+    "las",
+    "laz",
+    "grid_ascii",
+    // IANA media types
+    "application/rdf+xml",
+    "text/turtle",
+    "application/n-triples",
+    "application/n-quads",
+    "application/trig",
+    "application/ld+json",
+    "text/n3",
+    "text/csv",
+    "text/tab-separated-values",
+    "application/json",
+    "application/xml",
+    "text/xml",
+    "application/geo+json",
+    "application/gml+xml",
+    "application/vnd.google-earth.kml+xml",
+    "application/vnd.google-earth.kmz",
+    "application/geopackage+sqlite3",
+    "application/owl+xml",
+    "application/xslt+xml",
+    "text/calendar",
+    "application/vnd.ms-excel",
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    "application/vnd.oasis.opendocument.spreadsheet",
+    "application/vnd.ms-access",
+    "application/x-netcdf",
+    "application/atom+xml",
+    "application/rss+xml",
+    "application/yaml",
+    "application/vnd.apache.parquet",
+    "application/sparql-query",
+    "application/sparql-results+json",
+    "application/sparql-results+xml",
+    // Short names and media type subtypes
+    "turtle",
+    "ntriples",
+    "n_triples",
+    "nq",
+    "n_quads",
+    "trig",
+    "json-ld",
+    "jsonld",
+    "rdf+xml",
+    "ld+json",
+    "n-triples",
+    "n-quads",
+];
+
+/// Formats with openly published specifications that anyone may implement,
+/// after the non-proprietary formats of the EU MQA, the RDF formats included.
+const NON_PROPRIETARY_FORMATS: &[&str] = &[
+    // EU file types
+    "rdf",
+    "rdf_xml",
+    "rdf_turtle",
+    "rdf_n_triples",
+    "rdf_n_quads",
+    "rdf_trig",
+    "rdfa",
+    "json_ld",
+    "n3",
+    "csv",
+    "tsv",
+    "json",
+    "xml",
+    "geojson",
+    "gml",
+    "kml",
+    "kmz",
+    "gpkg",
+    "ods",
+    "odt",
+    "netcdf",
+    "hdf",
+    "grib",
+    "atom",
+    "rss",
+    "yaml",
+    "parquet",
+    "sparqlq",
+    "sparqlqres",
+    "wms_srvc",
+    "wfs_srvc",
+    "wcs_srvc",
+    "txt",
+    "html",
+    "xhtml",
+    "pdf",
+    "epub",
+    "png",
+    "svg",
+    "tiff",
+    "jpeg2000",
+    "zip",
+    "gzip",
+    "tar",
+    "7z",
+    "bzip2",
+    "owl",
+    "skos_xml",
+    "xslt",
+    "ics",
+    "gmz",
+    "las",
+    "laz",
+    "grid_ascii",
+    "odp",
+    "odg",
+    "odf",
+    "odc",
+    "odb",
+    "rtf",
+    "ps",
+    "sgml",
+    "warc",
+    "pdfa1a",
+    "pdfa1b",
+    "pdfa2a",
+    "pdfa2b",
+    "pdfa3",
+    "pdfx",
+    "gif",
+    "jpeg",
+    // IANA media types
+    "application/rdf+xml",
+    "text/turtle",
+    "application/n-triples",
+    "application/n-quads",
+    "application/trig",
+    "application/ld+json",
+    "text/n3",
+    "text/csv",
+    "text/tab-separated-values",
+    "application/json",
+    "application/xml",
+    "text/xml",
+    "application/geo+json",
+    "application/gml+xml",
+    "application/vnd.google-earth.kml+xml",
+    "application/vnd.google-earth.kmz",
+    "application/geopackage+sqlite3",
+    "application/vnd.oasis.opendocument.spreadsheet",
+    "application/vnd.oasis.opendocument.text",
+    "application/x-netcdf",
+    "application/atom+xml",
+    "application/rss+xml",
+    "application/yaml",
+    "application/vnd.apache.parquet",
+    "application/sparql-query",
+    "application/sparql-results+json",
+    "application/sparql-results+xml",
+    "text/plain",
+    "text/html",
+    "application/xhtml+xml",
+    "application/pdf",
+    "application/epub+zip",
+    "image/png",
+    "image/svg+xml",
+    "image/tiff",
+    "image/jp2",
+    "application/zip",
+    "application/gzip",
+    "application/x-tar",
+    "application/x-7z-compressed",
+    "application/x-bzip2",
+    "application/owl+xml",
+    "application/xslt+xml",
+    "text/calendar",
+    "application/vnd.oasis.opendocument.presentation",
+    "application/vnd.oasis.opendocument.graphics",
+    "application/vnd.oasis.opendocument.formula",
+    "application/vnd.oasis.opendocument.chart",
+    "application/vnd.oasis.opendocument.database",
+    "application/rtf",
+    "text/rtf",
+    "application/postscript",
+    "text/sgml",
+    "application/warc",
+    "image/gif",
+    "image/jpeg",
+    // Short names and media type subtypes
+    "turtle",
+    "ntriples",
+    "n_triples",
+    "nq",
+    "n_quads",
+    "trig",
+    "json-ld",
+    "jsonld",
+    "rdf+xml",
+    "ld+json",
+    "n-triples",
+    "n-quads",
+];
+
+lazy_static! {
+    static ref RDF: HashSet<&'static str> = RDF_FORMATS.iter().copied().collect();
+    static ref MACHINE_INTERPRETABLE: HashSet<&'static str> =
+        MACHINE_INTERPRETABLE_FORMATS.iter().copied().collect();
+    static ref NON_PROPRIETARY: HashSet<&'static str> =
+        NON_PROPRIETARY_FORMATS.iter().copied().collect();
+}
+
+/// Classes of a format, as used by the five-star rating of distributions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FormatClasses {
+    pub rdf: bool,
+    pub machine_interpretable: bool,
+    pub non_proprietary: bool,
+}
+
+/// Classes of a format given as an EU file type URI, an IANA media type URI,
+/// a media type or a short name. Media types not known as such are looked up
+/// by their subtype, like `rdf+xml`.
+pub fn classify_format(format: &str) -> FormatClasses {
+    let token = format_token(format);
+    let subtype = token.split_once('/').map(|(_, subtype)| subtype);
+    let contains = |set: &HashSet<&str>| {
+        set.contains(token.as_str()) || subtype.is_some_and(|subtype| set.contains(subtype))
+    };
+
+    FormatClasses {
+        rdf: contains(&*RDF),
+        machine_interpretable: contains(&*MACHINE_INTERPRETABLE),
+        non_proprietary: contains(&*NON_PROPRIETARY),
+    }
+}
+
+/// Lowercase key of a format in the lookup sets: the media type of IANA media
+/// type URIs and media types, without parameters like `; charset=utf-8`, and
+/// the last path segment of other URIs, like the code of EU file type URIs.
+fn format_token(format: &str) -> String {
+    let format = format.split(';').next().unwrap_or_default();
+    let format = format.trim().trim_end_matches('/').to_lowercase();
+    if let Some((_, media_type)) = format.split_once("/media-types/") {
+        return media_type.to_string();
+    }
+    if !format.contains(':') && format.matches('/').count() == 1 {
+        return format;
+    }
+    format.rsplit('/').next().unwrap_or_default().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EU_FILE_TYPES: &str = "http://publications.europa.eu/resource/authority/file-type/";
+    const IANA_MEDIA_TYPES: &str = "https://www.iana.org/assignments/media-types/";
+
+    fn classes(rdf: bool, machine_interpretable: bool, non_proprietary: bool) -> FormatClasses {
+        FormatClasses {
+            rdf,
+            machine_interpretable,
+            non_proprietary,
+        }
+    }
+
+    #[test]
+    fn test_classify_format() {
+        let rdf = classes(true, true, true);
+        let open = classes(false, true, true);
+        let proprietary = classes(false, true, false);
+        let document = classes(false, false, true);
+        let none = classes(false, false, false);
+
+        for (format, expected) in [
+            ("RDF_XML", rdf),
+            ("RDF_TURTLE", rdf),
+            ("RDF_N_TRIPLES", rdf),
+            ("RDF_N_QUADS", rdf),
+            ("RDF_TRIG", rdf),
+            ("JSON_LD", rdf),
+            ("N3", rdf),
+            ("RDFA", rdf),
+            ("CSV", open),
+            ("TSV", open),
+            ("JSON", open),
+            ("XML", open),
+            ("GEOJSON", open),
+            ("GML", open),
+            ("KML", open),
+            ("GPKG", open),
+            ("ODS", open),
+            ("NETCDF", open),
+            ("PARQUET", open),
+            ("WMS_SRVC", open),
+            ("OWL", open),
+            ("SKOS_XML", open),
+            ("ICS", open),
+            ("LAS", open),
+            ("GRID_ASCII", open),
+            ("XLS", proprietary),
+            ("XLSX", proprietary),
+            ("SHP", proprietary),
+            ("MDB", proprietary),
+            ("PDF", document),
+            ("HTML", document),
+            ("TXT", document),
+            ("ZIP", document),
+            ("PNG", document),
+            ("JPEG", document),
+            ("ODP", document),
+            ("RTF", document),
+            ("PDFA1B", document),
+            ("WARC", document),
+            ("DOCX", none),
+            ("EXE", none),
+        ] {
+            assert_eq!(
+                expected,
+                classify_format(&format!("{}{}", EU_FILE_TYPES, format)),
+                "{}",
+                format
+            );
+        }
+
+        for (media_type, expected) in [
+            ("text/turtle", rdf),
+            ("application/rdf+xml", rdf),
+            ("application/ld+json", rdf),
+            ("text/csv", open),
+            ("application/json", open),
+            ("application/geo+json", open),
+            ("application/vnd.oasis.opendocument.spreadsheet", open),
+            ("application/vnd.ms-excel", proprietary),
+            (
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+                proprietary,
+            ),
+            ("application/pdf", document),
+            ("text/plain", document),
+            ("text/calendar", open),
+            ("application/owl+xml", open),
+            ("application/vnd.oasis.opendocument.presentation", document),
+            ("image/jpeg", document),
+            ("application/msword", none),
+        ] {
+            assert_eq!(expected, classify_format(media_type), "{}", media_type);
+            assert_eq!(
+                expected,
+                classify_format(&format!("{}{}", IANA_MEDIA_TYPES, media_type)),
+                "{}",
+                media_type
+            );
+        }
+    }
+
+    #[test]
+    fn test_media_type_parameters_ignored() {
+        assert_eq!(
+            classes(false, true, true),
+            classify_format("text/csv; charset=utf-8")
+        );
+        assert_eq!(
+            classes(true, true, true),
+            classify_format(
+                "application/ld+json;profile=\"http://www.w3.org/ns/json-ld#expanded\""
+            )
+        );
+        assert_eq!("text/csv", format_token("Text/CSV ; charset=UTF-8"));
+    }
+
+    #[test]
+    fn test_rdf_formats_are_machine_interpretable_and_non_proprietary() {
+        for format in RDF_FORMATS {
+            assert!(
+                MACHINE_INTERPRETABLE.contains(format),
+                "{} should be machine-interpretable",
+                format
+            );
+            assert!(
+                NON_PROPRIETARY.contains(format),
+                "{} should be non-proprietary",
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_tokens_normalized() {
+        for format in RDF_FORMATS
+            .iter()
+            .chain(MACHINE_INTERPRETABLE_FORMATS)
+            .chain(NON_PROPRIETARY_FORMATS)
+        {
+            assert_eq!(*format, format_token(format), "{}", format);
+        }
+    }
+
+    #[test]
+    fn test_is_rdf_format() {
+        for format in [
+            "turtle",
+            "JSON-LD",
+            "jsonld",
+            "n3",
+            "text/turtle",
+            "application/rdf+xml",
+            "application/ld+json",
+            "application/n-triples",
+            "application/n-quads",
+            "application/trig",
+            "http://publications.europa.eu/resource/authority/file-type/RDF_XML",
+            "http://publications.europa.eu/resource/authority/file-type/RDF_TURTLE",
+            "http://publications.europa.eu/resource/authority/file-type/RDF_N_TRIPLES",
+            "http://publications.europa.eu/resource/authority/file-type/RDF_N_QUADS",
+            "http://publications.europa.eu/resource/authority/file-type/JSON_LD",
+            "http://publications.europa.eu/resource/authority/file-type/N3",
+            "https://www.iana.org/assignments/media-types/text/turtle",
+            "https://www.iana.org/assignments/media-types/application/rdf+xml",
+            "https://www.iana.org/assignments/media-types/application/ld+json",
+            "https://www.iana.org/assignments/media-types/application/n-triples/",
+        ] {
+            assert!(classify_format(format).rdf, "{} should be RDF", format);
+        }
+    }
+
+    #[test]
+    fn test_is_not_rdf_format() {
+        for format in [
+            "",
+            "csv",
+            "text/csv",
+            "application/json",
+            "application/xml",
+            "http://publications.europa.eu/resource/authority/file-type/CSV",
+            "http://publications.europa.eu/resource/authority/file-type/JSON",
+            "https://www.iana.org/assignments/media-types/application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            "https://www.iana.org/assignments/media-types/text/turtle-schema",
+        ] {
+            assert!(!classify_format(format).rdf, "{} should not be RDF", format);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+const IANA_MEDIA_TYPES: &str = "https://www.iana.org/assignments/media-types/";
+
+/// Media types of common file extensions, relative to the IANA registry.
+const EXTENSIONS: [(&str, &str); 16] = [
+    ("csv", "text/csv"),
+    ("json", "application/json"),
+    ("geojson", "application/geo+json"),
+    ("jsonld", "application/ld+json"),
+    ("xml", "application/xml"),
+    ("rdf", "application/rdf+xml"),
+    ("ttl", "text/turtle"),
+    ("nt", "application/n-triples"),
+    ("nq", "application/n-quads"),
+    ("txt", "text/plain"),
+    ("html", "text/html"),
+    ("pdf", "application/pdf"),
+    ("zip", "application/zip"),
+    ("xls", "application/vnd.ms-excel"),
+    (
+        "xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ),
+    ("ods", "application/vnd.oasis.opendocument.spreadsheet"),
+];
+
+/// IANA media type IRI inferred from the file extension of the last path
+/// segment of a download URL.
+pub fn infer_media_type(download_url: &str) -> Option<String> {
+    let extension = file_extension(download_url)?;
+    EXTENSIONS
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(extension))
+        .map(|(_, media_type)| format!("{}{}", IANA_MEDIA_TYPES, media_type))
+}
+
+/// Extension of the last path segment of a URL, ignoring query and fragment.
+fn file_extension(url: &str) -> Option<&str> {
+    let url = url.split(['?', '#']).next()?;
+    let path = match url.split_once("://") {
+        Some((_, rest)) => &rest[rest.find('/')?..],
+        None => url,
+    };
+    let segment = path.rsplit('/').next()?;
+    match segment.rsplit_once('.') {
+        Some((name, extension)) if !name.is_empty() && !extension.is_empty() => Some(extension),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_infer_media_type() {
+        assert_eq!(
+            Some("https://www.iana.org/assignments/media-types/text/csv".to_string()),
+            infer_media_type("https://data.foo/files/data.csv")
+        );
+        assert_eq!(
+            Some("https://www.iana.org/assignments/media-types/application/vnd.openxmlformats-officedocument.spreadsheetml.sheet".to_string()),
+            infer_media_type("https://data.foo/files/Data.XLSX")
+        );
+    }
+
+    #[test]
+    fn test_infer_media_type_with_query_and_fragment() {
+        for url in [
+            "https://data.foo/files/data.json?version=2",
+            "https://data.foo/files/data.json#content",
+            "https://data.foo/files/data.json?version=2#content",
+            "https://data.foo/files/data.csv.json?format=csv",
+        ] {
+            assert_eq!(
+                Some("https://www.iana.org/assignments/media-types/application/json".to_string()),
+                infer_media_type(url),
+                "{}",
+                url
+            );
+        }
+    }
+
+    #[test]
+    fn test_no_media_type_without_known_extension() {
+        for url in [
+            "https://data.foo/files/data",
+            "https://data.foo/files/",
+            "https://data.foo",
+            "https://data.foo.csv",
+            "https://data.foo/files.csv/data",
+            "https://data.foo/files/data?format=data.csv",
+            "https://data.foo/files/.csv",
+            "https://data.foo/files/data.exe",
+        ] {
+            assert_eq!(None, infer_media_type(url), "{}", url);
+        }
+    }
+}
@@ -1,35 +1,71 @@
+pub mod context;
+pub mod headers;
+pub mod inputs;
+pub mod membership;
+pub mod pipeline;
+pub mod producer;
+pub mod rate_limit;
+pub mod router;
+pub mod seek;
+pub mod size_guard;
+pub mod topics;
+pub mod unchanged;
+
 use std::{
-    time::{Duration, Instant},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
     {env, format},
 };
 
 use apache_avro::schema::Name;
+use futures::{future::BoxFuture, FutureExt};
 use lazy_static::lazy_static;
-use oxigraph::store::Store;
-use rdkafka::{
-    config::{ClientConfig},
-    consumer::stream_consumer::StreamConsumer,
-    consumer::Consumer,
-    error::KafkaError,
-    message::BorrowedMessage,
-    producer::{FutureProducer, FutureRecord},
-    Message,
+use oxigraph::{
+    model::{NamedNode, NamedNodeRef, Term},
+    store::Store,
 };
+use rdkafka::{message::OwnedMessage, Message};
 use schema_registry_converter::{
     async_impl::{
         avro::{AvroDecoder, AvroEncoder},
-        schema_registry::SrSettings,
+        schema_registry::{get_schema_by_id, SrSettings},
     },
     avro_common::DecodeResult,
-    schema_registry_common::SubjectNameStrategy,
+    schema_registry_common::{get_bytes_result, BytesResult},
 };
 use tracing::{Instrument, Level};
 
 use crate::{
+    codec::{self, decode_utf8_lossy, strip_bom},
+    compliance::{default_rules, load_compliance_rules},
+    custom_metrics::load_custom_metrics,
+    diff::removed_assessments,
     error::Error,
-    metrics::parse_rdf_graph_and_calculate_metrics,
-    prometheus_metrics::{PROCESSED_MESSAGES, PROCESSING_TIME},
-    schemas::{DatasetEvent, DatasetEventType, InputEvent, MQAEventType, MqaEvent},
+    kafka::{
+        headers::{build_headers, header_value, source_context},
+        pipeline::{Handled, MessageHandler, OutputRecord},
+        rate_limit::{rate_limit_key, PUBLISHER_RATE_LIMITER},
+        router::{route_topics, OUTPUT_ROUTES},
+        size_guard::{fit_output_graph, MAX_OUTPUT_BYTES},
+        unchanged::{graph_hash, AssessedGraph, UnchangedGraphs, UNCHANGED_GRAPHS},
+    },
+    metrics::{
+        dataset_nodes, parse_rdf_graph_and_calculate_metrics,
+        parse_rdf_graph_and_calculate_metrics_per_dataset, validate_assessment_base_iri,
+        AssessmentOptions, GraphLayout, GraphStats, OutputValidation, ReportMode, METRICS_REVISION,
+    },
+    post_processing::SourceContext,
+    prometheus_metrics::{
+        INPUT_GRAPH_QUADS, OUTPUT_GRAPH_QUADS, SKIPPED_EVENTS, SKIPPED_UNCHANGED,
+    },
+    proxy::PROXY,
+    rdf::{canonical_hash, list_publishers},
+    reference_data::positive_var,
+    schemas::{
+        current_timestamp, DatasetEvent, DatasetEventType, InputEvent, MQAEventType, MqaEvent,
+        SubjectNaming,
+    },
 };
 
 lazy_static! {
@@ -40,6 +76,330 @@ lazy_static! {
         env::var("INPUT_TOPIC").unwrap_or("mqa-dataset-events".to_string());
     pub static ref OUTPUT_TOPIC: String =
         env::var("OUTPUT_TOPIC").unwrap_or("mqa-events".to_string());
+    pub static ref GROUP_ID: String =
+        env::var("KAFKA_GROUP_ID").unwrap_or("fdk-mqa-property-checker".to_string());
+    pub static ref NUM_WORKERS: usize = env::var("NUM_WORKERS")
+        .ok()
+        .map(|v| parse_num_workers(&v))
+        .transpose()
+        .unwrap_or_else(|e| {
+            tracing::error!(error = e.to_string(), "invalid number of workers");
+            std::process::exit(1);
+        })
+        .unwrap_or(4);
+    pub static ref DRY_RUN: bool = env::var("DRY_RUN").map(|v| v == "true").unwrap_or(false);
+    pub static ref SPLIT_CATALOGS: bool = env::var("SPLIT_CATALOGS")
+        .map(|v| v == "true")
+        .unwrap_or(false);
+    pub static ref EVENT_OPTIONS: EventOptions = EventOptions {
+        process_event_types: env::var("PROCESS_EVENT_TYPES")
+            .map(|v| {
+                parse_event_types(&v).unwrap_or_else(|e| {
+                    tracing::error!(error = e.to_string(), "invalid event types to process");
+                    std::process::exit(1);
+                })
+            })
+            .unwrap_or_else(|_| EventOptions::default().process_event_types),
+        timestamp_mode: env::var("TIMESTAMP_MODE")
+            .map(|v| {
+                v.parse().unwrap_or_else(|e: Error| {
+                    tracing::error!(error = e.to_string(), "invalid timestamp mode");
+                    std::process::exit(1);
+                })
+            })
+            .unwrap_or_default(),
+        unchanged_graphs: UNCHANGED_GRAPHS.clone(),
+        ..Default::default()
+    };
+    pub static ref INPUT_ENCODING: Encoding = encoding_from_env("INPUT_ENCODING");
+    pub static ref OUTPUT_ENCODING: Encoding = encoding_from_env("OUTPUT_ENCODING");
+    pub static ref SUBJECT_NAME_STRATEGY: SubjectNaming = env::var("SUBJECT_NAME_STRATEGY")
+        .map(|v| {
+            v.parse().unwrap_or_else(|e: Error| {
+                tracing::error!(error = e.to_string(), "invalid subject name strategy");
+                std::process::exit(1);
+            })
+        })
+        .unwrap_or_default();
+    pub static ref REGISTER_SCHEMAS: bool = env::var("REGISTER_SCHEMAS")
+        .map(|v| v != "false")
+        .unwrap_or(true);
+    pub static ref AUTO_OFFSET_RESET: &'static str = env::var("AUTO_OFFSET_RESET")
+        .map(|v| {
+            parse_auto_offset_reset(&v).unwrap_or_else(|e| {
+                tracing::error!(error = e.to_string(), "invalid auto offset reset");
+                std::process::exit(1);
+            })
+        })
+        .unwrap_or("latest");
+    pub static ref LAG_WARNING_THRESHOLD: i64 = env::var("LAG_WARNING_THRESHOLD")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10000);
+    pub static ref ASSESSMENT_OPTIONS: AssessmentOptions = AssessmentOptions {
+        emit_timing: env::var("EMIT_TIMING")
+            .map(|v| v == "true")
+            .unwrap_or(false),
+        assessment_base_iri: env::var("ASSESSMENT_BASE_IRI").ok().inspect(|iri| {
+            validate_assessment_base_iri(iri).unwrap_or_else(|e| {
+                tracing::error!(error = e.to_string(), "invalid assessment base IRI");
+                std::process::exit(1);
+            })
+        }),
+        validate_assessment_iris: env::var("VALIDATE_ASSESSMENT_IRIS")
+            .map(|v| v != "false")
+            .unwrap_or(true),
+        distribution_concurrency: env::var("DISTRIBUTION_CONCURRENCY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(8),
+        infer_format_from_download_url: env::var("INFER_FORMAT_FROM_DOWNLOAD_URL")
+            .map(|v| v == "true")
+            .unwrap_or(false),
+        publisher_iri_prefixes: env::var("PUBLISHER_IRI_PREFIXES")
+            .map(|v| v.split(',').map(|prefix| prefix.trim().to_string()).collect())
+            .unwrap_or_else(|_| AssessmentOptions::default().publisher_iri_prefixes),
+        measure_language_tags: env::var("MEASURE_LANGUAGE_TAGS")
+            .map(|v| v != "false")
+            .unwrap_or(true),
+        report_mode: env::var("REPORT_MODE")
+            .map(|v| {
+                parse_report_mode(&v, &env::var("REPORT_CORE_METRICS").unwrap_or_default())
+                    .unwrap_or_else(|e| {
+                        tracing::error!(error = e.to_string(), "invalid report mode");
+                        std::process::exit(1);
+                    })
+            })
+            .unwrap_or_default(),
+        output_validation: env::var("OUTPUT_VALIDATION")
+            .map(|v| {
+                parse_output_validation(&v).unwrap_or_else(|e| {
+                    tracing::error!(error = e.to_string(), "invalid output validation");
+                    std::process::exit(1);
+                })
+            })
+            .unwrap_or_default(),
+        graph_layout: env::var("OUTPUT_RDF_FORMAT")
+            .map(|v| {
+                parse_graph_layout(&v).unwrap_or_else(|e| {
+                    tracing::error!(error = e.to_string(), "invalid output RDF format");
+                    std::process::exit(1);
+                })
+            })
+            .unwrap_or_default(),
+        post_processors: Default::default(),
+        custom_metrics: env::var("CUSTOM_METRICS_FILE")
+            .map(|path| {
+                load_custom_metrics(&path).unwrap_or_else(|e| {
+                    tracing::error!(error = e.to_string(), "invalid custom metrics");
+                    std::process::exit(1);
+                })
+            })
+            .unwrap_or_default(),
+        compliance_rules: env::var("COMPLIANCE_RULES_FILE")
+            .map(|path| {
+                load_compliance_rules(&path).unwrap_or_else(|e| {
+                    tracing::error!(error = e.to_string(), "invalid compliance rules");
+                    std::process::exit(1);
+                })
+            })
+            .unwrap_or_else(|_| default_rules()),
+        require_iri_values: env::var("REQUIRE_IRI_VALUES")
+            .map(|v| v == "true")
+            .unwrap_or(false),
+        max_warnings_per_assessment: positive_var("MAX_WARNINGS_PER_ASSESSMENT", 50) as usize,
+        sort_distributions: true,
+        reference_data: None,
+        require_empty_stores: false,
+        default_base_iri: env::var("DEFAULT_BASE_IRI").ok().inspect(|iri| {
+            NamedNode::new(iri.as_str()).unwrap_or_else(|e| {
+                tracing::error!(error = e.to_string(), "invalid default base IRI");
+                std::process::exit(1);
+            });
+        }),
+    };
+}
+
+/// Serialization format of Kafka message payloads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Avro,
+    Json,
+}
+
+impl FromStr for Encoding {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "avro" => Ok(Encoding::Avro),
+            "json" => Ok(Encoding::Json),
+            _ => Err(format!("invalid encoding '{}', expected 'avro' or 'json'", s).into()),
+        }
+    }
+}
+
+fn encoding_from_env(name: &str) -> Encoding {
+    env::var(name)
+        .unwrap_or("avro".to_string())
+        .parse()
+        .unwrap_or_else(|e: Error| {
+            tracing::error!(error = e.to_string(), name, "invalid encoding");
+            std::process::exit(1);
+        })
+}
+
+/// Parse a number of workers, which must be a positive integer.
+fn parse_num_workers(value: &str) -> Result<usize, Error> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("number of workers must be greater than 0".into()),
+        Ok(num_workers) => Ok(num_workers),
+        Err(e) => Err(format!("invalid number of workers '{}': {}", value, e).into()),
+    }
+}
+
+/// Report mode `full`, `true-only` or `core`, the latter also reporting false
+/// measurements of the comma separated core metrics.
+fn parse_report_mode(mode: &str, core_metrics: &str) -> Result<ReportMode, Error> {
+    match mode {
+        "full" => Ok(ReportMode::Full),
+        "true-only" => Ok(ReportMode::TrueOnly),
+        "core" => Ok(ReportMode::Core(
+            core_metrics
+                .split(',')
+                .map(str::trim)
+                .filter(|metric| !metric.is_empty())
+                .map(NamedNode::new)
+                .collect::<Result<_, _>>()?,
+        )),
+        _ => Err(format!(
+            "invalid report mode '{}', expected 'full', 'true-only' or 'core'",
+            mode
+        )
+        .into()),
+    }
+}
+
+/// Output validation `fail`, `warn` or `off`.
+fn parse_output_validation(validation: &str) -> Result<OutputValidation, Error> {
+    match validation {
+        "fail" => Ok(OutputValidation::Fail),
+        "warn" => Ok(OutputValidation::Warn),
+        "off" => Ok(OutputValidation::Off),
+        _ => Err(format!(
+            "invalid output validation '{}', expected 'fail', 'warn' or 'off'",
+            validation
+        )
+        .into()),
+    }
+}
+
+/// Graph layout of the output RDF format, `turtle` for all assessments in
+/// the default graph or `trig` for each assessment in a named graph.
+fn parse_graph_layout(format: &str) -> Result<GraphLayout, Error> {
+    match format {
+        "turtle" => Ok(GraphLayout::Flat),
+        "trig" => Ok(GraphLayout::PerAssessmentNamedGraphs),
+        _ => Err(format!(
+            "invalid output RDF format '{}', expected 'turtle' or 'trig'",
+            format
+        )
+        .into()),
+    }
+}
+
+/// How MQA events are made from dataset events.
+#[derive(Debug, Clone)]
+pub struct EventOptions {
+    /// Types of dataset events assessed, others are skipped.
+    pub process_event_types: Vec<DatasetEventType>,
+    pub timestamp_mode: TimestampMode,
+    /// Current time in milliseconds since the unix epoch.
+    pub clock: fn() -> i64,
+    /// Graphs last assessed, whose events are not assessed again while their
+    /// graph is unchanged.
+    pub unchanged_graphs: Option<Arc<UnchangedGraphs>>,
+    /// Maximum size of the graph of an MQA event.
+    pub max_output_bytes: usize,
+}
+
+impl Default for EventOptions {
+    fn default() -> Self {
+        EventOptions {
+            process_event_types: vec![DatasetEventType::DatasetHarvested],
+            timestamp_mode: TimestampMode::default(),
+            clock: current_timestamp,
+            unchanged_graphs: None,
+            max_output_bytes: *MAX_OUTPUT_BYTES,
+        }
+    }
+}
+
+/// Timestamp of MQA events: the time they are made, the timestamp of the
+/// dataset event, so replayed assessments keep the time of their input, or
+/// the timestamp of the dataset event when it has one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimestampMode {
+    Now,
+    #[default]
+    Input,
+    InputOrNow,
+}
+
+impl FromStr for TimestampMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "now" => Ok(TimestampMode::Now),
+            "input" => Ok(TimestampMode::Input),
+            "input-or-now" => Ok(TimestampMode::InputOrNow),
+            _ => Err(format!(
+                "invalid timestamp mode '{}', expected 'now', 'input' or 'input-or-now'",
+                s
+            )
+            .into()),
+        }
+    }
+}
+
+impl TimestampMode {
+    /// Timestamp of an MQA event of a dataset event with the given timestamp.
+    pub fn timestamp(&self, input: i64, clock: fn() -> i64) -> i64 {
+        match self {
+            TimestampMode::Now => clock(),
+            TimestampMode::Input => input,
+            TimestampMode::InputOrNow if input > 0 => input,
+            TimestampMode::InputOrNow => clock(),
+        }
+    }
+}
+
+/// Comma separated dataset event types to assess, at least one.
+fn parse_event_types(value: &str) -> Result<Vec<DatasetEventType>, Error> {
+    let event_types = value
+        .split(',')
+        .map(str::trim)
+        .filter(|event_type| !event_type.is_empty())
+        .map(str::parse)
+        .collect::<Result<Vec<DatasetEventType>, Error>>()?;
+    if event_types.is_empty() {
+        return Err("at least one event type must be processed".into());
+    }
+    Ok(event_types)
+}
+
+/// Where consumers without committed offsets start, `earliest` or `latest`.
+fn parse_auto_offset_reset(value: &str) -> Result<&'static str, Error> {
+    match value {
+        "earliest" => Ok("earliest"),
+        "latest" => Ok("latest"),
+        _ => Err(format!(
+            "invalid auto offset reset '{}', expected 'earliest' or 'latest'",
+            value
+        )
+        .into()),
+    }
 }
 
 pub fn create_sr_settings() -> Result<SrSettings, Error> {
@@ -51,130 +411,65 @@ pub fn create_sr_settings() -> Result<SrSettings, Error> {
         sr_settings_builder.add_url(url.to_string());
     });
 
-    let sr_settings = sr_settings_builder
-        .set_timeout(Duration::from_secs(30))
-        .build()?;
+    sr_settings_builder.set_timeout(Duration::from_secs(30));
+    // The proxy of the settings builder is left unset, as it would replace
+    // the one of the client.
+    let sr_settings = match PROXY.as_ref() {
+        Some(proxy) => sr_settings_builder.build_with(proxy.apply(reqwest::Client::builder()))?,
+        None => sr_settings_builder.build()?,
+    };
     Ok(sr_settings)
 }
 
-pub fn create_consumer() -> Result<StreamConsumer, KafkaError> {
-    let consumer: StreamConsumer = ClientConfig::new()
-        .set("group.id", "fdk-mqa-property-checker")
-        .set("bootstrap.servers", BROKERS.clone())
-        .set("enable.partition.eof", "false")
-        .set("session.timeout.ms", "6000")
-        .set("enable.auto.commit", "true")
-        .set("enable.auto.offset.store", "false")
-        .set("auto.offset.reset", "beginning")
-        .set("api.version.request", "false")
-        .set("security.protocol", "plaintext")
-        .set("max.partition.fetch.bytes", "2097152")
-        .create()?;
-    consumer.subscribe(&[&INPUT_TOPIC])?;
-    Ok(consumer)
-}
-
-pub fn create_producer() -> Result<FutureProducer, KafkaError> {
-    ClientConfig::new()
-        .set("bootstrap.servers", BROKERS.clone())
-        .set("message.timeout.ms", "5000")
-        .set("compression.type", "snappy")
-        .create()
-}
-
-/// Creates all the resources and runs the event loop. The event loop will:
-///   1) receive a stream of messages from the `StreamConsumer`.
-///   2) filter out eventual Kafka errors.
-///   3) send the message to a thread pool for processing.
-///   4) produce the result to the output topic.
-/// `tokio::spawn` is used to handle IO-bound tasks in parallel (e.g., producing
-/// the messages)
-pub async fn run_async_processor(worker_id: usize, sr_settings: SrSettings) -> Result<(), Error> {
-    tracing::info!(worker_id, "starting worker");
-
-    let consumer = create_consumer()?;
-    let producer = create_producer()?;
-    let mut encoder = AvroEncoder::new(sr_settings.clone());
-    let mut decoder = AvroDecoder::new(sr_settings);
-    let input_store = Store::new()?;
-    let output_store = Store::new()?;
-
-    tracing::info!(worker_id, "listening for messages");
-    loop {
-        let message = consumer.recv().await?;
-        let span = tracing::span!(
-            Level::INFO,
-            "message",
-            // topic = message.topic(),
-            partition = message.partition(),
-            offset = message.offset(),
-            timestamp = message.timestamp().to_millis(),
-        );
-
-        receive_message(
-            &consumer,
-            &producer,
-            &mut decoder,
-            &mut encoder,
-            &input_store,
-            &output_store,
-            &message,
-        )
-        .instrument(span)
-        .await;
-    }
+/// Handler assessing the dataset events of messages, the default handler of
+/// the workers of a [`pipeline::ProcessingPipeline`].
+pub struct PropertyChecker {
+    decoder: AvroDecoder<'static>,
+    encoder: AvroEncoder<'static>,
 }
 
-async fn receive_message(
-    consumer: &StreamConsumer,
-    producer: &FutureProducer,
-    decoder: &mut AvroDecoder<'_>,
-    encoder: &mut AvroEncoder<'_>,
-    input_store: &Store,
-    output_store: &Store,
-    message: &BorrowedMessage<'_>,
-) {
-    let start_time = Instant::now();
-    let result = handle_message(
-        producer,
-        decoder,
-        encoder,
-        input_store,
-        output_store,
-        message,
-    )
-    .await;
-    let elapsed_millis = start_time.elapsed().as_millis();
-    match result {
-        Ok(_) => {
-            tracing::info!(elapsed_millis, "message handled successfully");
-            PROCESSED_MESSAGES.with_label_values(&["success"]).inc();
-        }
-        Err(e) => {
-            tracing::error!(
-                elapsed_millis,
-                error = e.to_string(),
-                "failed while handling message"
-            );
-            PROCESSED_MESSAGES.with_label_values(&["error"]).inc();
+impl PropertyChecker {
+    pub fn new(sr_settings: SrSettings) -> Self {
+        PropertyChecker {
+            decoder: AvroDecoder::new(sr_settings.clone()),
+            encoder: AvroEncoder::new(sr_settings),
         }
-    };
-    PROCESSING_TIME.observe(elapsed_millis as f64 / 1000.0);
-    if let Err(e) = consumer.store_offset_from_message(&message) {
-        tracing::warn!(error = e.to_string(), "failed to store offset");
-    };
+    }
 }
 
-pub async fn handle_message(
-    producer: &FutureProducer,
+impl MessageHandler for PropertyChecker {
+    fn handle<'a>(
+        &'a mut self,
+        message: &'a OwnedMessage,
+        input_store: &'a Store,
+        output_store: &'a Store,
+    ) -> BoxFuture<'a, Result<Handled, Error>> {
+        handle_message(
+            &mut self.decoder,
+            &mut self.encoder,
+            input_store,
+            output_store,
+            message,
+        )
+        .boxed()
+    }
+}
+
+/// Handles a message, returning the records of its MQA events for each of
+/// their topics and the stats of its graph and MQA graphs, or neither when its
+/// event is skipped.
+pub async fn handle_message<M: Message>(
     decoder: &mut AvroDecoder<'_>,
     encoder: &mut AvroEncoder<'_>,
     input_store: &Store,
     output_store: &Store,
-    message: &BorrowedMessage<'_>,
-) -> Result<(), Error> {
+    message: &M,
+) -> Result<Handled, Error> {
     match decode_message(decoder, message).await? {
-        InputEvent::DatasetEvent(event) => {
+        InputEvent::DatasetEvent(mut event) => {
+            if event.previous_graph.is_none() {
+                event.previous_graph = header_value(message.headers(), headers::PREVIOUS_GRAPH);
+            }
             let span = tracing::span!(
                 Level::INFO,
                 "event",
@@ -182,37 +477,111 @@ pub async fn handle_message(
                 event_type = format!("{:?}", event.event_type),
             );
 
-            let key = event.fdk_id.clone();
-            let mqa_event = handle_dataset_event(input_store, output_store, event)
-                .instrument(span)
-                .await?;
-
-            let encoded = encoder
-                .encode_struct(
-                    mqa_event,
-                    &SubjectNameStrategy::RecordNameStrategy("no.fdk.mqa.MQAEvent".to_string()),
-                )
-                .await?;
+            let mut source = source_context(message.headers());
+            if event.base_iri.is_some() {
+                source.base_iri = event.base_iri.take();
+            }
+            let (routed_events, stats, assessed_graph) =
+                handle_dataset_event(input_store, output_store, event, &source, &EVENT_OPTIONS)
+                    .instrument(span)
+                    .await?;
 
-            let record: FutureRecord<String, Vec<u8>> =
-                FutureRecord::to(&OUTPUT_TOPIC).key(&key).payload(&encoded);
-            producer
-                .send(record, Duration::from_secs(0))
-                .await
-                .map_err(|e| e.0)?;
+            let headers = build_headers(header_value(message.headers(), headers::CORRELATION_ID));
+            let mut records = Vec::new();
+            for RoutedEvent {
+                mqa_event,
+                publishers,
+            } in routed_events
+            {
+                if let Some(limiter) = PUBLISHER_RATE_LIMITER.as_ref() {
+                    if let Some(key) = rate_limit_key(source.catalog_id.clone(), &publishers) {
+                        limiter.throttle(&key).await;
+                    }
+                }
+                for topic in route_topics(&OUTPUT_TOPIC, &OUTPUT_ROUTES, &publishers) {
+                    // The subject of the schema may depend on the topic.
+                    let payload = match *OUTPUT_ENCODING {
+                        Encoding::Avro => {
+                            encoder
+                                .encode_struct(&mqa_event, &SUBJECT_NAME_STRATEGY.strategy(topic))
+                                .await?
+                        }
+                        Encoding::Json => serde_json::to_vec(&mqa_event)?,
+                    };
+                    records.push(OutputRecord {
+                        topic: topic.to_string(),
+                        key: mqa_event.fdk_id.clone(),
+                        payload,
+                        headers: headers.clone(),
+                    });
+                }
+            }
+            Ok(Handled {
+                records,
+                stats,
+                assessed_graph,
+            })
         }
         InputEvent::Unknown { namespace, name } => {
             tracing::warn!(namespace, name, "skipping unknown event");
+            Ok(Handled::default())
         }
     }
-    Ok(())
 }
 
-async fn decode_message(
+/// MQA event of a dataset event, with the IRIs of the publishers of the
+/// datasets it assesses, by which it is routed and rate limited.
+#[derive(Debug, Clone)]
+pub struct RoutedEvent {
+    pub mqa_event: MqaEvent,
+    pub publishers: Vec<String>,
+}
+
+/// IRIs of the publishers of a dataset.
+fn dataset_publishers(dataset: NamedNodeRef, input_store: &Store) -> Vec<String> {
+    list_publishers(dataset, input_store)
+        .filter_map(Result::ok)
+        .filter_map(|quad| match quad.object {
+            Term::NamedNode(publisher) => Some(publisher.into_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// IRIs of the publishers of the datasets of each MQA graph of the assessed
+/// input store, in order of the graphs: one graph for each dataset when
+/// catalogs are split, or else one graph of all datasets.
+fn graph_publishers(input_store: &Store, split_catalogs: bool) -> Result<Vec<Vec<String>>, Error> {
+    let publishers = dataset_nodes(input_store)?
+        .iter()
+        .map(|dataset| dataset_publishers(dataset.as_ref(), input_store))
+        .collect::<Vec<Vec<String>>>();
+    if split_catalogs {
+        return Ok(publishers);
+    }
+    let mut publishers = publishers.concat();
+    publishers.sort();
+    publishers.dedup();
+    Ok(vec![publishers])
+}
+
+async fn decode_message<M: Message>(
     decoder: &mut AvroDecoder<'_>,
-    message: &BorrowedMessage<'_>,
+    message: &M,
 ) -> Result<InputEvent, Error> {
-    match decoder.decode(message.payload()).await? {
+    if *INPUT_ENCODING == Encoding::Json {
+        return decode_json_event(message.payload());
+    }
+
+    let (decoded, replacements) = match decoder.decode(message.payload()).await {
+        Ok(decoded) => (decoded, 0),
+        Err(e) => match decode_avro_lossy(message.payload()).await {
+            Some((decoded, replacements)) if replacements > 0 => (decoded, replacements),
+            _ => return Err(e.into()),
+        },
+    };
+
+    match decoded {
         DecodeResult {
             name:
                 Some(Name {
@@ -223,9 +592,10 @@ async fn decode_message(
             value,
         } => {
             let event = match (namespace.as_str(), name.as_str()) {
-                ("no.fdk.mqa", "DatasetEvent") => {
-                    InputEvent::DatasetEvent(apache_avro::from_value::<DatasetEvent>(&value)?)
-                }
+                ("no.fdk.mqa", "DatasetEvent") => dataset_event(
+                    apache_avro::from_value::<DatasetEvent>(&value)?,
+                    replacements,
+                ),
                 _ => InputEvent::Unknown { namespace, name },
             };
             Ok(event)
@@ -234,22 +604,780 @@ async fn decode_message(
     }
 }
 
+/// Decodes an Avro payload the decoder rejected, reading invalid UTF-8 in its
+/// strings lossily with the writer schema from the registry.
+async fn decode_avro_lossy(payload: Option<&[u8]>) -> Option<(DecodeResult, usize)> {
+    let BytesResult::Valid(id, datum) = get_bytes_result(payload) else {
+        return None;
+    };
+    let schema = get_schema_by_id(id, &create_sr_settings().ok()?)
+        .await
+        .ok()?;
+    codec::decode_avro_lossy(&schema.schema, &datum).ok()
+}
+
+/// Decodes a JSON payload as a dataset event, without the schema registry.
+pub fn decode_json_event(payload: Option<&[u8]>) -> Result<InputEvent, Error> {
+    let payload = payload.ok_or("message has no payload")?;
+    let (payload, replacements) = decode_utf8_lossy(payload);
+    Ok(dataset_event(serde_json::from_str(&payload)?, replacements))
+}
+
+/// Dataset event with the byte order mark stripped from its graph, reporting
+/// invalid UTF-8 replaced while decoding it.
+fn dataset_event(mut event: DatasetEvent, replacements: usize) -> InputEvent {
+    if replacements > 0 {
+        tracing::warn!(
+            fdk_id = event.fdk_id,
+            replacements,
+            "replaced invalid UTF-8 in event"
+        );
+    }
+    event.graph = strip_bom(event.graph);
+    InputEvent::DatasetEvent(event)
+}
+
+/// Error of handling an event, naming the event when its graph is invalid
+/// Turtle, whose details are logged as they are needed to fix the graph.
+fn event_error(e: Error, fdk_id: &str) -> Error {
+    match e {
+        Error::InvalidTurtle { details, .. } => {
+            tracing::warn!(fdk_id, details, "failed to parse graph");
+            Error::InvalidTurtle {
+                fdk_id: Some(fdk_id.to_string()),
+                details,
+            }
+        }
+        e => e,
+    }
+}
+
+/// MQA events of a dataset event: one for the graph, or one for each dataset
+/// of a catalog when catalogs are split, with the stats of the graph and of
+/// all its MQA graphs. Events of types not processed, and of unknown types,
+/// are skipped without MQA events or stats, as are events whose graph is
+/// unchanged, which get the MQA events of its last assessment when they are
+/// emitted again. The graph of an assessed event is given to be recorded as
+/// unchanged once its MQA events are delivered.
 async fn handle_dataset_event(
     input_store: &Store,
     output_store: &Store,
     event: DatasetEvent,
-) -> Result<MqaEvent, Error> {
+    source: &SourceContext,
+    event_options: &EventOptions,
+) -> Result<(Vec<RoutedEvent>, Option<GraphStats>, Option<AssessedGraph>), Error> {
     match event.event_type {
-        DatasetEventType::DatasetHarvested => {
-            let graph =
-                parse_rdf_graph_and_calculate_metrics(input_store, output_store, event.graph).await?;
-            Ok(MqaEvent {
-                event_type: MQAEventType::PropertiesChecked,
-                fdk_id: event.fdk_id,
-                graph,
-                timestamp: event.timestamp,
-            })
+        event_type if event_options.process_event_types.contains(&event_type) => {
+            let timestamp = event_options
+                .timestamp_mode
+                .timestamp(event.timestamp, event_options.clock);
+            let unchanged = event_options
+                .unchanged_graphs
+                .as_ref()
+                .map(|graphs| (graphs, graph_hash(&event.graph)));
+            if let Some((graphs, hash)) = &unchanged {
+                if let Some(mut routed_events) = graphs.unchanged(&event.fdk_id, hash) {
+                    tracing::info!(mode = ?graphs.mode(), "skipping unchanged graph");
+                    SKIPPED_UNCHANGED.inc();
+                    for RoutedEvent { mqa_event, .. } in routed_events.iter_mut() {
+                        mqa_event.timestamp = timestamp;
+                        mqa_event.source_timestamp = Some(event.timestamp);
+                    }
+                    return Ok((routed_events, None, None));
+                }
+            }
+            let fdk_id = event.fdk_id.clone();
+
+            let graphs = if *SPLIT_CATALOGS {
+                parse_rdf_graph_and_calculate_metrics_per_dataset(
+                    input_store,
+                    output_store,
+                    event.graph,
+                    &event.fdk_id,
+                    source,
+                    &ASSESSMENT_OPTIONS,
+                )
+                .await
+                .map_err(|e| event_error(e, &event.fdk_id))?
+            } else {
+                let (graph, stats) = parse_rdf_graph_and_calculate_metrics(
+                    input_store,
+                    output_store,
+                    event.graph,
+                    Some(&event.fdk_id),
+                    source,
+                    &ASSESSMENT_OPTIONS,
+                )
+                .await
+                .map_err(|e| event_error(e, &event.fdk_id))?;
+                vec![(event.fdk_id, graph, stats)]
+            };
+            let stats = message_stats(&graphs);
+            INPUT_GRAPH_QUADS.observe(stats.input_quads as f64);
+            OUTPUT_GRAPH_QUADS.observe(stats.output_quads as f64);
+
+            let graph_hash = canonical_hash(input_store)?;
+            let publishers = graph_publishers(input_store, *SPLIT_CATALOGS)?;
+            let removed_assessments = removed_assessments_of(
+                event.previous_graph.as_deref(),
+                &graphs
+                    .iter()
+                    .map(|(_, graph, _)| graph.as_str())
+                    .collect::<Vec<&str>>(),
+            );
+            let routed_events = graphs
+                .into_iter()
+                .zip(publishers)
+                .map(
+                    |((fdk_id, graph, stats), publishers)| -> Result<RoutedEvent, Error> {
+                        let output = fit_output_graph(
+                            graph,
+                            ASSESSMENT_OPTIONS.graph_layout,
+                            event_options.max_output_bytes,
+                        )?;
+                        let mqa_event = MqaEvent {
+                            event_type: MQAEventType::PropertiesChecked,
+                            fdk_id,
+                            graph: output.graph,
+                            timestamp,
+                            source_timestamp: Some(event.timestamp),
+                            graph_hash: Some(graph_hash.clone()),
+                            metrics_revision: Some(METRICS_REVISION),
+                            graph_encoding: output.encoding,
+                            removed_assessments: removed_assessments.clone(),
+                            input_quads: i32::try_from(stats.input_quads).ok(),
+                            output_quads: i32::try_from(stats.output_quads).ok(),
+                        };
+                        Ok(RoutedEvent {
+                            mqa_event,
+                            publishers,
+                        })
+                    },
+                )
+                .collect::<Result<Vec<RoutedEvent>, Error>>()?;
+            let assessed_graph = unchanged.map(|(graphs, hash)| {
+                AssessedGraph::new(graphs.clone(), fdk_id, hash, routed_events.clone())
+            });
+            Ok((routed_events, Some(stats), assessed_graph))
+        }
+        event_type => {
+            tracing::info!(event_type = event_type.as_str(), "skipping event type");
+            SKIPPED_EVENTS
+                .with_label_values(&[event_type.as_str()])
+                .inc();
+            Ok((Vec::new(), None, None))
         }
-        DatasetEventType::Unknown => Err(format!("unknown DatasetEventType").into()),
+    }
+}
+
+/// Stats of the graph of a message and of all its MQA graphs.
+fn message_stats(graphs: &[(String, String, GraphStats)]) -> GraphStats {
+    GraphStats {
+        input_quads: graphs
+            .first()
+            .map(|(_, _, stats)| stats.input_quads)
+            .unwrap_or_default(),
+        output_quads: graphs.iter().map(|(_, _, stats)| stats.output_quads).sum(),
+    }
+}
+
+/// Assessments of the previous MQA graph of an event that are not in its
+/// current MQA graphs, or none when the previous graph is not given or cannot
+/// be read.
+fn removed_assessments_of(previous_graph: Option<&str>, graphs: &[&str]) -> Option<Vec<String>> {
+    let previous_graph = previous_graph.filter(|graph| !graph.trim().is_empty())?;
+    removed_assessments(previous_graph, graphs)
+        .map_err(|e| tracing::warn!(error = e.to_string(), "failed to read previous graph"))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use flate2::read::GzDecoder;
+    use oxigraph::model::{
+        vocab::{rdf, xsd},
+        GraphNameRef, Literal, LiteralRef, NamedNodeRef, QuadRef,
+    };
+    use rdkafka::message::{Header, OwnedHeaders};
+
+    use crate::{
+        kafka::{size_guard::GZIP_BASE64, unchanged::SkipUnchanged},
+        rdf::parse_turtle,
+        vocab::{dcat_mqa, dcterms, prov},
+    };
+
+    #[test]
+    fn test_parse_num_workers() {
+        assert_eq!(1, parse_num_workers("1").unwrap());
+        assert_eq!(16, parse_num_workers("16").unwrap());
+        assert!(parse_num_workers("0").is_err());
+        assert!(parse_num_workers("-1").is_err());
+        assert!(parse_num_workers("four").is_err());
+    }
+
+    #[test]
+    fn test_graph_publishers_of_each_dataset() {
+        let store = Store::new().unwrap();
+        parse_turtle(
+            &store,
+            r#"
+            @prefix dcat: <http://www.w3.org/ns/dcat#> .
+            @prefix dct: <http://purl.org/dc/terms/> .
+
+            <https://catalog.foo> a dcat:Catalog ;
+                dct:publisher <https://organizations.foo/0> ;
+                dcat:dataset <https://dataset.foo/b> , <https://dataset.foo/a> .
+            <https://dataset.foo/a> a dcat:Dataset ;
+                dct:publisher <https://organizations.foo/1> .
+            <https://dataset.foo/b> a dcat:Dataset ;
+                dct:publisher <https://organizations.foo/2> .
+            "#
+            .to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                vec!["https://organizations.foo/1".to_string()],
+                vec!["https://organizations.foo/2".to_string()],
+            ],
+            graph_publishers(&store, true).unwrap()
+        );
+        assert_eq!(
+            vec![vec![
+                "https://organizations.foo/1".to_string(),
+                "https://organizations.foo/2".to_string(),
+            ]],
+            graph_publishers(&store, false).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_report_mode() {
+        assert_eq!(ReportMode::Full, parse_report_mode("full", "").unwrap());
+        assert_eq!(
+            ReportMode::TrueOnly,
+            parse_report_mode("true-only", "").unwrap()
+        );
+        assert_eq!(
+            ReportMode::Core(vec![
+                NamedNode::new_unchecked(
+                    "https://data.norge.no/vocabulary/dcatno-mqa#accessRightsAvailability"
+                ),
+                NamedNode::new_unchecked(
+                    "https://data.norge.no/vocabulary/dcatno-mqa#licenseAvailability"
+                ),
+            ]),
+            parse_report_mode(
+                "core",
+                "https://data.norge.no/vocabulary/dcatno-mqa#accessRightsAvailability, https://data.norge.no/vocabulary/dcatno-mqa#licenseAvailability"
+            )
+            .unwrap()
+        );
+        assert!(parse_report_mode("core", "licenseAvailability").is_err());
+        assert!(parse_report_mode("partial", "").is_err());
+    }
+
+    #[test]
+    fn test_parse_output_validation() {
+        assert_eq!(
+            OutputValidation::Fail,
+            parse_output_validation("fail").unwrap()
+        );
+        assert_eq!(
+            OutputValidation::Warn,
+            parse_output_validation("warn").unwrap()
+        );
+        assert_eq!(
+            OutputValidation::Off,
+            parse_output_validation("off").unwrap()
+        );
+        assert!(parse_output_validation("strict").is_err());
+    }
+
+    #[test]
+    fn test_parse_graph_layout() {
+        assert_eq!(GraphLayout::Flat, parse_graph_layout("turtle").unwrap());
+        assert_eq!(
+            GraphLayout::PerAssessmentNamedGraphs,
+            parse_graph_layout("trig").unwrap()
+        );
+        assert!(parse_graph_layout("nquads").is_err());
+    }
+
+    #[test]
+    fn test_parse_auto_offset_reset() {
+        assert_eq!("earliest", parse_auto_offset_reset("earliest").unwrap());
+        assert_eq!("latest", parse_auto_offset_reset("latest").unwrap());
+        assert!(parse_auto_offset_reset("beginning").is_err());
+    }
+
+    #[test]
+    fn test_parse_event_types() {
+        assert_eq!(
+            vec![DatasetEventType::DatasetHarvested],
+            parse_event_types("DATASET_HARVESTED").unwrap()
+        );
+        assert_eq!(
+            vec![
+                DatasetEventType::DatasetHarvested,
+                DatasetEventType::DatasetReasoned
+            ],
+            parse_event_types("DATASET_HARVESTED, DATASET_REASONED").unwrap()
+        );
+        assert!(parse_event_types("").is_err());
+        assert!(parse_event_types("DATASET_REMOVED").is_err());
+    }
+
+    fn dataset_event(event_type: DatasetEventType) -> DatasetEvent {
+        DatasetEvent {
+            event_type,
+            fdk_id: "fdk-id".to_string(),
+            graph: r#"
+                <https://dataset.foo> a <http://www.w3.org/ns/dcat#Dataset> ;
+                    <https://data.norge.no/vocabulary/dcatno-mqa#hasAssessment> <https://dataset.assessment.foo> .
+                "#
+            .to_string(),
+            timestamp: 1,
+            previous_graph: None,
+            base_iri: None,
+        }
+    }
+
+    /// MQA events of a dataset event, recording its graph as if they were
+    /// delivered.
+    async fn handled_events(event: DatasetEvent, event_options: &EventOptions) -> Vec<MqaEvent> {
+        let (mqa_events, _, assessed_graph) = handle_dataset_event(
+            &Store::new().unwrap(),
+            &Store::new().unwrap(),
+            event,
+            &SourceContext::default(),
+            event_options,
+        )
+        .await
+        .unwrap();
+        if let Some(assessed_graph) = assessed_graph {
+            assessed_graph.record();
+        }
+        mqa_events
+            .into_iter()
+            .map(|routed_event| routed_event.mqa_event)
+            .collect()
+    }
+
+    /// MQA graph of the dataset event with the given headers.
+    async fn assessed_with_headers(headers: OwnedHeaders) -> Store {
+        let mqa_event = handle_dataset_event(
+            &Store::new().unwrap(),
+            &Store::new().unwrap(),
+            dataset_event(DatasetEventType::DatasetHarvested),
+            &source_context(Some(&headers)),
+            &EventOptions::default(),
+        )
+        .await
+        .unwrap()
+        .0
+        .remove(0)
+        .mqa_event;
+        let store = Store::new().unwrap();
+        parse_turtle(&store, mqa_event.graph).unwrap();
+        store
+    }
+
+    #[tokio::test]
+    async fn test_graph_stats_of_handled_event() {
+        let input_store = Store::new().unwrap();
+        let output_store = Store::new().unwrap();
+        let (mqa_events, stats, _) = handle_dataset_event(
+            &input_store,
+            &output_store,
+            dataset_event(DatasetEventType::DatasetHarvested),
+            &SourceContext::default(),
+            &EventOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        let stats = stats.unwrap();
+        assert_eq!(input_store.len().unwrap(), stats.input_quads);
+        assert_eq!(output_store.len().unwrap(), stats.output_quads);
+        assert_eq!(
+            Some(stats.input_quads as i32),
+            mqa_events[0].mqa_event.input_quads
+        );
+        assert_eq!(
+            Some(stats.output_quads as i32),
+            mqa_events[0].mqa_event.output_quads
+        );
+
+        let (_, stats, _) = handle_dataset_event(
+            &input_store,
+            &output_store,
+            dataset_event(DatasetEventType::Unknown),
+            &SourceContext::default(),
+            &EventOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(None, stats);
+    }
+
+    #[tokio::test]
+    async fn test_large_mqa_graph_fitted() {
+        let mut event = dataset_event(DatasetEventType::DatasetHarvested);
+        for i in 0..500 {
+            event.graph.push_str(&format!(
+                r#"
+                <https://dataset.foo> <http://www.w3.org/ns/dcat#distribution> <https://distribution.foo/{i}> .
+                <https://distribution.foo/{i}> a <http://www.w3.org/ns/dcat#Distribution> ;
+                    <https://data.norge.no/vocabulary/dcatno-mqa#hasAssessment> <https://distribution.assessment.foo/{i:03}> .
+                "#
+            ));
+        }
+        let event_options = EventOptions {
+            max_output_bytes: 20_000,
+            ..Default::default()
+        };
+
+        let mqa_event = handled_events(event, &event_options).await.remove(0);
+        assert_eq!(Some(GZIP_BASE64), mqa_event.graph_encoding.as_deref());
+        assert!(mqa_event.graph.len() <= 20_000);
+        // The produced JSON payload, with the compressed graph.
+        assert!(serde_json::to_vec(&mqa_event).unwrap().len() < 21_000);
+
+        let mut graph = String::new();
+        GzDecoder::new(STANDARD.decode(&mqa_event.graph).unwrap().as_slice())
+            .read_to_string(&mut graph)
+            .unwrap();
+        let store = Store::new().unwrap();
+        parse_turtle(&store, graph).unwrap();
+        let kept = store
+            .quads_for_pattern(
+                None,
+                Some(rdf::TYPE),
+                Some(dcat_mqa::DISTRIBUTION_ASSESSMENT_CLASS.into()),
+                None,
+            )
+            .count();
+        let omitted = store
+            .quads_for_pattern(
+                Some(NamedNodeRef::new_unchecked("https://dataset.assessment.foo").into()),
+                Some(dcat_mqa::OMITTED_DISTRIBUTION_ASSESSMENTS),
+                None,
+                None,
+            )
+            .next()
+            .unwrap()
+            .unwrap()
+            .object;
+        assert!(kept > 0);
+        assert_eq!(
+            Term::from(Literal::new_typed_literal(
+                (500 - kept).to_string(),
+                xsd::INTEGER
+            )),
+            omitted
+        );
+    }
+
+    #[tokio::test]
+    async fn test_removed_assessments() {
+        let mut event = dataset_event(DatasetEventType::DatasetHarvested);
+        event.previous_graph = Some(
+            r#"
+            @prefix dcatnomqa: <https://data.norge.no/vocabulary/dcatno-mqa#> .
+            <https://dataset.assessment.foo> a dcatnomqa:DatasetAssessment ;
+                dcatnomqa:hasDistributionAssessment <https://distribution.assessment.foo> .
+            <https://distribution.assessment.foo> a dcatnomqa:DistributionAssessment .
+            "#
+            .to_string(),
+        );
+
+        let mqa_event = handled_events(event, &EventOptions::default())
+            .await
+            .remove(0);
+        assert_eq!(
+            Some(vec!["https://distribution.assessment.foo".to_string()]),
+            mqa_event.removed_assessments
+        );
+
+        let mqa_event = handled_events(
+            dataset_event(DatasetEventType::DatasetHarvested),
+            &EventOptions::default(),
+        )
+        .await
+        .remove(0);
+        assert_eq!(None, mqa_event.removed_assessments);
+    }
+
+    #[tokio::test]
+    async fn test_source_identifiers_from_headers() {
+        let assessment = NamedNodeRef::new_unchecked("https://dataset.assessment.foo");
+        let store = assessed_with_headers(
+            OwnedHeaders::new()
+                .insert(Header {
+                    key: headers::CATALOG_ID,
+                    value: Some("https://catalog.foo"),
+                })
+                .insert(Header {
+                    key: headers::HARVEST_ID,
+                    value: Some("harvest-42"),
+                }),
+        )
+        .await;
+
+        assert!(store
+            .contains(QuadRef::new(
+                assessment,
+                dcterms::IS_PART_OF,
+                NamedNodeRef::new_unchecked("https://catalog.foo"),
+                GraphNameRef::DefaultGraph,
+            ))
+            .unwrap());
+        // Identifiers that are not IRIs are literals.
+        assert!(store
+            .contains(QuadRef::new(
+                assessment,
+                prov::WAS_INFLUENCED_BY,
+                LiteralRef::new_simple_literal("harvest-42"),
+                GraphNameRef::DefaultGraph,
+            ))
+            .unwrap());
+
+        let store = assessed_with_headers(OwnedHeaders::new()).await;
+        for property in [dcterms::IS_PART_OF, prov::WAS_INFLUENCED_BY] {
+            assert!(store
+                .quads_for_pattern(None, Some(property), None, None)
+                .next()
+                .is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_process_event_types() {
+        let skipped = || {
+            SKIPPED_EVENTS
+                .with_label_values(&["DATASET_REASONED"])
+                .get()
+        };
+        let harvested = EventOptions::default();
+        let all = EventOptions {
+            process_event_types: vec![
+                DatasetEventType::DatasetHarvested,
+                DatasetEventType::DatasetReasoned,
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            1,
+            handled_events(
+                dataset_event(DatasetEventType::DatasetHarvested),
+                &harvested
+            )
+            .await
+            .len()
+        );
+        let before = skipped();
+        assert_eq!(
+            0,
+            handled_events(dataset_event(DatasetEventType::DatasetReasoned), &harvested)
+                .await
+                .len()
+        );
+        assert_eq!(before + 1, skipped());
+
+        assert_eq!(
+            1,
+            handled_events(dataset_event(DatasetEventType::DatasetReasoned), &all)
+                .await
+                .len()
+        );
+        assert_eq!(
+            0,
+            handled_events(dataset_event(DatasetEventType::Unknown), &all)
+                .await
+                .len()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_graph_skipped() {
+        let skipped = || SKIPPED_UNCHANGED.get();
+        let event_options = |mode| EventOptions {
+            unchanged_graphs: Some(Arc::new(UnchangedGraphs::new(mode, 10))),
+            ..Default::default()
+        };
+        let mut changed = dataset_event(DatasetEventType::DatasetHarvested);
+        changed
+            .graph
+            .push_str("<https://dataset.foo> <http://purl.org/dc/terms/title> \"Changed\"@en .");
+
+        let silent = event_options(SkipUnchanged::Silent);
+        let before = skipped();
+        let first =
+            handled_events(dataset_event(DatasetEventType::DatasetHarvested), &silent).await;
+        assert_eq!(1, first.len());
+        assert_eq!(before, skipped());
+        assert!(
+            handled_events(dataset_event(DatasetEventType::DatasetHarvested), &silent)
+                .await
+                .is_empty()
+        );
+        assert_eq!(before + 1, skipped());
+        assert_eq!(1, handled_events(changed, &silent).await.len());
+
+        let emit = event_options(SkipUnchanged::Emit);
+        let first = handled_events(dataset_event(DatasetEventType::DatasetHarvested), &emit)
+            .await
+            .remove(0);
+        let mut event = dataset_event(DatasetEventType::DatasetHarvested);
+        event.timestamp = 2;
+        let again = handled_events(event, &emit).await.remove(0);
+        assert_eq!(first.graph, again.graph);
+        assert_eq!(first.graph_hash, again.graph_hash);
+        assert_eq!(2, again.timestamp);
+        assert_eq!(Some(2), again.source_timestamp);
+    }
+
+    #[tokio::test]
+    async fn test_undelivered_graph_assessed_again() {
+        let graphs = Arc::new(UnchangedGraphs::new(SkipUnchanged::Silent, 10));
+        let event_options = EventOptions {
+            unchanged_graphs: Some(graphs.clone()),
+            ..Default::default()
+        };
+
+        // The MQA events of the first assessment are not delivered.
+        let (mqa_events, _, assessed_graph) = handle_dataset_event(
+            &Store::new().unwrap(),
+            &Store::new().unwrap(),
+            dataset_event(DatasetEventType::DatasetHarvested),
+            &SourceContext::default(),
+            &event_options,
+        )
+        .await
+        .unwrap();
+        assert_eq!(1, mqa_events.len());
+        assert!(assessed_graph.is_some());
+        assert!(graphs.is_empty());
+
+        assert_eq!(
+            1,
+            handled_events(
+                dataset_event(DatasetEventType::DatasetHarvested),
+                &event_options
+            )
+            .await
+            .len()
+        );
+        assert_eq!(1, graphs.len());
+    }
+
+    fn fixed_clock() -> i64 {
+        1700000000000
+    }
+
+    async fn event_timestamps(timestamp_mode: TimestampMode, input: i64) -> (i64, Option<i64>) {
+        let mut event = dataset_event(DatasetEventType::DatasetHarvested);
+        event.timestamp = input;
+        let event_options = EventOptions {
+            timestamp_mode,
+            clock: fixed_clock,
+            ..Default::default()
+        };
+
+        let mqa_event = handled_events(event, &event_options).await.remove(0);
+        (mqa_event.timestamp, mqa_event.source_timestamp)
+    }
+
+    #[tokio::test]
+    async fn test_timestamp_modes() {
+        assert_eq!(
+            (1700000000000, Some(1647698566000)),
+            event_timestamps(TimestampMode::Now, 1647698566000).await
+        );
+        assert_eq!(
+            (1647698566000, Some(1647698566000)),
+            event_timestamps(TimestampMode::Input, 1647698566000).await
+        );
+        assert_eq!(
+            (0, Some(0)),
+            event_timestamps(TimestampMode::Input, 0).await
+        );
+        assert_eq!(
+            (1647698566000, Some(1647698566000)),
+            event_timestamps(TimestampMode::InputOrNow, 1647698566000).await
+        );
+        assert_eq!(
+            (1700000000000, Some(0)),
+            event_timestamps(TimestampMode::InputOrNow, 0).await
+        );
+    }
+
+    #[test]
+    fn test_parse_timestamp_mode() {
+        assert_eq!(TimestampMode::Now, "now".parse().unwrap());
+        assert_eq!(TimestampMode::Input, "input".parse().unwrap());
+        assert_eq!(TimestampMode::InputOrNow, "input-or-now".parse().unwrap());
+        assert!("latest".parse::<TimestampMode>().is_err());
+    }
+
+    #[test]
+    fn test_parse_encoding() {
+        assert_eq!(Encoding::Avro, "avro".parse::<Encoding>().unwrap());
+        assert_eq!(Encoding::Json, "json".parse::<Encoding>().unwrap());
+        assert!("xml".parse::<Encoding>().is_err());
+    }
+
+    #[test]
+    fn test_decode_json_event() {
+        let payload = r#"{"type":"DATASET_HARVESTED","fdkId":"fdk-id","graph":"<a> <b> <c> .","timestamp":1}"#;
+
+        match decode_json_event(Some(payload.as_bytes())).unwrap() {
+            InputEvent::DatasetEvent(event) => {
+                assert_eq!(event.fdk_id, "fdk-id");
+                assert_eq!(event.timestamp, 1);
+            }
+            _ => panic!("payload not decoded as dataset event"),
+        }
+
+        assert!(decode_json_event(None).is_err());
+        assert!(decode_json_event(Some(b"not json")).is_err());
+    }
+
+    #[test]
+    fn test_decode_json_event_invalid_utf8() {
+        let payload = b"{\"type\":\"DATASET_HARVESTED\",\"fdkId\":\"fdk-id\",\"graph\":\"\xef\xbb\xbf<a> <b> \\\"\xc0\xaf\xff\\\" .\",\"timestamp\":1}";
+
+        match decode_json_event(Some(payload)).unwrap() {
+            InputEvent::DatasetEvent(event) => {
+                assert_eq!(event.fdk_id, "fdk-id");
+                assert_eq!(event.graph, "<a> <b> \"\u{fffd}\u{fffd}\u{fffd}\" .");
+            }
+            _ => panic!("payload not decoded as dataset event"),
+        }
+    }
+
+    #[test]
+    fn test_event_error_names_event() {
+        let e = event_error(
+            Error::InvalidTurtle {
+                fdk_id: None,
+                details: "foo".to_string(),
+            },
+            "fdk-id",
+        );
+        assert!(matches!(
+            e,
+            Error::InvalidTurtle { fdk_id: Some(ref id), .. } if id == "fdk-id"
+        ));
+
+        let e = event_error(Error::DatasetNodeNotFound, "fdk-id");
+        assert!(matches!(e, Error::DatasetNodeNotFound));
     }
 }
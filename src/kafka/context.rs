@@ -0,0 +1,224 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use lazy_static::lazy_static;
+use rdkafka::{
+    consumer::{BaseConsumer, ConsumerContext, Rebalance},
+    error::KafkaResult,
+    ClientContext, TopicPartitionList,
+};
+
+use crate::{kafka::seek::seek_to_timestamp, prometheus_metrics::ASSIGNED_PARTITIONS};
+
+lazy_static! {
+    pub static ref PARTITION_ASSIGNMENTS: Arc<PartitionAssignments> =
+        Arc::new(PartitionAssignments::default());
+}
+
+/// Topic partitions currently assigned to each worker.
+#[derive(Debug, Default)]
+pub struct PartitionAssignments {
+    workers: Mutex<HashMap<usize, BTreeSet<(String, i32)>>>,
+}
+
+impl PartitionAssignments {
+    /// Partitions assigned to a worker, ordered by topic and partition.
+    pub fn worker(&self, worker_id: usize) -> Vec<(String, i32)> {
+        self.workers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&worker_id)
+            .map(|partitions| partitions.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Whether any worker has at least one partition assigned.
+    pub fn any_assigned(&self) -> bool {
+        self.workers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .any(|partitions| !partitions.is_empty())
+    }
+
+    fn assign(&self, worker_id: usize, partitions: Vec<(String, i32)>) -> usize {
+        let mut workers = self.workers.lock().unwrap_or_else(|e| e.into_inner());
+        let assigned = workers.entry(worker_id).or_default();
+        assigned.extend(partitions);
+        assigned.len()
+    }
+
+    fn revoke(&self, worker_id: usize, partitions: Vec<(String, i32)>) -> usize {
+        let mut workers = self.workers.lock().unwrap_or_else(|e| e.into_inner());
+        let assigned = workers.entry(worker_id).or_default();
+        for partition in partitions {
+            assigned.remove(&partition);
+        }
+        assigned.len()
+    }
+}
+
+/// Consumer context logging rebalances and commits of a worker, and keeping
+/// track of its assigned partitions.
+pub struct WorkerContext {
+    worker_id: usize,
+    assignments: Arc<PartitionAssignments>,
+    seek_to_timestamp: Option<i64>,
+}
+
+impl WorkerContext {
+    pub fn new(worker_id: usize, assignments: Arc<PartitionAssignments>) -> Self {
+        WorkerContext {
+            worker_id,
+            assignments,
+            seek_to_timestamp: None,
+        }
+    }
+
+    /// Moves assigned partitions to the first offset at or after the
+    /// timestamp, in milliseconds since the epoch, before consuming them.
+    pub fn with_seek_to_timestamp(mut self, timestamp: Option<i64>) -> Self {
+        self.seek_to_timestamp = timestamp;
+        self
+    }
+
+    /// Applies a rebalance event to the worker's partition assignment.
+    fn rebalance(&self, rebalance: &Rebalance) {
+        let worker_id = self.worker_id;
+        let assigned = match rebalance {
+            Rebalance::Assign(tpl) => {
+                tracing::info!(
+                    worker_id,
+                    partitions = format_partitions(tpl),
+                    "partitions assigned"
+                );
+                self.assignments.assign(worker_id, partitions(tpl))
+            }
+            Rebalance::Revoke(tpl) => {
+                tracing::info!(
+                    worker_id,
+                    partitions = format_partitions(tpl),
+                    "partitions revoked"
+                );
+                self.assignments.revoke(worker_id, partitions(tpl))
+            }
+            Rebalance::Error(e) => {
+                tracing::error!(worker_id, error = e.to_string(), "rebalance error");
+                return;
+            }
+        };
+        ASSIGNED_PARTITIONS
+            .with_label_values(&[&worker_id.to_string()])
+            .set(assigned as i64);
+    }
+}
+
+impl ClientContext for WorkerContext {}
+
+impl ConsumerContext for WorkerContext {
+    fn pre_rebalance(&self, _: &BaseConsumer<Self>, rebalance: &Rebalance) {
+        if let Rebalance::Revoke(_) | Rebalance::Error(_) = rebalance {
+            self.rebalance(rebalance);
+        }
+    }
+
+    fn post_rebalance(&self, consumer: &BaseConsumer<Self>, rebalance: &Rebalance) {
+        if let Rebalance::Assign(tpl) = rebalance {
+            self.rebalance(rebalance);
+            if let Some(timestamp) = self.seek_to_timestamp {
+                seek_to_timestamp(consumer, self.worker_id, tpl, timestamp);
+            }
+        }
+    }
+
+    fn commit_callback(&self, result: KafkaResult<()>, offsets: &TopicPartitionList) {
+        match result {
+            Ok(_) => tracing::debug!(
+                worker_id = self.worker_id,
+                partitions = format_partitions(offsets),
+                "offsets committed"
+            ),
+            Err(e) => tracing::warn!(
+                worker_id = self.worker_id,
+                partitions = format_partitions(offsets),
+                error = e.to_string(),
+                "failed to commit offsets"
+            ),
+        }
+    }
+}
+
+fn partitions(tpl: &TopicPartitionList) -> Vec<(String, i32)> {
+    tpl.elements()
+        .iter()
+        .map(|elem| (elem.topic().to_string(), elem.partition()))
+        .collect()
+}
+
+fn format_partitions(tpl: &TopicPartitionList) -> String {
+    partitions(tpl)
+        .iter()
+        .map(|(topic, partition)| format!("{}/{}", topic, partition))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn topic_partitions(partitions: &[i32]) -> TopicPartitionList {
+        let mut tpl = TopicPartitionList::new();
+        for partition in partitions {
+            tpl.add_partition("mqa-dataset-events", *partition);
+        }
+        tpl
+    }
+
+    #[test]
+    fn test_assign_and_revoke() {
+        let assignments = Arc::new(PartitionAssignments::default());
+        let context = WorkerContext::new(0, assignments.clone());
+        assert!(!assignments.any_assigned());
+
+        context.rebalance(&Rebalance::Assign(&topic_partitions(&[2, 0])));
+        assert!(assignments.any_assigned());
+        assert_eq!(
+            vec![
+                ("mqa-dataset-events".to_string(), 0),
+                ("mqa-dataset-events".to_string(), 2)
+            ],
+            assignments.worker(0)
+        );
+
+        context.rebalance(&Rebalance::Revoke(&topic_partitions(&[0])));
+        assert_eq!(
+            vec![("mqa-dataset-events".to_string(), 2)],
+            assignments.worker(0)
+        );
+
+        context.rebalance(&Rebalance::Revoke(&topic_partitions(&[2])));
+        assert!(assignments.worker(0).is_empty());
+        assert!(!assignments.any_assigned());
+    }
+
+    #[test]
+    fn test_assignments_per_worker() {
+        let assignments = Arc::new(PartitionAssignments::default());
+        let first = WorkerContext::new(0, assignments.clone());
+        let second = WorkerContext::new(1, assignments.clone());
+
+        first.rebalance(&Rebalance::Assign(&topic_partitions(&[0])));
+        second.rebalance(&Rebalance::Assign(&topic_partitions(&[1])));
+        first.rebalance(&Rebalance::Revoke(&topic_partitions(&[0])));
+
+        assert!(assignments.worker(0).is_empty());
+        assert_eq!(
+            vec![("mqa-dataset-events".to_string(), 1)],
+            assignments.worker(1)
+        );
+        assert!(assignments.any_assigned());
+    }
+}
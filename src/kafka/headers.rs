@@ -0,0 +1,140 @@
+use rdkafka::message::{Header, Headers, OwnedHeaders};
+use uuid::Uuid;
+
+use crate::post_processing::SourceContext;
+
+pub const SOURCE: &str = "mqa-source";
+pub const VERSION: &str = "mqa-version";
+pub const CORRELATION_ID: &str = "correlation-id";
+
+/// Headers of dataset events set by the harvester.
+pub const CATALOG_ID: &str = "catalogId";
+pub const HARVEST_ID: &str = "harvestId";
+/// MQA graph of the previous assessment, when not in the event itself.
+pub const PREVIOUS_GRAPH: &str = "previousGraph";
+/// Profile of the reference data a dataset event is assessed with.
+pub const REFERENCE_DATA_PROFILE: &str = "reference-data-profile";
+/// Base IRI of the graph of a dataset event, when not in the event itself.
+pub const BASE_IRI: &str = "baseIri";
+
+pub const SOURCE_VALUE: &str = "property-checker";
+
+/// Returns the value of a header as a string, if present and valid UTF-8.
+pub fn header_value<H: Headers>(headers: Option<&H>, key: &str) -> Option<String> {
+    headers?
+        .iter()
+        .find(|header| header.key == key)
+        .and_then(|header| header.value)
+        .and_then(|value| std::str::from_utf8(value).ok())
+        .map(|value| value.to_string())
+}
+
+/// Catalog, harvest, reference data profile and base IRI of a dataset event,
+/// from its headers when present and not empty.
+pub fn source_context<H: Headers>(headers: Option<&H>) -> SourceContext {
+    let value = |key| header_value(headers, key).filter(|value| !value.trim().is_empty());
+    SourceContext {
+        catalog_id: value(CATALOG_ID),
+        harvest_id: value(HARVEST_ID),
+        reference_data_profile: value(REFERENCE_DATA_PROFILE),
+        base_iri: value(BASE_IRI),
+    }
+}
+
+/// Headers for a produced MQA event, propagating the correlation id of the
+/// incoming message or generating a new one.
+pub fn build_headers(correlation_id: Option<String>) -> OwnedHeaders {
+    let correlation_id = correlation_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    OwnedHeaders::new()
+        .insert(Header {
+            key: SOURCE,
+            value: Some(SOURCE_VALUE),
+        })
+        .insert(Header {
+            key: VERSION,
+            value: Some(env!("CARGO_PKG_VERSION")),
+        })
+        .insert(Header {
+            key: CORRELATION_ID,
+            value: Some(correlation_id.as_str()),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_headers() {
+        let headers = build_headers(Some("abc".to_string()));
+
+        assert_eq!(3, headers.count());
+        assert_eq!(
+            Some(SOURCE_VALUE.to_string()),
+            header_value(Some(&headers), SOURCE)
+        );
+        assert_eq!(
+            Some(env!("CARGO_PKG_VERSION").to_string()),
+            header_value(Some(&headers), VERSION)
+        );
+        assert_eq!(
+            Some("abc".to_string()),
+            header_value(Some(&headers), CORRELATION_ID)
+        );
+    }
+
+    #[test]
+    fn test_build_headers_generates_correlation_id() {
+        let headers = build_headers(None);
+        let correlation_id = header_value(Some(&headers), CORRELATION_ID).unwrap();
+
+        assert!(Uuid::parse_str(&correlation_id).is_ok());
+    }
+
+    #[test]
+    fn test_header_value_missing() {
+        let headers = OwnedHeaders::new().insert(Header {
+            key: "other",
+            value: Some("value"),
+        });
+
+        assert_eq!(None, header_value(Some(&headers), CORRELATION_ID));
+        assert_eq!(None, header_value::<OwnedHeaders>(None, CORRELATION_ID));
+    }
+
+    #[test]
+    fn test_source_context() {
+        let headers = OwnedHeaders::new()
+            .insert(Header {
+                key: CATALOG_ID,
+                value: Some("https://catalog.foo"),
+            })
+            .insert(Header {
+                key: HARVEST_ID,
+                value: Some(""),
+            })
+            .insert(Header {
+                key: REFERENCE_DATA_PROFILE,
+                value: Some("test"),
+            })
+            .insert(Header {
+                key: BASE_IRI,
+                value: Some("https://data.foo/"),
+            });
+
+        assert_eq!(
+            SourceContext {
+                catalog_id: Some("https://catalog.foo".to_string()),
+                harvest_id: None,
+                reference_data_profile: Some("test".to_string()),
+                base_iri: Some("https://data.foo/".to_string()),
+            },
+            source_context(Some(&headers))
+        );
+        assert_eq!(
+            SourceContext::default(),
+            source_context::<OwnedHeaders>(None)
+        );
+    }
+}
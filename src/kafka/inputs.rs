@@ -0,0 +1,144 @@
+use std::{collections::HashSet, env};
+
+use lazy_static::lazy_static;
+
+use crate::{
+    error::Error,
+    kafka::{parse_num_workers, INPUT_TOPIC, NUM_WORKERS},
+};
+
+lazy_static! {
+    pub static ref INPUTS: Vec<InputSpec> = env::var("INPUTS")
+        .map(|v| {
+            parse_input_specs(&v, *NUM_WORKERS).unwrap_or_else(|e| {
+                tracing::error!(error = e.to_string(), "invalid inputs");
+                std::process::exit(1);
+            })
+        })
+        .unwrap_or_else(|_| vec![InputSpec::new(INPUT_TOPIC.as_str(), *NUM_WORKERS)]);
+}
+
+/// Topic consumed by a set of workers of its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputSpec {
+    pub topic: String,
+    pub num_workers: usize,
+}
+
+impl InputSpec {
+    pub fn new(topic: impl Into<String>, num_workers: usize) -> Self {
+        InputSpec {
+            topic: topic.into(),
+            num_workers,
+        }
+    }
+}
+
+/// Comma separated `topic[:workers]` specs, like
+/// `dataset-events:4,dataset-events-priority:1`, with `default_workers`
+/// workers for topics without a number of workers.
+pub fn parse_input_specs(value: &str, default_workers: usize) -> Result<Vec<InputSpec>, Error> {
+    let specs = value
+        .split(',')
+        .map(|spec| parse_input_spec(spec.trim(), default_workers))
+        .collect::<Result<Vec<_>, _>>()?;
+    validate_input_specs(&specs)?;
+    Ok(specs)
+}
+
+/// Specs in the `topic:workers` form parsed by [`parse_input_specs`].
+pub fn format_input_specs(specs: &[InputSpec]) -> String {
+    specs
+        .iter()
+        .map(|spec| format!("{}:{}", spec.topic, spec.num_workers))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn parse_input_spec(spec: &str, default_workers: usize) -> Result<InputSpec, Error> {
+    // Topic names cannot contain ':', so the last one separates the workers.
+    let (topic, num_workers) = match spec.rsplit_once(':') {
+        Some((topic, num_workers)) => (topic, parse_num_workers(num_workers)?),
+        None => (spec, default_workers),
+    };
+    Ok(InputSpec::new(topic, num_workers))
+}
+
+/// Checks that there is at least one input, and that inputs have topics of
+/// their own and workers.
+pub fn validate_input_specs(specs: &[InputSpec]) -> Result<(), Error> {
+    if specs.is_empty() {
+        return Err("no input topics".into());
+    }
+    let mut topics = HashSet::new();
+    for spec in specs {
+        if spec.topic.trim().is_empty() {
+            return Err("input has no topic".into());
+        }
+        if spec.num_workers == 0 {
+            return Err("number of workers must be greater than 0".into());
+        }
+        if !topics.insert(spec.topic.as_str()) {
+            return Err(format!("input topic '{}' is given more than once", spec.topic).into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_input_specs() {
+        assert_eq!(
+            vec![
+                InputSpec::new("dataset-events", 4),
+                InputSpec::new("dataset-events-priority", 1),
+            ],
+            parse_input_specs("dataset-events:4, dataset-events-priority:1", 2).unwrap()
+        );
+        assert_eq!(
+            vec![InputSpec::new("dataset-events", 2)],
+            parse_input_specs("dataset-events", 2).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_format_input_specs() {
+        let specs = vec![
+            InputSpec::new("dataset-events", 4),
+            InputSpec::new("dataset-events-priority", 1),
+        ];
+        assert_eq!(
+            "dataset-events:4,dataset-events-priority:1",
+            format_input_specs(&specs)
+        );
+        assert_eq!(
+            specs,
+            parse_input_specs(&format_input_specs(&specs), 2).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_worker_counts() {
+        assert!(parse_input_specs("dataset-events:0", 2).is_err());
+        assert!(parse_input_specs("dataset-events:-1", 2).is_err());
+        assert!(parse_input_specs("dataset-events:four", 2).is_err());
+        assert!(parse_input_specs("dataset-events:", 2).is_err());
+    }
+
+    #[test]
+    fn test_invalid_topics() {
+        assert_eq!(
+            "input topic 'dataset-events' is given more than once",
+            parse_input_specs("dataset-events:4,dataset-events:1", 2)
+                .unwrap_err()
+                .to_string()
+        );
+        assert!(parse_input_specs("", 2).is_err());
+        assert!(parse_input_specs("dataset-events,", 2).is_err());
+        assert!(parse_input_specs(":4", 2).is_err());
+        assert!(validate_input_specs(&[]).is_err());
+    }
+}
@@ -0,0 +1,235 @@
+use std::env;
+
+use lazy_static::lazy_static;
+use rdkafka::config::ClientConfig;
+
+use crate::error::Error;
+
+lazy_static! {
+    pub static ref CONSUMER_MEMBERSHIP: ConsumerMembership = ConsumerMembership::from_env()
+        .unwrap_or_else(|e| {
+            tracing::error!(error = e.to_string(), "invalid consumer group membership");
+            std::process::exit(1);
+        });
+}
+
+/// Strategy of the group leader when assigning partitions to members.
+/// `cooperative-sticky` uses incremental rebalances, where only moved
+/// partitions are revoked and assigned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssignmentStrategy {
+    Range,
+    RoundRobin,
+    CooperativeSticky,
+}
+
+impl AssignmentStrategy {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AssignmentStrategy::Range => "range",
+            AssignmentStrategy::RoundRobin => "roundrobin",
+            AssignmentStrategy::CooperativeSticky => "cooperative-sticky",
+        }
+    }
+}
+
+/// How workers take part in the consumer group. With a group instance id,
+/// members are static and a restarted worker rejoining within the session
+/// timeout gets its partitions back without a rebalance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsumerMembership {
+    pub group_instance_id: Option<String>,
+    pub session_timeout_ms: u32,
+    pub heartbeat_interval_ms: Option<u32>,
+    pub assignment_strategy: Option<AssignmentStrategy>,
+}
+
+impl Default for ConsumerMembership {
+    fn default() -> Self {
+        ConsumerMembership {
+            group_instance_id: None,
+            session_timeout_ms: 6000,
+            heartbeat_interval_ms: None,
+            assignment_strategy: None,
+        }
+    }
+}
+
+impl ConsumerMembership {
+    fn from_env() -> Result<Self, Error> {
+        let membership = ConsumerMembership {
+            group_instance_id: env::var("GROUP_INSTANCE_ID")
+                .ok()
+                .map(|v| parse_group_instance_id(&v, env::var("HOSTNAME").ok()))
+                .transpose()?,
+            session_timeout_ms: env::var("SESSION_TIMEOUT_MS")
+                .ok()
+                .map(|v| parse_millis("session timeout", &v))
+                .transpose()?
+                .unwrap_or(ConsumerMembership::default().session_timeout_ms),
+            heartbeat_interval_ms: env::var("HEARTBEAT_INTERVAL_MS")
+                .ok()
+                .map(|v| parse_millis("heartbeat interval", &v))
+                .transpose()?,
+            assignment_strategy: env::var("PARTITION_ASSIGNMENT_STRATEGY")
+                .ok()
+                .map(|v| parse_assignment_strategy(&v))
+                .transpose()?,
+        };
+        membership.validate()?;
+        Ok(membership)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        match self.heartbeat_interval_ms {
+            Some(heartbeat) if heartbeat >= self.session_timeout_ms => Err(format!(
+                "heartbeat interval {} ms must be lower than session timeout {} ms",
+                heartbeat, self.session_timeout_ms
+            )
+            .into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets the membership properties of the consumer of a worker. Instance
+    /// ids are suffixed with the worker id, as they must be unique within
+    /// the group.
+    pub fn configure(&self, config: &mut ClientConfig, worker_id: usize) {
+        config.set("session.timeout.ms", self.session_timeout_ms.to_string());
+        if let Some(instance_id) = &self.group_instance_id {
+            config.set(
+                "group.instance.id",
+                format!("{}-{}", instance_id, worker_id),
+            );
+        }
+        if let Some(heartbeat) = self.heartbeat_interval_ms {
+            config.set("heartbeat.interval.ms", heartbeat.to_string());
+        }
+        if let Some(strategy) = self.assignment_strategy {
+            config.set("partition.assignment.strategy", strategy.as_str());
+        }
+    }
+}
+
+/// Group instance id, `auto` for the hostname, which is the pod name when
+/// running in Kubernetes.
+fn parse_group_instance_id(value: &str, hostname: Option<String>) -> Result<String, Error> {
+    match value.trim() {
+        "" => Err("group instance id must not be empty".into()),
+        "auto" => hostname
+            .filter(|hostname| !hostname.is_empty())
+            .ok_or_else(|| "group instance id 'auto' requires HOSTNAME to be set".into()),
+        instance_id => Ok(instance_id.to_string()),
+    }
+}
+
+fn parse_millis(name: &str, value: &str) -> Result<u32, Error> {
+    match value.parse::<u32>() {
+        Ok(0) => Err(format!("{} must be greater than 0", name).into()),
+        Ok(millis) => Ok(millis),
+        Err(e) => Err(format!("invalid {} '{}': {}", name, value, e).into()),
+    }
+}
+
+/// Partition assignment strategy `range`, `roundrobin` or
+/// `cooperative-sticky`.
+fn parse_assignment_strategy(value: &str) -> Result<AssignmentStrategy, Error> {
+    match value {
+        "range" => Ok(AssignmentStrategy::Range),
+        "roundrobin" => Ok(AssignmentStrategy::RoundRobin),
+        "cooperative-sticky" => Ok(AssignmentStrategy::CooperativeSticky),
+        _ => Err(format!(
+            "invalid partition assignment strategy '{}', expected 'range', 'roundrobin' or 'cooperative-sticky'",
+            value
+        )
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configured(membership: &ConsumerMembership, worker_id: usize) -> ClientConfig {
+        let mut config = ClientConfig::new();
+        membership.configure(&mut config, worker_id);
+        config
+    }
+
+    #[test]
+    fn test_default_membership() {
+        let config = configured(&ConsumerMembership::default(), 0);
+
+        assert_eq!(Some("6000"), config.get("session.timeout.ms"));
+        assert_eq!(None, config.get("group.instance.id"));
+        assert_eq!(None, config.get("heartbeat.interval.ms"));
+        assert_eq!(None, config.get("partition.assignment.strategy"));
+    }
+
+    #[test]
+    fn test_static_membership() {
+        let membership = ConsumerMembership {
+            group_instance_id: Some("fdk-mqa-property-checker-7d9f".to_string()),
+            session_timeout_ms: 45000,
+            heartbeat_interval_ms: Some(3000),
+            assignment_strategy: Some(AssignmentStrategy::CooperativeSticky),
+        };
+        assert!(membership.validate().is_ok());
+
+        let config = configured(&membership, 2);
+        assert_eq!(Some("45000"), config.get("session.timeout.ms"));
+        assert_eq!(
+            Some("fdk-mqa-property-checker-7d9f-2"),
+            config.get("group.instance.id")
+        );
+        assert_eq!(Some("3000"), config.get("heartbeat.interval.ms"));
+        assert_eq!(
+            Some("cooperative-sticky"),
+            config.get("partition.assignment.strategy")
+        );
+    }
+
+    #[test]
+    fn test_parse_group_instance_id() {
+        assert_eq!(
+            "pod-0",
+            parse_group_instance_id("auto", Some("pod-0".to_string())).unwrap()
+        );
+        assert_eq!(
+            "checker",
+            parse_group_instance_id("checker", Some("pod-0".to_string())).unwrap()
+        );
+        assert!(parse_group_instance_id("auto", None).is_err());
+        assert!(parse_group_instance_id("auto", Some(String::new())).is_err());
+        assert!(parse_group_instance_id(" ", None).is_err());
+    }
+
+    #[test]
+    fn test_parse_assignment_strategy() {
+        assert_eq!(
+            AssignmentStrategy::Range,
+            parse_assignment_strategy("range").unwrap()
+        );
+        assert_eq!(
+            AssignmentStrategy::RoundRobin,
+            parse_assignment_strategy("roundrobin").unwrap()
+        );
+        assert_eq!(
+            AssignmentStrategy::CooperativeSticky,
+            parse_assignment_strategy("cooperative-sticky").unwrap()
+        );
+        assert!(parse_assignment_strategy("sticky").is_err());
+        assert!(parse_assignment_strategy("Range").is_err());
+    }
+
+    #[test]
+    fn test_heartbeat_must_be_lower_than_session_timeout() {
+        let membership = ConsumerMembership {
+            heartbeat_interval_ms: Some(6000),
+            ..Default::default()
+        };
+        assert!(membership.validate().is_err());
+        assert!(parse_millis("session timeout", "0").is_err());
+        assert!(parse_millis("session timeout", "6s").is_err());
+    }
+}
@@ -0,0 +1,1105 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use futures::{future::BoxFuture, stream::FuturesUnordered, FutureExt, StreamExt};
+use oxigraph::store::Store;
+use rdkafka::{
+    config::ClientConfig,
+    consumer::{stream_consumer::StreamConsumer, Consumer},
+    error::KafkaError,
+    message::{OwnedHeaders, OwnedMessage},
+    producer::{FutureProducer, FutureRecord},
+    Message,
+};
+use tokio::sync::watch;
+use tracing::{Instrument, Level};
+
+use crate::{
+    error::Error,
+    kafka::{
+        context::{WorkerContext, PARTITION_ASSIGNMENTS},
+        create_sr_settings,
+        inputs::{validate_input_specs, InputSpec, INPUTS},
+        membership::CONSUMER_MEMBERSHIP,
+        producer::{send_and_confirm, RecordSender, DELIVERY_POLICY},
+        seek::SEEK_TO_TIMESTAMP,
+        unchanged::AssessedGraph,
+        PropertyChecker, AUTO_OFFSET_RESET, BROKERS, DRY_RUN, GROUP_ID, LAG_WARNING_THRESHOLD,
+        NUM_WORKERS,
+    },
+    metrics::GraphStats,
+    prometheus_metrics::{
+        CONSUMER_LAG, LAST_PROCESSED_OFFSET, PROCESSED_MESSAGES, PROCESSING_ERRORS, PROCESSING_TIME,
+    },
+    store_pool::{StorePool, GRAPH_QUADS_WARNING_THRESHOLD, STORE_RECYCLE_INTERVAL},
+    supervisor::{supervise, RestartPolicy, RESTART_POLICY},
+};
+
+/// How often each worker fetches partition watermarks to update the consumer lag.
+const LAG_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+pub type WorkerConsumer = StreamConsumer<WorkerContext>;
+
+/// Record to produce for a handled message.
+#[derive(Debug, Clone)]
+pub struct OutputRecord {
+    pub topic: String,
+    pub key: String,
+    pub payload: Vec<u8>,
+    pub headers: OwnedHeaders,
+}
+
+/// Outcome of handling a message: the records to produce, and the stats of
+/// its graph and MQA graphs when it was assessed.
+#[derive(Debug, Default)]
+pub struct Handled {
+    pub records: Vec<OutputRecord>,
+    pub stats: Option<GraphStats>,
+    /// Graph recorded as the last assessed for its fdkId once the records
+    /// are delivered.
+    pub assessed_graph: Option<AssessedGraph>,
+}
+
+/// Handles the messages of a worker, with the input and output stores the
+/// worker reuses for every message. The stores are emptied by the worker
+/// after each message.
+pub trait MessageHandler: Send {
+    fn handle<'a>(
+        &'a mut self,
+        message: &'a OwnedMessage,
+        input_store: &'a Store,
+        output_store: &'a Store,
+    ) -> BoxFuture<'a, Result<Handled, Error>>;
+}
+
+/// Consumer of the messages of a worker.
+pub trait MessageSource {
+    /// Receives the next message.
+    fn receive(&self) -> BoxFuture<'_, Result<OwnedMessage, KafkaError>>;
+
+    /// Stores the offset of a handled message, committed with the next
+    /// automatic commit.
+    fn store_offset_of(&self, message: &OwnedMessage) -> Result<(), KafkaError>;
+
+    /// High watermark of a partition of a topic.
+    fn high_watermark(&self, topic: &str, partition: i32) -> Result<i64, KafkaError>;
+}
+
+impl MessageSource for WorkerConsumer {
+    fn receive(&self) -> BoxFuture<'_, Result<OwnedMessage, KafkaError>> {
+        async move { self.recv().await.map(|message| message.detach()) }.boxed()
+    }
+
+    fn store_offset_of(&self, message: &OwnedMessage) -> Result<(), KafkaError> {
+        self.store_offset(message.topic(), message.partition(), message.offset())
+    }
+
+    fn high_watermark(&self, topic: &str, partition: i32) -> Result<i64, KafkaError> {
+        self.fetch_watermarks(topic, partition, Duration::from_secs(1))
+            .map(|(_, high_watermark)| high_watermark)
+    }
+}
+
+/// Makes the handler of a worker, given its id.
+pub type PropertyCheckerFactory = fn(usize) -> Result<PropertyChecker, Error>;
+
+fn property_checker(_worker_id: usize) -> Result<PropertyChecker, Error> {
+    Ok(PropertyChecker::new(create_sr_settings()?))
+}
+
+/// Workers consuming messages from the input topics, each topic with workers
+/// of its own, handling each message with a handler of their own and
+/// producing the resulting records. Offsets are stored once the records of a
+/// message are delivered.
+pub struct ProcessingPipeline<F = PropertyCheckerFactory> {
+    brokers: String,
+    group_id: String,
+    inputs: Vec<InputSpec>,
+    dry_run: bool,
+    restart_policy: RestartPolicy,
+    handler: F,
+}
+
+impl ProcessingPipeline {
+    /// Builder of a pipeline configured by the environment, handling messages
+    /// with the property checker.
+    pub fn builder() -> ProcessingPipelineBuilder {
+        ProcessingPipelineBuilder {
+            brokers: BROKERS.clone(),
+            group_id: GROUP_ID.clone(),
+            inputs: INPUTS.clone(),
+            dry_run: *DRY_RUN,
+            restart_policy: RESTART_POLICY.clone(),
+            handler: property_checker,
+        }
+    }
+}
+
+impl<F, H> ProcessingPipeline<F>
+where
+    F: Fn(usize) -> Result<H, Error> + Send + Sync + 'static,
+    H: MessageHandler + 'static,
+{
+    /// Runs the workers until `shutdown` is set or its sender dropped,
+    /// restarting failed workers according to the restart policy. Returns an
+    /// error once a worker is not to be restarted.
+    pub async fn run(self, shutdown: watch::Receiver<bool>) -> Result<(), Error> {
+        let pipeline = Arc::new(self);
+        let mut workers = pipeline
+            .worker_topics()
+            .into_iter()
+            .enumerate()
+            .map(|(worker_id, topic)| {
+                let pipeline = pipeline.clone();
+                let shutdown = shutdown.clone();
+                tokio::spawn(async move {
+                    supervise(worker_id, &pipeline.restart_policy, || {
+                        pipeline
+                            .clone()
+                            .run_worker(worker_id, topic.clone(), shutdown.clone())
+                    })
+                    .await
+                })
+            })
+            .collect::<FuturesUnordered<_>>();
+
+        while let Some(result) = workers.next().await {
+            result.map_err(|e| Error::from(e.to_string()))??;
+        }
+        Ok(())
+    }
+
+    /// Input topic of each worker, in order of worker ids. Worker ids are
+    /// unique across the inputs, as they identify the worker in the consumer
+    /// group and in metrics.
+    fn worker_topics(&self) -> Vec<String> {
+        self.inputs
+            .iter()
+            .flat_map(|input| std::iter::repeat_n(input.topic.clone(), input.num_workers))
+            .collect()
+    }
+
+    /// Creates the resources of a worker and handles its messages.
+    async fn run_worker(
+        self: Arc<Self>,
+        worker_id: usize,
+        input_topic: String,
+        shutdown: watch::Receiver<bool>,
+    ) -> Result<(), Error> {
+        tracing::info!(
+            worker_id,
+            input_topic,
+            dry_run = self.dry_run,
+            "starting worker"
+        );
+
+        let consumer = create_consumer(&self.brokers, &self.group_id, &input_topic, worker_id)?;
+        let producer = create_producer(&self.brokers)?;
+        let handler = (self.handler)(worker_id)?;
+        let store_pool = StorePool::new(
+            worker_id,
+            *STORE_RECYCLE_INTERVAL,
+            *GRAPH_QUADS_WARNING_THRESHOLD,
+        )?;
+
+        tracing::info!(worker_id, "listening for messages");
+        process_messages(
+            &consumer,
+            &producer,
+            handler,
+            store_pool,
+            self.dry_run,
+            shutdown,
+        )
+        .await
+    }
+}
+
+/// Builder of a [`ProcessingPipeline`], defaulting to the configuration of the
+/// environment.
+pub struct ProcessingPipelineBuilder<F = PropertyCheckerFactory> {
+    brokers: String,
+    group_id: String,
+    inputs: Vec<InputSpec>,
+    dry_run: bool,
+    restart_policy: RestartPolicy,
+    handler: F,
+}
+
+impl<F> ProcessingPipelineBuilder<F> {
+    /// Comma separated list of Kafka brokers.
+    pub fn brokers(mut self, brokers: impl Into<String>) -> Self {
+        self.brokers = brokers.into();
+        self
+    }
+
+    pub fn group_id(mut self, group_id: impl Into<String>) -> Self {
+        self.group_id = group_id.into();
+        self
+    }
+
+    /// Consume a single input topic, with the number of workers of the first
+    /// input.
+    pub fn input_topic(mut self, input_topic: impl Into<String>) -> Self {
+        let num_workers = self
+            .inputs
+            .first()
+            .map_or(*NUM_WORKERS, |input| input.num_workers);
+        self.inputs = vec![InputSpec::new(input_topic, num_workers)];
+        self
+    }
+
+    /// Number of workers of each input topic.
+    pub fn num_workers(mut self, num_workers: usize) -> Self {
+        for input in &mut self.inputs {
+            input.num_workers = num_workers;
+        }
+        self
+    }
+
+    /// Input topics, each consumed by workers of its own.
+    pub fn inputs(mut self, inputs: Vec<InputSpec>) -> Self {
+        self.inputs = inputs;
+        self
+    }
+
+    /// Handle messages without producing records, committing offsets in a
+    /// consumer group of their own.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn restart_policy(mut self, restart_policy: RestartPolicy) -> Self {
+        self.restart_policy = restart_policy;
+        self
+    }
+
+    /// Factory of the handlers of the workers, called with the id of each
+    /// worker when it is started or restarted. The property checker is made
+    /// with [`PropertyChecker::new`], to be replaced or wrapped.
+    pub fn handler<G, H>(self, handler: G) -> ProcessingPipelineBuilder<G>
+    where
+        G: Fn(usize) -> Result<H, Error> + Send + Sync + 'static,
+        H: MessageHandler + 'static,
+    {
+        ProcessingPipelineBuilder {
+            brokers: self.brokers,
+            group_id: self.group_id,
+            inputs: self.inputs,
+            dry_run: self.dry_run,
+            restart_policy: self.restart_policy,
+            handler,
+        }
+    }
+
+    pub fn build(self) -> Result<ProcessingPipeline<F>, Error> {
+        if self.brokers.trim().is_empty() {
+            return Err("pipeline has no brokers".into());
+        }
+        validate_input_specs(&self.inputs)?;
+        Ok(ProcessingPipeline {
+            group_id: consumer_group_id(&self.group_id, self.dry_run),
+            brokers: self.brokers,
+            inputs: self.inputs,
+            dry_run: self.dry_run,
+            restart_policy: self.restart_policy,
+            handler: self.handler,
+        })
+    }
+}
+
+/// Consumer group of the workers. Dry runs commit their offsets in a group of
+/// their own, leaving the offsets of the regular deployment untouched.
+fn consumer_group_id(group_id: &str, dry_run: bool) -> String {
+    if dry_run {
+        format!("{}-dry-run", group_id)
+    } else {
+        group_id.to_string()
+    }
+}
+
+pub fn create_consumer(
+    brokers: &str,
+    group_id: &str,
+    input_topic: &str,
+    worker_id: usize,
+) -> Result<WorkerConsumer, KafkaError> {
+    let mut config = ClientConfig::new();
+    config
+        .set("group.id", group_id)
+        .set("bootstrap.servers", brokers)
+        .set("enable.partition.eof", "false")
+        .set("enable.auto.commit", "true")
+        .set("enable.auto.offset.store", "false")
+        .set("auto.offset.reset", *AUTO_OFFSET_RESET)
+        .set("api.version.request", "false")
+        .set("security.protocol", "plaintext")
+        .set("max.partition.fetch.bytes", "2097152");
+    CONSUMER_MEMBERSHIP.configure(&mut config, worker_id);
+    let consumer: WorkerConsumer = config.create_with_context(
+        WorkerContext::new(worker_id, PARTITION_ASSIGNMENTS.clone())
+            .with_seek_to_timestamp(*SEEK_TO_TIMESTAMP),
+    )?;
+    consumer.subscribe(&[input_topic])?;
+    Ok(consumer)
+}
+
+pub fn create_producer(brokers: &str) -> Result<FutureProducer, KafkaError> {
+    ClientConfig::new()
+        .set("bootstrap.servers", brokers)
+        .set(
+            "message.timeout.ms",
+            DELIVERY_POLICY.timeout.as_millis().to_string(),
+        )
+        .set("compression.type", "snappy")
+        .create()
+}
+
+/// Runs the event loop of a worker until `shutdown` is set or its sender
+/// dropped. The event loop will:
+///   1) receive a message from the source.
+///   2) handle it with the handler of the worker.
+///   3) produce the records of the handler and wait for their delivery.
+///   4) store the offset of the message, and empty the stores.
+///
+/// In dry-run mode messages are handled, but nothing is produced.
+pub async fn process_messages<S, P, H>(
+    source: &S,
+    producer: &P,
+    mut handler: H,
+    mut store_pool: StorePool,
+    dry_run: bool,
+    mut shutdown: watch::Receiver<bool>,
+) -> Result<(), Error>
+where
+    S: MessageSource,
+    P: RecordSender,
+    H: MessageHandler,
+{
+    let mut lag_checks: HashMap<i32, Instant> = HashMap::new();
+
+    loop {
+        if *shutdown.borrow_and_update() {
+            return Ok(());
+        }
+        let message = tokio::select! {
+            message = source.receive() => message?,
+            _ = shutdown.changed() => return Ok(()),
+        };
+        let span = tracing::span!(
+            Level::INFO,
+            "message",
+            topic = message.topic(),
+            partition = message.partition(),
+            offset = message.offset(),
+            timestamp = message.timestamp().to_millis(),
+        );
+
+        process_message(
+            source,
+            producer,
+            &mut handler,
+            &store_pool,
+            &message,
+            dry_run,
+        )
+        .instrument(span)
+        .await?;
+        store_pool.release()?;
+        debug_assert!(
+            store_pool.input().is_empty()? && store_pool.output().is_empty()?,
+            "stores not empty after handling a message"
+        );
+
+        let partition = message.partition();
+        LAST_PROCESSED_OFFSET
+            .with_label_values(&[message.topic(), &partition.to_string()])
+            .set(message.offset());
+        if lag_checks
+            .get(&partition)
+            .is_none_or(|checked| checked.elapsed() >= LAG_CHECK_INTERVAL)
+        {
+            update_consumer_lag(source, message.topic(), partition, message.offset());
+            lag_checks.insert(partition, Instant::now());
+        }
+    }
+}
+
+/// Number of messages left in a partition after the message at `offset`.
+fn consumer_lag(high_watermark: i64, offset: i64) -> i64 {
+    (high_watermark - offset - 1).max(0)
+}
+
+fn update_consumer_lag(source: &impl MessageSource, topic: &str, partition: i32, offset: i64) {
+    match source.high_watermark(topic, partition) {
+        Ok(high_watermark) => {
+            let lag = consumer_lag(high_watermark, offset);
+            CONSUMER_LAG
+                .with_label_values(&[topic, &partition.to_string()])
+                .set(lag);
+            if lag > *LAG_WARNING_THRESHOLD {
+                tracing::warn!(
+                    topic,
+                    partition,
+                    lag,
+                    threshold = *LAG_WARNING_THRESHOLD,
+                    "consumer lag exceeds threshold"
+                );
+            }
+        }
+        Err(e) => tracing::warn!(
+            topic,
+            partition,
+            error = e.to_string(),
+            "failed to fetch watermarks"
+        ),
+    }
+}
+
+async fn process_message<S, P, H>(
+    source: &S,
+    producer: &P,
+    handler: &mut H,
+    store_pool: &StorePool,
+    message: &OwnedMessage,
+    dry_run: bool,
+) -> Result<(), Error>
+where
+    S: MessageSource,
+    P: RecordSender,
+    H: MessageHandler,
+{
+    let start_time = Instant::now();
+    let (result, stats) =
+        match handle_and_publish(producer, handler, store_pool, message, dry_run).await {
+            Ok(stats) => (Ok(()), stats),
+            Err(e) => (Err(e), None),
+        };
+    let elapsed_millis = start_time.elapsed().as_millis();
+    record_result(message.topic(), &result, stats, elapsed_millis, dry_run);
+    PROCESSING_TIME
+        .with_label_values(&[message.topic()])
+        .observe(elapsed_millis as f64 / 1000.0);
+    // The offset of a message whose records were not delivered is never
+    // committed. The worker fails instead, and consumes the message again
+    // from the last committed offset when restarted.
+    if let Err(e @ Error::DeliveryFailed(_)) = result {
+        return Err(e);
+    }
+    if let Err(e) = source.store_offset_of(message) {
+        tracing::warn!(error = e.to_string(), "failed to store offset");
+    };
+    Ok(())
+}
+
+/// Handles a message and produces its records, returning the stats of its
+/// graph and MQA graphs, or none when it was not assessed.
+async fn handle_and_publish<P: RecordSender, H: MessageHandler>(
+    producer: &P,
+    handler: &mut H,
+    store_pool: &StorePool,
+    message: &OwnedMessage,
+    dry_run: bool,
+) -> Result<Option<GraphStats>, Error> {
+    let handled = handler
+        .handle(message, store_pool.input(), store_pool.output())
+        .await?;
+    for record in handled.records {
+        publish(
+            producer,
+            output_record(&record.topic, &record.key, &record.payload, record.headers),
+            dry_run,
+        )
+        .await?;
+    }
+    if let Some(assessed_graph) = handled.assessed_graph {
+        assessed_graph.record();
+    }
+    Ok(handled.stats)
+}
+
+/// Logs the outcome of handling a message, with the stats of its graphs when
+/// assessed, and counts it.
+fn record_result(
+    topic: &str,
+    result: &Result<(), Error>,
+    stats: Option<GraphStats>,
+    elapsed_millis: u128,
+    dry_run: bool,
+) {
+    let dry_run_label = dry_run.to_string();
+    match result {
+        Ok(_) => {
+            tracing::info!(
+                elapsed_millis,
+                input_quads = stats.map(|stats| stats.input_quads),
+                output_quads = stats.map(|stats| stats.output_quads),
+                "message handled successfully"
+            );
+            PROCESSED_MESSAGES
+                .with_label_values(&[topic, "success", &dry_run_label])
+                .inc();
+        }
+        Err(e) => {
+            tracing::error!(
+                elapsed_millis,
+                error = e.to_string(),
+                error_code = e.code(),
+                "failed while handling message"
+            );
+            PROCESSED_MESSAGES
+                .with_label_values(&[topic, "error", &dry_run_label])
+                .inc();
+            PROCESSING_ERRORS
+                .with_label_values(&[topic, e.code(), &dry_run_label])
+                .inc();
+        }
+    };
+}
+
+/// Output record keyed by fdkId, so the topic can be compacted and
+/// partitioned by dataset.
+fn output_record<'a>(
+    topic: &'a str,
+    key: &'a String,
+    payload: &'a Vec<u8>,
+    headers: OwnedHeaders,
+) -> FutureRecord<'a, String, Vec<u8>> {
+    FutureRecord::to(topic)
+        .key(key)
+        .payload(payload)
+        .headers(headers)
+}
+
+/// Sends a record to its topic and waits for its delivery, or only logs its
+/// size in dry-run mode.
+async fn publish<P: RecordSender>(
+    producer: &P,
+    record: FutureRecord<'_, String, Vec<u8>>,
+    dry_run: bool,
+) -> Result<(), Error> {
+    if dry_run {
+        tracing::debug!(
+            bytes = record
+                .payload
+                .map(|payload| payload.len())
+                .unwrap_or_default(),
+            "dry run, skipping produce"
+        );
+        return Ok(());
+    }
+    send_and_confirm(producer, record, &DELIVERY_POLICY).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::VecDeque, sync::Mutex};
+
+    use futures::future;
+    use rdkafka::{producer::Producer, Timestamp};
+
+    use super::*;
+    use crate::kafka::{
+        headers::{self, build_headers, header_value},
+        unchanged::{SkipUnchanged, UnchangedGraphs},
+        INPUT_TOPIC, OUTPUT_TOPIC,
+    };
+
+    /// Messages received in order, signalling shutdown once all are received.
+    struct MockSource {
+        messages: Mutex<VecDeque<OwnedMessage>>,
+        stored_offsets: Mutex<Vec<i64>>,
+        shutdown: watch::Sender<bool>,
+    }
+
+    impl MockSource {
+        fn new(payloads: &[&str]) -> (Self, watch::Receiver<bool>) {
+            MockSource::with_topic("mqa-dataset-events", payloads)
+        }
+
+        fn with_topic(topic: &str, payloads: &[&str]) -> (Self, watch::Receiver<bool>) {
+            let (shutdown, shutdown_rx) = watch::channel(false);
+            let messages = payloads
+                .iter()
+                .enumerate()
+                .map(|(offset, payload)| {
+                    OwnedMessage::new(
+                        Some(payload.as_bytes().to_vec()),
+                        None,
+                        topic.to_string(),
+                        Timestamp::NotAvailable,
+                        0,
+                        offset as i64,
+                        None,
+                    )
+                })
+                .collect();
+            let source = MockSource {
+                messages: Mutex::new(messages),
+                stored_offsets: Mutex::new(Vec::new()),
+                shutdown,
+            };
+            (source, shutdown_rx)
+        }
+
+        fn stored_offsets(&self) -> Vec<i64> {
+            self.stored_offsets.lock().unwrap().clone()
+        }
+    }
+
+    impl MessageSource for MockSource {
+        fn receive(&self) -> BoxFuture<'_, Result<OwnedMessage, KafkaError>> {
+            match self.messages.lock().unwrap().pop_front() {
+                Some(message) => future::ready(Ok(message)).boxed(),
+                None => {
+                    self.shutdown.send_replace(true);
+                    future::pending().boxed()
+                }
+            }
+        }
+
+        fn store_offset_of(&self, message: &OwnedMessage) -> Result<(), KafkaError> {
+            self.stored_offsets.lock().unwrap().push(message.offset());
+            Ok(())
+        }
+
+        fn high_watermark(&self, _topic: &str, _partition: i32) -> Result<i64, KafkaError> {
+            Ok(0)
+        }
+    }
+
+    /// Delivers records at once, except those with payload `UNDELIVERABLE`
+    /// and the first `failures` records.
+    #[derive(Default)]
+    struct MockSender {
+        delivered: Mutex<Vec<(String, Vec<u8>)>>,
+        failures: Mutex<usize>,
+    }
+
+    impl MockSender {
+        fn delivered_payloads(&self) -> Vec<String> {
+            self.delivered
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(_, payload)| String::from_utf8(payload.clone()).unwrap())
+                .collect()
+        }
+    }
+
+    impl RecordSender for MockSender {
+        fn send_record<'a>(
+            &self,
+            record: FutureRecord<'a, String, Vec<u8>>,
+        ) -> Result<
+            BoxFuture<'static, Result<(), KafkaError>>,
+            (KafkaError, FutureRecord<'a, String, Vec<u8>>),
+        > {
+            let payload = record.payload.cloned().unwrap_or_default();
+            let mut failures = self.failures.lock().unwrap();
+            if payload == b"UNDELIVERABLE" || *failures > 0 {
+                *failures = failures.saturating_sub(1);
+                return Ok(future::ready(Err(KafkaError::Canceled)).boxed());
+            }
+            self.delivered
+                .lock()
+                .unwrap()
+                .push((record.topic.to_string(), payload));
+            Ok(future::ready(Ok(())).boxed())
+        }
+    }
+
+    /// Produces the payload of each message in upper case, failing for
+    /// messages with payload `invalid`.
+    struct UppercaseHandler;
+
+    impl MessageHandler for UppercaseHandler {
+        fn handle<'a>(
+            &'a mut self,
+            message: &'a OwnedMessage,
+            _input_store: &'a Store,
+            _output_store: &'a Store,
+        ) -> BoxFuture<'a, Result<Handled, Error>> {
+            let payload = message.payload().unwrap_or_default().to_ascii_uppercase();
+            let result: Result<Handled, Error> = if payload == b"INVALID" {
+                Err("invalid message".into())
+            } else {
+                Ok(Handled {
+                    records: vec![OutputRecord {
+                        topic: "uppercase-events".to_string(),
+                        key: message.offset().to_string(),
+                        payload,
+                        headers: build_headers(None),
+                    }],
+                    stats: None,
+                    assessed_graph: None,
+                })
+            };
+            future::ready(result).boxed()
+        }
+    }
+
+    /// Produces the payload of each message in upper case, unless it is the
+    /// payload last recorded as unchanged.
+    struct UnchangedHandler(Arc<UnchangedGraphs>);
+
+    impl MessageHandler for UnchangedHandler {
+        fn handle<'a>(
+            &'a mut self,
+            message: &'a OwnedMessage,
+            _input_store: &'a Store,
+            _output_store: &'a Store,
+        ) -> BoxFuture<'a, Result<Handled, Error>> {
+            let payload = message.payload().unwrap_or_default();
+            let hash = String::from_utf8(payload.to_vec()).unwrap();
+            if self.0.unchanged("fdk-id", &hash).is_some() {
+                return future::ready(Ok(Handled::default())).boxed();
+            }
+            let handled = Handled {
+                records: vec![OutputRecord {
+                    topic: "uppercase-events".to_string(),
+                    key: "fdk-id".to_string(),
+                    payload: payload.to_ascii_uppercase(),
+                    headers: build_headers(None),
+                }],
+                stats: None,
+                assessed_graph: Some(AssessedGraph::new(
+                    self.0.clone(),
+                    "fdk-id".to_string(),
+                    hash,
+                    Vec::new(),
+                )),
+            };
+            future::ready(Ok(handled)).boxed()
+        }
+    }
+
+    /// Counts the messages handled by the handler it wraps.
+    struct Counting<H> {
+        inner: H,
+        handled: Arc<Mutex<usize>>,
+    }
+
+    impl<H: MessageHandler> MessageHandler for Counting<H> {
+        fn handle<'a>(
+            &'a mut self,
+            message: &'a OwnedMessage,
+            input_store: &'a Store,
+            output_store: &'a Store,
+        ) -> BoxFuture<'a, Result<Handled, Error>> {
+            *self.handled.lock().unwrap() += 1;
+            self.inner.handle(message, input_store, output_store)
+        }
+    }
+
+    fn store_pool() -> StorePool {
+        StorePool::new(0, None, 1000).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_messages_handled_by_custom_handler() {
+        let (source, shutdown) = MockSource::new(&["foo", "invalid", "bar"]);
+        let sender = MockSender::default();
+        let handled = Arc::new(Mutex::new(0));
+        let handler = Counting {
+            inner: UppercaseHandler,
+            handled: handled.clone(),
+        };
+
+        let result =
+            process_messages(&source, &sender, handler, store_pool(), false, shutdown).await;
+
+        assert!(result.is_ok());
+        assert_eq!(3, *handled.lock().unwrap());
+        assert_eq!(vec!["FOO", "BAR"], sender.delivered_payloads());
+        assert_eq!(
+            "uppercase-events",
+            sender.delivered.lock().unwrap()[0].0.as_str()
+        );
+        // Messages that failed to be handled are not consumed again.
+        assert_eq!(vec![0, 1, 2], source.stored_offsets());
+    }
+
+    #[tokio::test]
+    async fn test_undelivered_message_fails_worker() {
+        let (source, shutdown) = MockSource::new(&["foo", "undeliverable", "bar"]);
+        let sender = MockSender::default();
+
+        let result = process_messages(
+            &source,
+            &sender,
+            UppercaseHandler,
+            store_pool(),
+            false,
+            shutdown,
+        )
+        .await;
+
+        assert!(matches!(result, Err(Error::DeliveryFailed(_))));
+        assert_eq!(vec!["FOO"], sender.delivered_payloads());
+        assert_eq!(vec![0], source.stored_offsets());
+    }
+
+    #[tokio::test]
+    async fn test_undelivered_message_not_skipped_as_unchanged() {
+        let graphs = Arc::new(UnchangedGraphs::new(SkipUnchanged::Silent, 10));
+        let sender = MockSender {
+            failures: Mutex::new(1),
+            ..Default::default()
+        };
+
+        let (source, shutdown) = MockSource::new(&["foo"]);
+        let result = process_messages(
+            &source,
+            &sender,
+            UnchangedHandler(graphs.clone()),
+            store_pool(),
+            false,
+            shutdown,
+        )
+        .await;
+        assert!(matches!(result, Err(Error::DeliveryFailed(_))));
+        assert!(graphs.is_empty());
+
+        // The restarted worker consumes the message again.
+        let (source, shutdown) = MockSource::new(&["foo", "foo"]);
+        let result = process_messages(
+            &source,
+            &sender,
+            UnchangedHandler(graphs.clone()),
+            store_pool(),
+            false,
+            shutdown,
+        )
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(vec!["FOO"], sender.delivered_payloads());
+        assert_eq!(vec![0, 1], source.stored_offsets());
+        assert_eq!(1, graphs.len());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_pipeline_does_not_produce() {
+        let (source, shutdown) = MockSource::new(&["foo", "undeliverable"]);
+        let sender = MockSender::default();
+
+        let result = process_messages(
+            &source,
+            &sender,
+            UppercaseHandler,
+            store_pool(),
+            true,
+            shutdown,
+        )
+        .await;
+
+        assert!(result.is_ok());
+        assert!(sender.delivered_payloads().is_empty());
+        assert_eq!(vec![0, 1], source.stored_offsets());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_stops_worker() {
+        let (source, shutdown) = MockSource::new(&["foo"]);
+        source.shutdown.send_replace(true);
+        let sender = MockSender::default();
+
+        let result = process_messages(
+            &source,
+            &sender,
+            UppercaseHandler,
+            store_pool(),
+            false,
+            shutdown,
+        )
+        .await;
+
+        assert!(result.is_ok());
+        assert!(source.stored_offsets().is_empty());
+    }
+
+    #[test]
+    fn test_build_pipeline() {
+        let pipeline = ProcessingPipeline::builder()
+            .brokers("kafka-0:9092")
+            .group_id("embedding-service")
+            .input_topic("dataset-events")
+            .num_workers(2)
+            .dry_run(true)
+            .handler(|_| Ok(UppercaseHandler))
+            .build()
+            .unwrap();
+        assert_eq!("kafka-0:9092", pipeline.brokers);
+        assert_eq!("embedding-service-dry-run", pipeline.group_id);
+        assert_eq!(vec![InputSpec::new("dataset-events", 2)], pipeline.inputs);
+
+        assert!(ProcessingPipeline::builder().brokers("").build().is_err());
+        assert!(ProcessingPipeline::builder()
+            .input_topic(" ")
+            .build()
+            .is_err());
+        assert!(ProcessingPipeline::builder()
+            .num_workers(0)
+            .build()
+            .is_err());
+        assert!(ProcessingPipeline::builder()
+            .inputs(Vec::new())
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_workers_of_inputs() {
+        let pipeline = ProcessingPipeline::builder()
+            .inputs(vec![
+                InputSpec::new("dataset-events", 2),
+                InputSpec::new("dataset-events-priority", 1),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(
+            vec![
+                "dataset-events",
+                "dataset-events",
+                "dataset-events-priority"
+            ],
+            pipeline.worker_topics()
+        );
+
+        assert!(ProcessingPipeline::builder()
+            .inputs(vec![
+                InputSpec::new("dataset-events", 2),
+                InputSpec::new("dataset-events", 1),
+            ])
+            .build()
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_metrics_labeled_with_input_topic() {
+        let processed = |topic: &str, status: &str| {
+            PROCESSED_MESSAGES
+                .with_label_values(&[topic, status, "false"])
+                .get()
+        };
+        let errors = |topic: &str| {
+            PROCESSING_ERRORS
+                .with_label_values(&[topic, "other", "false"])
+                .get()
+        };
+
+        for (topic, payloads) in [
+            ("labeled-events", vec!["foo", "bar"]),
+            ("labeled-events-priority", vec!["invalid"]),
+        ] {
+            let (source, shutdown) = MockSource::with_topic(topic, &payloads);
+            process_messages(
+                &source,
+                &MockSender::default(),
+                UppercaseHandler,
+                store_pool(),
+                false,
+                shutdown,
+            )
+            .await
+            .unwrap();
+        }
+
+        assert_eq!(2, processed("labeled-events", "success"));
+        assert_eq!(0, processed("labeled-events", "error"));
+        assert_eq!(0, processed("labeled-events-priority", "success"));
+        assert_eq!(1, processed("labeled-events-priority", "error"));
+        assert_eq!(0, errors("labeled-events"));
+        assert_eq!(1, errors("labeled-events-priority"));
+        assert_eq!(
+            2,
+            PROCESSING_TIME
+                .with_label_values(&["labeled-events"])
+                .get_sample_count()
+        );
+        assert_eq!(
+            1,
+            LAST_PROCESSED_OFFSET
+                .with_label_values(&["labeled-events", "0"])
+                .get()
+        );
+    }
+
+    #[test]
+    fn test_consumer_lag() {
+        assert_eq!(0, consumer_lag(100, 99));
+        assert_eq!(10, consumer_lag(100, 89));
+        assert_eq!(99, consumer_lag(100, 0));
+    }
+
+    #[test]
+    fn test_consumer_lag_empty_partition() {
+        assert_eq!(0, consumer_lag(0, 0));
+        assert_eq!(0, consumer_lag(42, 42));
+    }
+
+    #[test]
+    fn test_consumer_group_id() {
+        assert_eq!(
+            "fdk-mqa-property-checker",
+            consumer_group_id("fdk-mqa-property-checker", false)
+        );
+        assert_eq!(
+            "fdk-mqa-property-checker-dry-run",
+            consumer_group_id("fdk-mqa-property-checker", true)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_does_not_produce() {
+        let producer = create_producer(&BROKERS).unwrap();
+        let processed = || {
+            PROCESSED_MESSAGES
+                .with_label_values(&[INPUT_TOPIC.as_str(), "success", "true"])
+                .get()
+        };
+        let before = processed();
+
+        let key = "fdk-id".to_string();
+        let payload = b"<a> <b> <c> .".to_vec();
+        let result = publish(
+            &producer,
+            output_record(&OUTPUT_TOPIC, &key, &payload, build_headers(None)),
+            true,
+        )
+        .await;
+        record_result(&INPUT_TOPIC, &result, None, 0, true);
+
+        assert!(result.is_ok());
+        assert_eq!(0, producer.in_flight_count());
+        assert_eq!(before + 1, processed());
+    }
+
+    #[test]
+    fn test_output_record_key_and_headers() {
+        let key = "fdk-id".to_string();
+        let payload = b"payload".to_vec();
+        let record = output_record(
+            &OUTPUT_TOPIC,
+            &key,
+            &payload,
+            build_headers(Some("abc".to_string())),
+        );
+
+        assert_eq!(OUTPUT_TOPIC.as_str(), record.topic);
+        assert_eq!(Some(&key), record.key);
+        assert_eq!(Some(&payload), record.payload);
+        assert_eq!(
+            Some(headers::SOURCE_VALUE.to_string()),
+            header_value(record.headers.as_ref(), headers::SOURCE)
+        );
+        assert_eq!(
+            Some("abc".to_string()),
+            header_value(record.headers.as_ref(), headers::CORRELATION_ID)
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use std::{env, time::Duration};
+
+use futures::{future::BoxFuture, FutureExt};
+use lazy_static::lazy_static;
+use rdkafka::{
+    error::{KafkaError, RDKafkaErrorCode},
+    producer::{FutureProducer, FutureRecord},
+};
+
+use crate::{error::Error, prometheus_metrics::PRODUCER_QUEUE_FULL_RETRIES};
+
+lazy_static! {
+    pub static ref DELIVERY_POLICY: DeliveryPolicy = DeliveryPolicy {
+        timeout: Duration::from_millis(
+            env::var("DELIVERY_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10000),
+        ),
+        queue_full_backoff: Duration::from_millis(
+            env::var("PRODUCER_QUEUE_FULL_BACKOFF_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(100),
+        ),
+        max_queue_full_retries: env::var("PRODUCER_QUEUE_FULL_MAX_RETRIES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(50),
+    };
+}
+
+/// How records are sent. A record is retried after `queue_full_backoff` while
+/// the producer queue is full, at most `max_queue_full_retries` times, and
+/// must be delivered within `timeout` once enqueued.
+#[derive(Debug, Clone)]
+pub struct DeliveryPolicy {
+    pub timeout: Duration,
+    pub queue_full_backoff: Duration,
+    pub max_queue_full_retries: u32,
+}
+
+/// Enqueues records, resolving to the outcome of their delivery.
+pub trait RecordSender {
+    #[allow(clippy::type_complexity)]
+    fn send_record<'a>(
+        &self,
+        record: FutureRecord<'a, String, Vec<u8>>,
+    ) -> Result<
+        BoxFuture<'static, Result<(), KafkaError>>,
+        (KafkaError, FutureRecord<'a, String, Vec<u8>>),
+    >;
+}
+
+impl RecordSender for FutureProducer {
+    fn send_record<'a>(
+        &self,
+        record: FutureRecord<'a, String, Vec<u8>>,
+    ) -> Result<
+        BoxFuture<'static, Result<(), KafkaError>>,
+        (KafkaError, FutureRecord<'a, String, Vec<u8>>),
+    > {
+        self.send_result(record).map(|delivery| {
+            async move {
+                match delivery.await {
+                    Ok(Ok(_)) => Ok(()),
+                    Ok(Err((e, _))) => Err(e),
+                    Err(_) => Err(KafkaError::Canceled),
+                }
+            }
+            .boxed()
+        })
+    }
+}
+
+/// Sends a record and waits until the broker has confirmed its delivery,
+/// backing off while the producer queue is full.
+pub async fn send_and_confirm<S: RecordSender>(
+    sender: &S,
+    mut record: FutureRecord<'_, String, Vec<u8>>,
+    policy: &DeliveryPolicy,
+) -> Result<(), Error> {
+    let mut retries = 0;
+    let delivery = loop {
+        match sender.send_record(record) {
+            Ok(delivery) => break delivery,
+            Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), returned))
+                if retries < policy.max_queue_full_retries =>
+            {
+                retries += 1;
+                PRODUCER_QUEUE_FULL_RETRIES.inc();
+                tracing::warn!(retries, "producer queue full, retrying send");
+                tokio::time::sleep(policy.queue_full_backoff).await;
+                record = returned;
+            }
+            Err((e, _)) => return Err(Error::DeliveryFailed(e)),
+        }
+    };
+
+    tokio::time::timeout(policy.timeout, delivery)
+        .await
+        .unwrap_or(Err(KafkaError::MessageProduction(
+            RDKafkaErrorCode::MessageTimedOut,
+        )))
+        .map_err(Error::DeliveryFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use futures::future;
+
+    use super::*;
+
+    const POLICY: DeliveryPolicy = DeliveryPolicy {
+        timeout: Duration::from_millis(50),
+        queue_full_backoff: Duration::from_millis(1),
+        max_queue_full_retries: 3,
+    };
+
+    /// Rejects the first `queue_full` sends, then enqueues records that are
+    /// either delivered at once or never.
+    struct MockSender {
+        queue_full: Cell<u32>,
+        delivers: bool,
+        sends: Cell<u32>,
+    }
+
+    impl MockSender {
+        fn new(queue_full: u32, delivers: bool) -> Self {
+            MockSender {
+                queue_full: Cell::new(queue_full),
+                delivers,
+                sends: Cell::new(0),
+            }
+        }
+    }
+
+    impl RecordSender for MockSender {
+        fn send_record<'a>(
+            &self,
+            record: FutureRecord<'a, String, Vec<u8>>,
+        ) -> Result<
+            BoxFuture<'static, Result<(), KafkaError>>,
+            (KafkaError, FutureRecord<'a, String, Vec<u8>>),
+        > {
+            self.sends.set(self.sends.get() + 1);
+            if self.queue_full.get() > 0 {
+                self.queue_full.set(self.queue_full.get() - 1);
+                return Err((
+                    KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull),
+                    record,
+                ));
+            }
+            if self.delivers {
+                Ok(future::ready(Ok(())).boxed())
+            } else {
+                Ok(future::pending().boxed())
+            }
+        }
+    }
+
+    fn record<'a>(key: &'a String, payload: &'a Vec<u8>) -> FutureRecord<'a, String, Vec<u8>> {
+        FutureRecord::to("mqa-events").key(key).payload(payload)
+    }
+
+    #[tokio::test]
+    async fn test_confirmed_delivery() {
+        let (key, payload) = ("fdk-id".to_string(), b"payload".to_vec());
+        let sender = MockSender::new(0, true);
+
+        let result = send_and_confirm(&sender, record(&key, &payload), &POLICY).await;
+
+        assert!(result.is_ok());
+        assert_eq!(1, sender.sends.get());
+    }
+
+    #[tokio::test]
+    async fn test_delivery_timeout() {
+        let (key, payload) = ("fdk-id".to_string(), b"payload".to_vec());
+        let sender = MockSender::new(0, false);
+
+        let result = send_and_confirm(&sender, record(&key, &payload), &POLICY).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::DeliveryFailed(KafkaError::MessageProduction(
+                RDKafkaErrorCode::MessageTimedOut
+            )))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_queue_full_retry() {
+        let (key, payload) = ("fdk-id".to_string(), b"payload".to_vec());
+        let before = PRODUCER_QUEUE_FULL_RETRIES.get();
+
+        let sender = MockSender::new(2, true);
+        let result = send_and_confirm(&sender, record(&key, &payload), &POLICY).await;
+        assert!(result.is_ok());
+        assert_eq!(3, sender.sends.get());
+        assert_eq!(before + 2, PRODUCER_QUEUE_FULL_RETRIES.get());
+
+        let sender = MockSender::new(10, true);
+        let result = send_and_confirm(&sender, record(&key, &payload), &POLICY).await;
+        assert!(matches!(
+            result,
+            Err(Error::DeliveryFailed(KafkaError::MessageProduction(
+                RDKafkaErrorCode::QueueFull
+            )))
+        ));
+        assert_eq!(4, sender.sends.get());
+        assert_eq!(before + 5, PRODUCER_QUEUE_FULL_RETRIES.get());
+    }
+}
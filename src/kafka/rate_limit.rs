@@ -0,0 +1,270 @@
+use std::{collections::HashMap, env, sync::Mutex, time::Duration};
+
+use lazy_static::lazy_static;
+use tokio::time::Instant;
+
+use crate::{error::Error, prometheus_metrics::RATE_LIMITED_EVENTS};
+
+/// Longest delay of an event from a publisher over its rate.
+pub const MAX_DELAY: Duration = Duration::from_secs(1);
+
+/// How often buckets refilled to capacity are dropped, as they are no
+/// different from new buckets.
+const EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
+lazy_static! {
+    pub static ref PER_PUBLISHER_RATE: Option<f64> = env::var("PER_PUBLISHER_RATE")
+        .ok()
+        .map(|v| parse_rate(&v))
+        .transpose()
+        .unwrap_or_else(|e| {
+            tracing::error!(error = e.to_string(), "invalid per publisher rate");
+            std::process::exit(1);
+        });
+    pub static ref PUBLISHER_RATE_LIMITER: Option<PublisherRateLimiter> =
+        PER_PUBLISHER_RATE.map(|rate| PublisherRateLimiter::new(rate, MAX_DELAY));
+}
+
+/// Events per second, greater than 0.
+fn parse_rate(value: &str) -> Result<f64, Error> {
+    match value.parse::<f64>() {
+        Ok(rate) if rate.is_finite() && rate > 0.0 => Ok(rate),
+        _ => Err(format!(
+            "invalid per publisher rate '{}', expected events per second greater than 0",
+            value
+        )
+        .into()),
+    }
+}
+
+/// Key events are rate limited by: the `catalogId` header of the message,
+/// or else the first publisher IRI of the dataset in IRI order.
+pub fn rate_limit_key(catalog_id: Option<String>, publishers: &[String]) -> Option<String> {
+    catalog_id
+        .filter(|catalog_id| !catalog_id.is_empty())
+        .or_else(|| publishers.iter().min().cloned())
+}
+
+/// Token bucket refilled with `rate` tokens per second, holding at most one
+/// second of tokens. Events taken without a token are owed, down to
+/// `max_delay` of tokens, and delayed until the debt is refilled.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    floor: f64,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(rate: f64, max_delay: Duration, now: Instant) -> Self {
+        let capacity = rate.max(1.0);
+        TokenBucket {
+            rate,
+            capacity,
+            floor: -rate * max_delay.as_secs_f64(),
+            tokens: capacity,
+            refilled: now,
+        }
+    }
+
+    /// Takes a token for an event, returning how long it must be delayed,
+    /// zero when the bucket held a token.
+    pub fn take(&mut self, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.refilled = now;
+
+        self.tokens = (self.tokens - 1.0).max(self.floor);
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+
+    /// Whether the bucket has been refilled to capacity.
+    pub fn is_full(&self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+        self.tokens + elapsed * self.rate >= self.capacity
+    }
+}
+
+/// Token buckets by publisher, and when full buckets were last dropped.
+#[derive(Debug)]
+struct Buckets {
+    buckets: HashMap<String, TokenBucket>,
+    evicted: Instant,
+}
+
+/// Token buckets of publishers, shared by the workers. As Kafka orders
+/// messages within a partition, events of publishers over their rate are
+/// deprioritized by a delay rather than reordered.
+#[derive(Debug)]
+pub struct PublisherRateLimiter {
+    rate: f64,
+    max_delay: Duration,
+    buckets: Mutex<Buckets>,
+}
+
+impl PublisherRateLimiter {
+    pub fn new(rate: f64, max_delay: Duration) -> Self {
+        PublisherRateLimiter {
+            rate,
+            max_delay,
+            buckets: Mutex::new(Buckets {
+                buckets: HashMap::new(),
+                evicted: Instant::now(),
+            }),
+        }
+    }
+
+    fn delay(&self, publisher: &str) -> Duration {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if now.saturating_duration_since(buckets.evicted) >= EVICTION_INTERVAL {
+            buckets.buckets.retain(|_, bucket| !bucket.is_full(now));
+            buckets.evicted = now;
+        }
+        buckets
+            .buckets
+            .entry(publisher.to_string())
+            .or_insert_with(|| TokenBucket::new(self.rate, self.max_delay, now))
+            .take(now)
+    }
+
+    /// Waits before an event of a publisher over its rate is processed,
+    /// returning the delay.
+    pub async fn throttle(&self, publisher: &str) -> Duration {
+        let delay = self.delay(publisher);
+        if !delay.is_zero() {
+            tracing::debug!(
+                publisher,
+                delay_millis = delay.as_millis(),
+                "publisher over rate, delaying event"
+            );
+            RATE_LIMITED_EVENTS.inc();
+            tokio::time::sleep(delay).await;
+        }
+        delay
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2.0, MAX_DELAY, start);
+
+        assert_eq!(Duration::ZERO, bucket.take(start));
+        assert_eq!(Duration::ZERO, bucket.take(start));
+        assert_eq!(Duration::from_millis(500), bucket.take(start));
+        assert_eq!(Duration::from_secs(1), bucket.take(start));
+        // Debt is bounded by the maximum delay.
+        assert_eq!(Duration::from_secs(1), bucket.take(start));
+
+        // Refilled at two tokens per second.
+        let later = start + Duration::from_secs(2);
+        assert_eq!(Duration::ZERO, bucket.take(later));
+        assert_eq!(Duration::ZERO, bucket.take(later + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_token_bucket_full() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2.0, MAX_DELAY, start);
+        assert!(bucket.is_full(start));
+
+        bucket.take(start);
+        bucket.take(start);
+        assert!(!bucket.is_full(start + Duration::from_millis(500)));
+        assert!(bucket.is_full(start + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_token_bucket_below_one_event_per_second() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(0.5, Duration::from_secs(10), start);
+
+        assert_eq!(Duration::ZERO, bucket.take(start));
+        assert_eq!(Duration::from_secs(2), bucket.take(start));
+        assert_eq!(Duration::ZERO, bucket.take(start + Duration::from_secs(4)));
+    }
+
+    #[test]
+    fn test_rate_limit_key() {
+        let publishers = vec![
+            "https://organizations.foo/2".to_string(),
+            "https://organizations.foo/1".to_string(),
+        ];
+
+        assert_eq!(
+            Some("catalog-1".to_string()),
+            rate_limit_key(Some("catalog-1".to_string()), &publishers)
+        );
+        assert_eq!(
+            Some("https://organizations.foo/1".to_string()),
+            rate_limit_key(None, &publishers)
+        );
+        assert_eq!(
+            Some("https://organizations.foo/1".to_string()),
+            rate_limit_key(Some(String::new()), &publishers)
+        );
+        assert_eq!(None, rate_limit_key(None, &[]));
+    }
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(2.5, parse_rate("2.5").unwrap());
+        assert!(parse_rate("0").is_err());
+        assert!(parse_rate("-1").is_err());
+        assert!(parse_rate("inf").is_err());
+        assert!(parse_rate("fast").is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_over_rate_events_delayed() {
+        let limiter = PublisherRateLimiter::new(1.0, MAX_DELAY);
+        let start = Instant::now();
+
+        assert_eq!(
+            Duration::ZERO,
+            limiter.throttle("https://flooding.foo").await
+        );
+        assert_eq!(start, Instant::now());
+
+        assert_eq!(
+            Duration::from_secs(1),
+            limiter.throttle("https://flooding.foo").await
+        );
+        assert_eq!(start + Duration::from_secs(1), Instant::now());
+
+        // Other publishers have their own buckets.
+        assert_eq!(Duration::ZERO, limiter.throttle("https://other.foo").await);
+        assert_eq!(start + Duration::from_secs(1), Instant::now());
+
+        let before = RATE_LIMITED_EVENTS.get();
+        limiter.throttle("https://flooding.foo").await;
+        assert!(RATE_LIMITED_EVENTS.get() > before);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_full_buckets_evicted() {
+        let limiter = PublisherRateLimiter::new(1.0, MAX_DELAY);
+        let bucket_count = || limiter.buckets.lock().unwrap().buckets.len();
+
+        limiter.throttle("https://idle.foo").await;
+        limiter.throttle("https://flooding.foo").await;
+        limiter.throttle("https://flooding.foo").await;
+        assert_eq!(2, bucket_count());
+
+        // Both buckets have been refilled, and are dropped.
+        tokio::time::advance(EVICTION_INTERVAL).await;
+        limiter.throttle("https://other.foo").await;
+        assert_eq!(1, bucket_count());
+    }
+}
@@ -0,0 +1,182 @@
+use std::{env, str::FromStr};
+
+use lazy_static::lazy_static;
+
+use crate::error::Error;
+
+lazy_static! {
+    pub static ref OUTPUT_ROUTES: Vec<Route> = env::var("OUTPUT_ROUTES")
+        .map(|v| {
+            parse_routes(&v).unwrap_or_else(|e| {
+                tracing::error!(error = e.to_string(), "invalid output routes");
+                std::process::exit(1);
+            })
+        })
+        .unwrap_or_default();
+}
+
+/// Additional topic MQA events are produced to when the dataset has a
+/// publisher with an IRI starting with `publisher_prefix`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub topic: String,
+    pub publisher_prefix: String,
+}
+
+impl FromStr for Route {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((topic, publisher_prefix))
+                if !topic.trim().is_empty() && !publisher_prefix.trim().is_empty() =>
+            {
+                Ok(Route {
+                    topic: topic.trim().to_string(),
+                    publisher_prefix: publisher_prefix.trim().to_string(),
+                })
+            }
+            _ => Err(format!(
+                "invalid route '{}', expected '<topic>=<publisher-iri-prefix>'",
+                s
+            )
+            .into()),
+        }
+    }
+}
+
+/// Comma separated routes.
+pub fn parse_routes(value: &str) -> Result<Vec<Route>, Error> {
+    value
+        .split(',')
+        .filter(|route| !route.trim().is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// Topics an MQA event is produced to: the output topic, followed by the
+/// topics of routes matching any of the publishers, each topic once.
+pub fn route_topics<'a>(
+    output_topic: &'a str,
+    routes: &'a [Route],
+    publishers: &[String],
+) -> Vec<&'a str> {
+    let mut topics = vec![output_topic];
+    for route in routes {
+        if !topics.contains(&route.topic.as_str())
+            && publishers
+                .iter()
+                .any(|publisher| publisher.starts_with(&route.publisher_prefix))
+        {
+            topics.push(&route.topic);
+        }
+    }
+    topics
+}
+
+/// Topics MQA events may be produced to: the output topic, followed by the
+/// topics of the routes, each topic once.
+pub fn output_topics<'a>(output_topic: &'a str, routes: &'a [Route]) -> Vec<&'a str> {
+    let mut topics = vec![output_topic];
+    for route in routes {
+        if !topics.contains(&route.topic.as_str()) {
+            topics.push(&route.topic);
+        }
+    }
+    topics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORGANIZATIONS: &str =
+        "https://organization-catalog.fellesdatakatalog.digdir.no/organizations/";
+
+    fn routes() -> Vec<Route> {
+        parse_routes(&format!(
+            "mqa-scoring={0}974760673, mqa-agencies={0}97, mqa-scoring={0}91",
+            ORGANIZATIONS
+        ))
+        .unwrap()
+    }
+
+    fn publisher(organization_number: &str) -> String {
+        format!("{}{}", ORGANIZATIONS, organization_number)
+    }
+
+    #[test]
+    fn test_parse_routes() {
+        assert_eq!(
+            vec![Route {
+                topic: "mqa-scoring".to_string(),
+                publisher_prefix: publisher("974760673"),
+            }],
+            parse_routes(&format!("mqa-scoring={}", publisher("974760673"))).unwrap()
+        );
+        assert!(parse_routes("").unwrap().is_empty());
+        assert!(parse_routes("mqa-scoring").is_err());
+        assert!(parse_routes("=https://data.brreg.no/").is_err());
+        assert!(parse_routes("mqa-scoring=").is_err());
+    }
+
+    #[test]
+    fn test_no_matching_route() {
+        assert_eq!(
+            vec!["mqa-events"],
+            route_topics("mqa-events", &routes(), &[publisher("810000000")])
+        );
+        assert_eq!(
+            vec!["mqa-events"],
+            route_topics("mqa-events", &routes(), &[])
+        );
+        assert_eq!(
+            vec!["mqa-events"],
+            route_topics("mqa-events", &[], &[publisher("974760673")])
+        );
+    }
+
+    #[test]
+    fn test_one_matching_route() {
+        assert_eq!(
+            vec!["mqa-events", "mqa-scoring"],
+            route_topics("mqa-events", &routes(), &[publisher("910000000")])
+        );
+    }
+
+    #[test]
+    fn test_overlapping_routes() {
+        assert_eq!(
+            vec!["mqa-events", "mqa-scoring", "mqa-agencies"],
+            route_topics("mqa-events", &routes(), &[publisher("974760673")])
+        );
+        assert_eq!(
+            vec!["mqa-events", "mqa-agencies", "mqa-scoring"],
+            route_topics(
+                "mqa-events",
+                &routes(),
+                &[publisher("910000000"), publisher("970000000")]
+            )
+        );
+        assert_eq!(
+            vec!["mqa-events"],
+            route_topics(
+                "mqa-events",
+                &[Route {
+                    topic: "mqa-events".to_string(),
+                    publisher_prefix: ORGANIZATIONS.to_string(),
+                }],
+                &[publisher("974760673")]
+            )
+        );
+    }
+
+    #[test]
+    fn test_output_topics() {
+        assert_eq!(
+            vec!["mqa-events", "mqa-scoring", "mqa-agencies"],
+            output_topics("mqa-events", &routes())
+        );
+        assert_eq!(vec!["mqa-events"], output_topics("mqa-events", &[]));
+    }
+}
@@ -0,0 +1,380 @@
+use std::{collections::HashSet, env, sync::Mutex, time::Duration};
+
+use lazy_static::lazy_static;
+use rdkafka::{
+    consumer::{BaseConsumer, Consumer, ConsumerContext, RebalanceProtocol},
+    error::KafkaResult,
+    Offset, TopicPartitionList,
+};
+
+use crate::error::Error;
+
+lazy_static! {
+    pub static ref SEEK_TO_TIMESTAMP: Option<i64> = env::var("SEEK_TO_TIMESTAMP").ok().map(|v| {
+        parse_timestamp_millis(&v).unwrap_or_else(|e| {
+            tracing::error!(error = e.to_string(), "invalid seek timestamp");
+            std::process::exit(1);
+        })
+    });
+    /// Partitions already moved to the seek timestamp. Each partition is only
+    /// moved the first time it is assigned in this process, so later
+    /// rebalances and worker restarts continue from the committed offsets.
+    static ref SEEKED_PARTITIONS: Mutex<HashSet<(String, i32)>> = Mutex::new(HashSet::new());
+}
+
+const SEEK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Moves newly assigned partitions to the first offset at or after
+/// `timestamp`, in milliseconds since the epoch.
+pub fn seek_to_timestamp<C: ConsumerContext>(
+    consumer: &BaseConsumer<C>,
+    worker_id: usize,
+    assigned: &TopicPartitionList,
+    timestamp: i64,
+) {
+    if let Err(e) = try_seek_to_timestamp(consumer, worker_id, assigned, timestamp) {
+        tracing::error!(
+            worker_id,
+            timestamp,
+            error = e.to_string(),
+            "failed to seek partitions to timestamp"
+        );
+    }
+}
+
+fn try_seek_to_timestamp<C: ConsumerContext>(
+    consumer: &BaseConsumer<C>,
+    worker_id: usize,
+    assigned: &TopicPartitionList,
+    timestamp: i64,
+) -> KafkaResult<()> {
+    let mut timestamps = TopicPartitionList::new();
+    for (topic, partition) in unseeked_partitions(assigned) {
+        timestamps.add_partition_offset(&topic, partition, Offset::Offset(timestamp))?;
+    }
+    if timestamps.count() == 0 {
+        return Ok(());
+    }
+
+    let found = consumer.offsets_for_times(timestamps, SEEK_TIMEOUT)?;
+    let mut offsets = TopicPartitionList::new();
+    for elem in found.elements() {
+        let (topic, partition) = (elem.topic(), elem.partition());
+        if let Err(e) = elem.error() {
+            tracing::warn!(
+                worker_id,
+                topic,
+                partition,
+                error = e.to_string(),
+                "no offset found for timestamp"
+            );
+            continue;
+        }
+        let watermarks = consumer.fetch_watermarks(topic, partition, SEEK_TIMEOUT)?;
+        let offset = backfill_offset(elem.offset(), watermarks);
+        tracing::info!(
+            worker_id,
+            topic,
+            partition,
+            timestamp,
+            offset,
+            "seeking partition to timestamp"
+        );
+        offsets.add_partition_offset(topic, partition, Offset::Offset(offset))?;
+    }
+    if offsets.count() == 0 {
+        return Ok(());
+    }
+
+    let cooperative = matches!(
+        consumer.rebalance_protocol(),
+        RebalanceProtocol::Cooperative
+    );
+    match reassignment(cooperative, assigned, &offsets)? {
+        Reassignment::Replace(assignment) => consumer.assign(&assignment)?,
+        Reassignment::Incremental(partitions) => {
+            consumer.incremental_unassign(&partitions)?;
+            consumer.incremental_assign(&partitions)?;
+        }
+    }
+    mark_seeked(&offsets);
+    Ok(())
+}
+
+/// Assigned partitions not yet moved to the seek timestamp.
+fn unseeked_partitions(assigned: &TopicPartitionList) -> Vec<(String, i32)> {
+    let seeked = SEEKED_PARTITIONS.lock().unwrap_or_else(|e| e.into_inner());
+    assigned
+        .elements()
+        .iter()
+        .map(|elem| (elem.topic().to_string(), elem.partition()))
+        .filter(|partition| !seeked.contains(partition))
+        .collect()
+}
+
+/// Records partitions as moved to the seek timestamp, once they have been
+/// assigned at their seek offsets. Partitions failing to move are moved when
+/// next assigned.
+fn mark_seeked(offsets: &TopicPartitionList) {
+    let mut seeked = SEEKED_PARTITIONS.lock().unwrap_or_else(|e| e.into_inner());
+    for elem in offsets.elements() {
+        seeked.insert((elem.topic().to_string(), elem.partition()));
+    }
+}
+
+/// Assignment change moving partitions to their seek offsets.
+#[derive(Debug, PartialEq)]
+enum Reassignment {
+    /// The whole assignment, replaced by eager rebalances.
+    Replace(TopicPartitionList),
+    /// The seeked partitions, unassigned and assigned again by incremental
+    /// (cooperative) rebalances, which must leave other partitions alone.
+    Incremental(TopicPartitionList),
+}
+
+/// Partitions cannot be seeked before their fetchers have started, so they
+/// are assigned again starting at the found `offsets` instead.
+fn reassignment(
+    cooperative: bool,
+    assigned: &TopicPartitionList,
+    offsets: &TopicPartitionList,
+) -> KafkaResult<Reassignment> {
+    if cooperative {
+        return Ok(Reassignment::Incremental(offsets.clone()));
+    }
+    let mut assignment = assigned.clone();
+    for elem in offsets.elements() {
+        assignment.set_partition_offset(elem.topic(), elem.partition(), elem.offset())?;
+    }
+    Ok(Reassignment::Replace(assignment))
+}
+
+/// Offset a partition is moved to, given the offset `offsets_for_times` found
+/// for the timestamp and the low and high watermarks of the partition.
+/// Partitions without messages at or after the timestamp are moved to the end.
+pub fn backfill_offset(found: Offset, (low, high): (i64, i64)) -> i64 {
+    match found {
+        Offset::Offset(offset) => offset.clamp(low, high),
+        _ => high,
+    }
+}
+
+/// Parse an ISO 8601 timestamp, either a date or a date and time with a `Z`
+/// or `±HH:MM` offset, into milliseconds since the epoch.
+pub fn parse_timestamp_millis(value: &str) -> Result<i64, Error> {
+    let invalid = || {
+        Error::from(format!(
+            "invalid timestamp '{}', expected ISO 8601 like '2024-01-31T12:00:00Z'",
+            value
+        ))
+    };
+
+    let (date, time) = value.split_once('T').unwrap_or((value, "00:00:00Z"));
+
+    let mut date_parts = date.split('-');
+    let (year, month, day) = match (
+        date_parts.next().and_then(|v| digits(v, 4)),
+        date_parts.next().and_then(|v| digits(v, 2)),
+        date_parts.next().and_then(|v| digits(v, 2)),
+        date_parts.next(),
+    ) {
+        (Some(year), Some(month), Some(day), None)
+            if (1..=12).contains(&month) && (1..=days_in_month(year, month)).contains(&day) =>
+        {
+            (year, month, day)
+        }
+        _ => return Err(invalid()),
+    };
+
+    let (time, offset_minutes) = match time.strip_suffix('Z') {
+        Some(time) => (time, 0),
+        None => {
+            let split = time.rfind(['+', '-']).ok_or_else(invalid)?;
+            let (time, offset) = time.split_at(split);
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            let (hours, minutes) = offset[1..].split_once(':').ok_or_else(invalid)?;
+            match (digits(hours, 2), digits(minutes, 2)) {
+                (Some(hours), Some(minutes)) if hours <= 23 && minutes <= 59 => {
+                    (time, sign * (hours * 60 + minutes))
+                }
+                _ => return Err(invalid()),
+            }
+        }
+    };
+
+    let mut time_parts = time.split(':');
+    let (hour, minute, second) = (
+        time_parts.next().and_then(|v| digits(v, 2)),
+        time_parts.next().and_then(|v| digits(v, 2)),
+        time_parts.next().unwrap_or("00"),
+    );
+    let (second, fraction) = second.split_once('.').unwrap_or((second, ""));
+    let millis = match fraction {
+        "" => Some(0),
+        fraction if fraction.chars().all(|c| c.is_ascii_digit()) => {
+            format!("{:0<3}", &fraction[..fraction.len().min(3)])
+                .parse()
+                .ok()
+        }
+        _ => None,
+    };
+    let (hour, minute, second, millis) =
+        match (hour, minute, digits(second, 2), millis, time_parts.next()) {
+            (Some(hour), Some(minute), Some(second), Some(millis), None)
+                if hour <= 23 && minute <= 59 && second <= 59 =>
+            {
+                (hour, minute, second, millis)
+            }
+            _ => return Err(invalid()),
+        };
+
+    let seconds = days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second
+        - offset_minutes * 60;
+    Ok(seconds * 1000 + millis)
+}
+
+/// Number made of exactly `len` ASCII digits.
+pub(crate) fn digits(value: &str, len: usize) -> Option<i64> {
+    if value.len() == len && value.chars().all(|c| c.is_ascii_digit()) {
+        value.parse().ok()
+    } else {
+        None
+    }
+}
+
+pub(crate) fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar.
+pub(crate) fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn topic_partitions(partitions: &[(i32, Offset)]) -> TopicPartitionList {
+        let mut tpl = TopicPartitionList::new();
+        for (partition, offset) in partitions {
+            tpl.add_partition_offset("mqa-dataset-events", *partition, *offset)
+                .unwrap();
+        }
+        tpl
+    }
+
+    #[test]
+    fn test_eager_reassignment() {
+        let assigned = topic_partitions(&[(0, Offset::Invalid), (1, Offset::Invalid)]);
+        let offsets = topic_partitions(&[(1, Offset::Offset(42))]);
+        assert_eq!(
+            Reassignment::Replace(topic_partitions(&[
+                (0, Offset::Invalid),
+                (1, Offset::Offset(42))
+            ])),
+            reassignment(false, &assigned, &offsets).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_cooperative_reassignment() {
+        // Only the seeked partitions are reassigned, as an incremental
+        // rebalance must not touch partitions assigned before it.
+        let assigned = topic_partitions(&[(0, Offset::Invalid), (1, Offset::Invalid)]);
+        let offsets = topic_partitions(&[(1, Offset::Offset(42))]);
+        assert_eq!(
+            Reassignment::Incremental(topic_partitions(&[(1, Offset::Offset(42))])),
+            reassignment(true, &assigned, &offsets).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_partitions_seeked_once_reassigned() {
+        let mut assigned = TopicPartitionList::new();
+        assigned.add_partition("seeked-partitions", 0);
+        assigned.add_partition("seeked-partitions", 1);
+        // Looking the partitions up does not record them as seeked, so
+        // partitions failing to be reassigned are moved when next assigned.
+        assert_eq!(2, unseeked_partitions(&assigned).len());
+        assert_eq!(2, unseeked_partitions(&assigned).len());
+
+        let mut offsets = TopicPartitionList::new();
+        offsets
+            .add_partition_offset("seeked-partitions", 1, Offset::Offset(42))
+            .unwrap();
+        mark_seeked(&offsets);
+        assert_eq!(
+            vec![("seeked-partitions".to_string(), 0)],
+            unseeked_partitions(&assigned)
+        );
+    }
+
+    #[test]
+    fn test_backfill_offset() {
+        // Offset of the first message at or after the timestamp.
+        assert_eq!(42, backfill_offset(Offset::Offset(42), (10, 100)));
+        // No messages at or after the timestamp.
+        assert_eq!(100, backfill_offset(Offset::End, (10, 100)));
+        assert_eq!(0, backfill_offset(Offset::End, (0, 0)));
+        // Offsets outside the watermarks, e.g. after retention.
+        assert_eq!(10, backfill_offset(Offset::Offset(3), (10, 100)));
+        assert_eq!(100, backfill_offset(Offset::Offset(120), (10, 100)));
+    }
+
+    #[test]
+    fn test_parse_timestamp_millis() {
+        assert_eq!(0, parse_timestamp_millis("1970-01-01T00:00:00Z").unwrap());
+        assert_eq!(0, parse_timestamp_millis("1970-01-01").unwrap());
+        assert_eq!(1709164800000, parse_timestamp_millis("2024-02-29").unwrap());
+        assert_eq!(
+            1709209815500,
+            parse_timestamp_millis("2024-02-29T12:30:15.5Z").unwrap()
+        );
+        assert_eq!(
+            1709209815500,
+            parse_timestamp_millis("2024-02-29T14:30:15.500+02:00").unwrap()
+        );
+        assert_eq!(
+            1709209800000,
+            parse_timestamp_millis("2024-02-29T07:30-05:00").unwrap()
+        );
+        assert_eq!(
+            -86400000,
+            parse_timestamp_millis("1969-12-31T00:00:00Z").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_timestamp() {
+        for value in [
+            "",
+            "yesterday",
+            "1709209815500",
+            "2023-02-29",
+            "2024-13-01",
+            "2024-1-01",
+            "2024-01-01T25:00:00Z",
+            "2024-01-01T12:00:00",
+            "2024-01-01T12:00:00+0200",
+            "2024-01-01T12:00:00.x5Z",
+            "2024-01-01T12:00:00:00Z",
+        ] {
+            assert!(
+                parse_timestamp_millis(value).is_err(),
+                "{} should be invalid",
+                value
+            );
+        }
+    }
+}
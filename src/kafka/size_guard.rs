@@ -0,0 +1,501 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    env,
+    io::Write,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flate2::{write::GzEncoder, Compression};
+use lazy_static::lazy_static;
+use oxigraph::{
+    io::{RdfFormat, RdfParser},
+    model::{vocab::xsd, Literal, NamedNode, Quad, Subject, Term},
+    store::{StorageError, Store},
+};
+
+use crate::{
+    error::Error,
+    metrics::GraphLayout,
+    prometheus_metrics::OVERSIZED_OUTPUT_GRAPHS,
+    rdf::{dump_graph_as_trig, dump_graph_as_turtle},
+    vocab::dcat_mqa,
+};
+
+lazy_static! {
+    pub static ref MAX_OUTPUT_BYTES: usize = env::var("MAX_OUTPUT_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(900_000);
+}
+
+/// Encoding of graphs compressed to fit within the maximum size.
+pub const GZIP_BASE64: &str = "gzip+base64";
+
+/// How a graph is fit within the maximum size of produced messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeDecision {
+    /// Sent as is.
+    Plain,
+    /// Sent compressed.
+    Compress,
+    /// Sent compressed with distribution assessments left out.
+    Truncate,
+}
+
+/// Decides how a graph of `bytes` bytes is fit within `max_bytes`. Its size
+/// once compressed is only computed when the graph does not fit as is.
+pub fn size_decision<E>(
+    bytes: usize,
+    compressed_bytes: impl FnOnce() -> Result<usize, E>,
+    max_bytes: usize,
+) -> Result<SizeDecision, E> {
+    if bytes <= max_bytes {
+        Ok(SizeDecision::Plain)
+    } else if compressed_bytes()? <= max_bytes {
+        Ok(SizeDecision::Compress)
+    } else {
+        Ok(SizeDecision::Truncate)
+    }
+}
+
+/// Number of the `distributions` assessments of a graph of `bytes` bytes to
+/// keep for it to fit within `max_bytes`, estimated proportionally with a
+/// margin of a tenth. Fewer than all are kept when the graph does not fit.
+pub fn distributions_to_keep(distributions: usize, bytes: usize, max_bytes: usize) -> usize {
+    if bytes <= max_bytes {
+        return distributions;
+    }
+    let estimate = distributions as u128 * max_bytes as u128 * 9 / (bytes as u128 * 10);
+    (estimate as usize).min(distributions.saturating_sub(1))
+}
+
+/// Graph of an MQA event, fit within the maximum size.
+#[derive(Debug)]
+pub struct OutputGraph {
+    pub graph: String,
+    /// [`GZIP_BASE64`] when compressed.
+    pub encoding: Option<String>,
+    /// Number of distribution assessments left out.
+    pub omitted_distribution_assessments: usize,
+}
+
+/// Fits a serialized MQA graph within `max_bytes`, compressing it if needed,
+/// and leaving out distribution assessments, beyond the first in order of
+/// their IRIs, if that is not enough. Dataset assessments and their
+/// measurements are always kept, with the number of distribution assessments
+/// left out of each.
+pub fn fit_output_graph(
+    graph: String,
+    layout: GraphLayout,
+    max_bytes: usize,
+) -> Result<OutputGraph, Error> {
+    let mut compressed = String::new();
+    let decision = size_decision(
+        graph.len(),
+        || -> Result<usize, Error> {
+            compressed = gzip_base64(graph.as_bytes())?;
+            Ok(compressed.len())
+        },
+        max_bytes,
+    )?;
+
+    match decision {
+        SizeDecision::Plain => Ok(OutputGraph {
+            graph,
+            encoding: None,
+            omitted_distribution_assessments: 0,
+        }),
+        SizeDecision::Compress => {
+            OVERSIZED_OUTPUT_GRAPHS
+                .with_label_values(&["compressed"])
+                .inc();
+            tracing::info!(
+                bytes = graph.len(),
+                compressed_bytes = compressed.len(),
+                "compressed oversized output graph"
+            );
+            Ok(OutputGraph {
+                graph: compressed,
+                encoding: Some(GZIP_BASE64.to_string()),
+                omitted_distribution_assessments: 0,
+            })
+        }
+        SizeDecision::Truncate => truncate(&graph, compressed.len(), layout, max_bytes),
+    }
+}
+
+/// Leaves out distribution assessments until the compressed graph fits.
+fn truncate(
+    graph: &str,
+    compressed_bytes: usize,
+    layout: GraphLayout,
+    max_bytes: usize,
+) -> Result<OutputGraph, Error> {
+    let store = Store::new()?;
+    let parser = match layout {
+        GraphLayout::Flat => RdfParser::from_format(RdfFormat::Turtle),
+        GraphLayout::PerAssessmentNamedGraphs => RdfParser::from_format(RdfFormat::TriG),
+    };
+    store.load_from_reader(parser, graph.as_bytes())?;
+
+    let mut kept = distribution_assessments(&store)?;
+    let mut omitted: BTreeMap<NamedNode, usize> = BTreeMap::new();
+    let mut bytes = compressed_bytes;
+    while !kept.is_empty() {
+        let keep = distributions_to_keep(kept.len(), bytes, max_bytes);
+        for assessment in kept.split_off(keep) {
+            for dataset_assessment in remove_distribution_assessment(&store, assessment)? {
+                *omitted.entry(dataset_assessment).or_default() += 1;
+            }
+        }
+        record_omitted(&store, &omitted, layout)?;
+
+        let serialized = match layout {
+            GraphLayout::Flat => dump_graph_as_turtle(&store)?,
+            GraphLayout::PerAssessmentNamedGraphs => dump_graph_as_trig(&store)?,
+        };
+        let compressed = gzip_base64(&serialized)?;
+        if compressed.len() <= max_bytes {
+            let omitted_distribution_assessments = omitted.values().sum();
+            OVERSIZED_OUTPUT_GRAPHS
+                .with_label_values(&["truncated"])
+                .inc();
+            tracing::warn!(
+                bytes = graph.len(),
+                compressed_bytes = compressed.len(),
+                omitted_distribution_assessments,
+                "left distribution assessments out of oversized output graph"
+            );
+            return Ok(OutputGraph {
+                graph: compressed,
+                encoding: Some(GZIP_BASE64.to_string()),
+                omitted_distribution_assessments,
+            });
+        }
+        bytes = compressed.len();
+    }
+    Err(Error::GraphTooLarge { bytes })
+}
+
+/// Gzip compressed bytes, encoded as base64 to be sent as an Avro string.
+fn gzip_base64(bytes: &[u8]) -> Result<String, Error> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bytes)?;
+    Ok(STANDARD.encode(encoder.finish()?))
+}
+
+/// Distribution assessments of a store, in order of their IRIs.
+fn distribution_assessments(store: &Store) -> Result<Vec<NamedNode>, StorageError> {
+    let mut assessments = store
+        .quads_for_pattern(
+            None,
+            Some(oxigraph::model::vocab::rdf::TYPE),
+            Some(dcat_mqa::DISTRIBUTION_ASSESSMENT_CLASS.into()),
+            None,
+        )
+        .filter_map(|quad| match quad {
+            Ok(Quad {
+                subject: Subject::NamedNode(assessment),
+                ..
+            }) => Some(Ok(assessment)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    assessments.sort();
+    assessments.dedup();
+    Ok(assessments)
+}
+
+/// Removes a distribution assessment with the measurements and annotations
+/// it contains, returning the dataset assessments it was linked from.
+fn remove_distribution_assessment(
+    store: &Store,
+    assessment: NamedNode,
+) -> Result<Vec<NamedNode>, StorageError> {
+    let dataset_assessments = store
+        .quads_for_pattern(
+            None,
+            Some(dcat_mqa::HAS_DISTRIBUTION_ASSESSMENT),
+            Some(assessment.as_ref().into()),
+            None,
+        )
+        .filter_map(|quad| match quad {
+            Ok(Quad {
+                subject: Subject::NamedNode(dataset_assessment),
+                ..
+            }) => Some(Ok(dataset_assessment)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+        .collect::<Result<HashSet<_>, _>>()?;
+
+    // The blank nodes describing the assessment are those reachable from it,
+    // and the annotations targeting them.
+    let mut nodes = HashSet::new();
+    let mut pending: Vec<Subject> = vec![assessment.into()];
+    while let Some(node) = pending.pop() {
+        if !nodes.insert(node.clone()) {
+            continue;
+        }
+        for quad in store.quads_for_pattern(Some(node.as_ref()), None, None, None) {
+            if let Term::BlankNode(object) = quad?.object {
+                pending.push(object.into());
+            }
+        }
+        let term = Term::from(node.clone());
+        for quad in store.quads_for_pattern(None, None, Some(term.as_ref()), None) {
+            if let Subject::BlankNode(subject) = quad?.subject {
+                pending.push(subject.into());
+            }
+        }
+    }
+
+    let mut quads = Vec::new();
+    for node in &nodes {
+        for quad in store.quads_for_pattern(Some(node.as_ref()), None, None, None) {
+            quads.push(quad?);
+        }
+        let term = Term::from(node.clone());
+        for quad in store.quads_for_pattern(None, None, Some(term.as_ref()), None) {
+            quads.push(quad?);
+        }
+    }
+    for quad in quads {
+        store.remove(&quad)?;
+    }
+
+    Ok(dataset_assessments.into_iter().collect())
+}
+
+/// Sets the number of distribution assessments left out of each dataset
+/// assessment.
+fn record_omitted(
+    store: &Store,
+    omitted: &BTreeMap<NamedNode, usize>,
+    layout: GraphLayout,
+) -> Result<(), StorageError> {
+    for (dataset_assessment, count) in omitted {
+        let graph = layout.graph_of(dataset_assessment.as_ref());
+        let previous = store
+            .quads_for_pattern(
+                Some(dataset_assessment.as_ref().into()),
+                Some(dcat_mqa::OMITTED_DISTRIBUTION_ASSESSMENTS),
+                None,
+                Some(graph),
+            )
+            .collect::<Result<Vec<_>, _>>()?;
+        for quad in previous {
+            store.remove(&quad)?;
+        }
+        store.insert(&Quad::new(
+            dataset_assessment.clone(),
+            dcat_mqa::OMITTED_DISTRIBUTION_ASSESSMENTS,
+            Literal::new_typed_literal(count.to_string(), xsd::INTEGER),
+            graph,
+        ))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+    use oxigraph::model::{NamedNodeRef, QuadRef};
+
+    use super::*;
+    use crate::{
+        rdf::{
+            add_boolean_quality_measurement, insert_dataset_assessment,
+            insert_distribution_assessment, parse_turtle,
+        },
+        vocab::dqv,
+    };
+
+    const DATASET_ASSESSMENT: &str = "https://dataset.assessment.foo";
+    const DISTRIBUTION_METRICS: [NamedNodeRef<'static>; 8] = [
+        dcat_mqa::DOWNLOAD_URL_AVAILABILITY,
+        dcat_mqa::FORMAT_AVAILABILITY,
+        dcat_mqa::MEDIA_TYPE_AVAILABILITY,
+        dcat_mqa::FORMAT_MEDIA_TYPE_VOCABULARY_ALIGNMENT,
+        dcat_mqa::FORMAT_MEDIA_TYPE_NON_PROPRIETARY,
+        dcat_mqa::FORMAT_MEDIA_TYPE_MACHINE_INTERPRETABLE,
+        dcat_mqa::LICENSE_AVAILABILITY,
+        dcat_mqa::KNOWN_LICENSE,
+    ];
+
+    #[test]
+    fn test_size_decision_boundaries() {
+        let compressed = |bytes: usize| move || Ok::<_, Error>(bytes);
+
+        assert_eq!(
+            SizeDecision::Plain,
+            size_decision(100, || Err::<usize, _>("not compressed"), 100).unwrap()
+        );
+        assert_eq!(
+            SizeDecision::Compress,
+            size_decision(101, compressed(100), 100).unwrap()
+        );
+        assert_eq!(
+            SizeDecision::Truncate,
+            size_decision(101, compressed(101), 100).unwrap()
+        );
+        assert!(size_decision(101, || Err::<usize, _>("failed"), 100).is_err());
+    }
+
+    #[test]
+    fn test_distributions_to_keep() {
+        assert_eq!(500, distributions_to_keep(500, 1000, 1000));
+        assert_eq!(449, distributions_to_keep(500, 1001, 1000));
+        assert_eq!(225, distributions_to_keep(500, 2000, 1000));
+        assert_eq!(0, distributions_to_keep(1, 1001, 1000));
+        assert_eq!(0, distributions_to_keep(0, 1001, 1000));
+    }
+
+    /// MQA graph of a dataset with the given number of distributions, each
+    /// with a few measurements.
+    fn assessment_graph(distributions: usize) -> String {
+        let store = Store::new().unwrap();
+        let graph = GraphNameRef::DefaultGraph;
+        let dataset_assessment = NamedNodeRef::new_unchecked(DATASET_ASSESSMENT);
+        let dataset = NamedNodeRef::new_unchecked("https://dataset.foo");
+        insert_dataset_assessment(dataset_assessment, dataset, Some("fdk-id"), graph, &store)
+            .unwrap();
+        add_boolean_quality_measurement(
+            dcat_mqa::KEYWORD_AVAILABILITY,
+            dataset_assessment,
+            dataset,
+            true,
+            graph,
+            &store,
+        )
+        .unwrap();
+
+        for i in 0..distributions {
+            let assessment =
+                NamedNode::new_unchecked(format!("https://distribution.assessment.foo/{:03}", i));
+            let distribution = NamedNode::new_unchecked(format!("https://distribution.foo/{i}"));
+            insert_distribution_assessment(
+                dataset_assessment,
+                assessment.as_ref(),
+                distribution.as_ref(),
+                graph,
+                &store,
+            )
+            .unwrap();
+            for metric in DISTRIBUTION_METRICS {
+                add_boolean_quality_measurement(
+                    metric,
+                    assessment.as_ref(),
+                    distribution.as_ref(),
+                    i % 2 == 0,
+                    graph,
+                    &store,
+                )
+                .unwrap();
+            }
+        }
+        String::from_utf8(dump_graph_as_turtle(&store).unwrap()).unwrap()
+    }
+
+    fn decode(output: &OutputGraph) -> Store {
+        let graph = match output.encoding.as_deref() {
+            Some(GZIP_BASE64) => {
+                let mut graph = String::new();
+                GzDecoder::new(STANDARD.decode(&output.graph).unwrap().as_slice())
+                    .read_to_string(&mut graph)
+                    .unwrap();
+                graph
+            }
+            _ => output.graph.clone(),
+        };
+        let store = Store::new().unwrap();
+        parse_turtle(&store, graph).unwrap();
+        store
+    }
+
+    #[test]
+    fn test_small_graph_sent_as_is() {
+        let graph = assessment_graph(2);
+        let output = fit_output_graph(graph.clone(), GraphLayout::Flat, graph.len()).unwrap();
+
+        assert_eq!(graph, output.graph);
+        assert_eq!(None, output.encoding);
+    }
+
+    #[test]
+    fn test_large_graph_compressed() {
+        let graph = assessment_graph(500);
+        let max_bytes = graph.len() - 1;
+
+        let output = fit_output_graph(graph.clone(), GraphLayout::Flat, max_bytes).unwrap();
+
+        assert_eq!(Some(GZIP_BASE64), output.encoding.as_deref());
+        assert!(output.graph.len() <= max_bytes);
+        assert_eq!(0, output.omitted_distribution_assessments);
+        let expected = Store::new().unwrap();
+        parse_turtle(&expected, graph).unwrap();
+        assert_eq!(expected.len().unwrap(), decode(&output).len().unwrap());
+    }
+
+    #[test]
+    fn test_large_graph_truncated() {
+        let graph = assessment_graph(500);
+        let output = fit_output_graph(graph, GraphLayout::Flat, 20_000).unwrap();
+
+        assert_eq!(Some(GZIP_BASE64), output.encoding.as_deref());
+        assert!(output.graph.len() <= 20_000);
+
+        let store = decode(&output);
+        let kept = distribution_assessments(&store).unwrap();
+        assert!(!kept.is_empty());
+        assert_eq!(500, kept.len() + output.omitted_distribution_assessments);
+        assert_eq!("https://distribution.assessment.foo/000", kept[0].as_str());
+        assert_eq!(
+            kept.len() * DISTRIBUTION_METRICS.len(),
+            store
+                .quads_for_pattern(None, Some(dqv::COMPUTED_ON), None, None)
+                .filter(|quad| quad.as_ref().unwrap().object
+                    != Term::from(NamedNode::new_unchecked("https://dataset.foo")))
+                .count()
+        );
+
+        // Dataset level measurements are kept, noting the omission.
+        let dataset_assessment = NamedNodeRef::new_unchecked(DATASET_ASSESSMENT);
+        assert_eq!(
+            1,
+            store
+                .quads_for_pattern(
+                    None,
+                    Some(dqv::IS_MEASUREMENT_OF),
+                    Some(dcat_mqa::KEYWORD_AVAILABILITY.into()),
+                    None
+                )
+                .count()
+        );
+        assert!(store
+            .contains(QuadRef::new(
+                dataset_assessment,
+                dcat_mqa::OMITTED_DISTRIBUTION_ASSESSMENTS,
+                Literal::new_typed_literal(
+                    output.omitted_distribution_assessments.to_string(),
+                    xsd::INTEGER
+                )
+                .as_ref(),
+                GraphNameRef::DefaultGraph,
+            ))
+            .unwrap());
+    }
+
+    #[test]
+    fn test_graph_too_large_without_distributions() {
+        let graph = assessment_graph(0);
+
+        assert!(matches!(
+            fit_output_graph(graph, GraphLayout::Flat, 10),
+            Err(Error::GraphTooLarge { .. })
+        ));
+    }
+}
@@ -0,0 +1,230 @@
+use std::{collections::HashSet, env, time::Duration};
+
+use lazy_static::lazy_static;
+use rdkafka::{
+    admin::{AdminClient, AdminOptions, NewTopic, TopicReplication},
+    client::DefaultClientContext,
+    config::ClientConfig,
+    consumer::{BaseConsumer, Consumer},
+};
+
+use crate::{
+    error::Error,
+    kafka::{
+        inputs::{InputSpec, INPUTS},
+        router::{output_topics, Route, OUTPUT_ROUTES},
+        BROKERS, OUTPUT_TOPIC,
+    },
+};
+
+lazy_static! {
+    pub static ref SKIP_TOPIC_CHECK: bool = env::var("SKIP_TOPIC_CHECK")
+        .map(|v| v == "true")
+        .unwrap_or(false);
+    pub static ref CREATE_MISSING_TOPICS: bool = env::var("CREATE_MISSING_TOPICS")
+        .map(|v| v == "true")
+        .unwrap_or(false);
+    pub static ref TOPIC_PARTITIONS: i32 = env::var("TOPIC_PARTITIONS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1);
+    pub static ref TOPIC_REPLICATION: i32 = env::var("TOPIC_REPLICATION")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1);
+}
+
+/// What to do about the topics the service depends on.
+#[derive(Debug, PartialEq, Eq)]
+enum TopicAction {
+    None,
+    Create(Vec<String>),
+    Fail(Vec<String>),
+}
+
+fn topic_action(
+    existing: &HashSet<String>,
+    required: &[&str],
+    create_missing: bool,
+) -> TopicAction {
+    let missing = required
+        .iter()
+        .filter(|topic| !existing.contains(**topic))
+        .map(|topic| topic.to_string())
+        .collect::<Vec<String>>();
+
+    if missing.is_empty() {
+        TopicAction::None
+    } else if create_missing {
+        TopicAction::Create(missing)
+    } else {
+        TopicAction::Fail(missing)
+    }
+}
+
+/// Names of the topics in the cluster, without requesting any particular
+/// topic, as that may auto-create it.
+fn existing_topics() -> Result<HashSet<String>, Error> {
+    let consumer: BaseConsumer = ClientConfig::new()
+        .set("bootstrap.servers", BROKERS.clone())
+        .create()?;
+    let metadata = consumer.fetch_metadata(None, Duration::from_secs(10))?;
+
+    Ok(metadata
+        .topics()
+        .iter()
+        .filter(|topic| topic.error().is_none())
+        .map(|topic| topic.name().to_string())
+        .collect())
+}
+
+/// Names of the topics in the cluster, fetched from the brokers.
+pub async fn fetch_existing_topics() -> Result<HashSet<String>, Error> {
+    tokio::task::spawn_blocking(existing_topics)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Input topics, followed by the topics MQA events may be produced to.
+fn required_topics<'a>(
+    inputs: &'a [InputSpec],
+    output_topic: &'a str,
+    routes: &'a [Route],
+) -> Vec<&'a str> {
+    inputs
+        .iter()
+        .map(|input| input.topic.as_str())
+        .chain(output_topics(output_topic, routes))
+        .collect()
+}
+
+/// Input, output and route topics missing from the existing topics.
+pub fn missing_topics(existing: &HashSet<String>) -> Vec<String> {
+    let required = required_topics(&INPUTS, &OUTPUT_TOPIC, &OUTPUT_ROUTES);
+    match topic_action(existing, &required, false) {
+        TopicAction::Fail(missing) => missing,
+        _ => Vec::new(),
+    }
+}
+
+async fn create_topics(topics: &[String]) -> Result<(), Error> {
+    let admin: AdminClient<DefaultClientContext> = ClientConfig::new()
+        .set("bootstrap.servers", BROKERS.clone())
+        .create()?;
+    let new_topics = topics
+        .iter()
+        .map(|topic| {
+            NewTopic::new(
+                topic,
+                *TOPIC_PARTITIONS,
+                TopicReplication::Fixed(*TOPIC_REPLICATION),
+            )
+        })
+        .collect::<Vec<NewTopic>>();
+
+    for result in admin
+        .create_topics(&new_topics, &AdminOptions::new())
+        .await?
+    {
+        match result {
+            Ok(topic) => tracing::info!(topic, "topic created"),
+            Err((topic, code)) => {
+                return Err(format!("unable to create topic '{}': {}", topic, code).into())
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check that the input, output and route topics exist, creating them if configured
+/// to. Fails with an error naming the missing topics otherwise.
+pub async fn ensure_topics() -> Result<(), Error> {
+    if *SKIP_TOPIC_CHECK {
+        tracing::info!("skipping topic check");
+        return Ok(());
+    }
+
+    let existing = fetch_existing_topics().await?;
+    let required = required_topics(&INPUTS, &OUTPUT_TOPIC, &OUTPUT_ROUTES);
+    match topic_action(&existing, &required, *CREATE_MISSING_TOPICS) {
+        TopicAction::None => Ok(()),
+        TopicAction::Create(missing) => {
+            tracing::warn!(topics = missing.join(","), "creating missing topics");
+            create_topics(&missing).await
+        }
+        TopicAction::Fail(missing) => Err(format!(
+            "missing topics: {}, create them or set CREATE_MISSING_TOPICS=true",
+            missing.join(", ")
+        )
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kafka::router::parse_routes;
+
+    fn metadata(topics: &[&str]) -> HashSet<String> {
+        topics.iter().map(|topic| topic.to_string()).collect()
+    }
+
+    #[test]
+    fn test_required_topics() {
+        let routes = parse_routes(
+            "mqa-scoring=https://data.brreg.no/974760673, mqa-events=https://data.brreg.no/",
+        )
+        .unwrap();
+        assert_eq!(
+            vec!["mqa-dataset-events", "mqa-events", "mqa-scoring"],
+            required_topics(
+                &[InputSpec::new("mqa-dataset-events", 1)],
+                "mqa-events",
+                &routes
+            )
+        );
+    }
+
+    #[test]
+    fn test_all_topics_exist() {
+        assert_eq!(
+            TopicAction::None,
+            topic_action(
+                &metadata(&["mqa-dataset-events", "mqa-events", "dataset-events"]),
+                &["mqa-dataset-events", "mqa-events"],
+                false
+            )
+        );
+    }
+
+    #[test]
+    fn test_missing_topics_fail() {
+        assert_eq!(
+            TopicAction::Fail(vec!["mqa-events".to_string()]),
+            topic_action(
+                &metadata(&["mqa-dataset-events"]),
+                &["mqa-dataset-events", "mqa-events"],
+                false
+            )
+        );
+        assert_eq!(
+            TopicAction::Fail(vec![
+                "mqa-dataset-events".to_string(),
+                "mqa-events".to_string()
+            ]),
+            topic_action(&metadata(&[]), &["mqa-dataset-events", "mqa-events"], false)
+        );
+    }
+
+    #[test]
+    fn test_missing_topics_created() {
+        assert_eq!(
+            TopicAction::Create(vec!["mqa-events".to_string()]),
+            topic_action(
+                &metadata(&["mqa-dataset-events"]),
+                &["mqa-dataset-events", "mqa-events"],
+                true
+            )
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    env,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use lazy_static::lazy_static;
+use sha2::{Digest, Sha256};
+
+use crate::{error::Error, kafka::RoutedEvent, reference_data::positive_var};
+
+lazy_static! {
+    pub static ref UNCHANGED_GRAPHS: Option<Arc<UnchangedGraphs>> = env::var("SKIP_UNCHANGED")
+        .ok()
+        .map(|v| {
+            v.parse().unwrap_or_else(|e: Error| {
+                tracing::error!(error = e.to_string(), "invalid skip unchanged mode");
+                std::process::exit(1);
+            })
+        })
+        .map(|mode| {
+            Arc::new(UnchangedGraphs::new(
+                mode,
+                positive_var("SKIP_UNCHANGED_CACHE_SIZE", 10000) as usize,
+            ))
+        });
+}
+
+/// What is produced for an event whose graph is unchanged: the MQA events of
+/// its last assessment, with timestamps of the event, or nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipUnchanged {
+    Emit,
+    Silent,
+}
+
+impl FromStr for SkipUnchanged {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "emit" => Ok(SkipUnchanged::Emit),
+            "silent" => Ok(SkipUnchanged::Silent),
+            _ => Err(format!(
+                "invalid skip unchanged mode '{}', expected 'emit' or 'silent'",
+                s
+            )
+            .into()),
+        }
+    }
+}
+
+/// Hash of the graph last assessed for each fdkId, with the MQA events of
+/// the assessment when they are emitted again, for at most `capacity` fdkIds,
+/// evicting those least recently seen.
+///
+/// This is best-effort: the cache is in memory of a single process, so
+/// unchanged graphs are assessed again after a restart, by other instances of
+/// the consumer group, and after their fdkId is evicted.
+#[derive(Debug)]
+pub struct UnchangedGraphs {
+    mode: SkipUnchanged,
+    capacity: usize,
+    cache: Mutex<Cache>,
+}
+
+#[derive(Debug, Default)]
+struct Cache {
+    entries: HashMap<String, Entry>,
+    /// fdkIds by when they were last seen.
+    recency: BTreeMap<u64, String>,
+    clock: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    graph_hash: String,
+    events: Vec<RoutedEvent>,
+    seen: u64,
+}
+
+impl Cache {
+    fn touch(&mut self, fdk_id: &str) {
+        self.clock += 1;
+        if let Some(entry) = self.entries.get_mut(fdk_id) {
+            self.recency.remove(&entry.seen);
+            entry.seen = self.clock;
+            self.recency.insert(self.clock, fdk_id.to_string());
+        }
+    }
+}
+
+impl UnchangedGraphs {
+    pub fn new(mode: SkipUnchanged, capacity: usize) -> Self {
+        UnchangedGraphs {
+            mode,
+            capacity,
+            cache: Mutex::new(Cache::default()),
+        }
+    }
+
+    pub fn mode(&self) -> SkipUnchanged {
+        self.mode
+    }
+
+    /// MQA events of the last assessment of an fdkId when its graph had the
+    /// given hash, empty when they are not emitted again, or none when the
+    /// graph changed or is not known.
+    pub fn unchanged(&self, fdk_id: &str, graph_hash: &str) -> Option<Vec<RoutedEvent>> {
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        cache.touch(fdk_id);
+        cache
+            .entries
+            .get(fdk_id)
+            .filter(|entry| entry.graph_hash == graph_hash)
+            .map(|entry| entry.events.clone())
+    }
+
+    /// Records the hash of the graph assessed for an fdkId, and the MQA events
+    /// of the assessment when they are emitted again.
+    pub fn insert(&self, fdk_id: &str, graph_hash: String, events: &[RoutedEvent]) {
+        if self.capacity == 0 {
+            return;
+        }
+        let events = match self.mode {
+            SkipUnchanged::Emit => events.to_vec(),
+            SkipUnchanged::Silent => Vec::new(),
+        };
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = cache.entries.get_mut(fdk_id) {
+            entry.graph_hash = graph_hash;
+            entry.events = events;
+            cache.touch(fdk_id);
+            return;
+        }
+        while cache.entries.len() >= self.capacity {
+            let Some((_, evicted)) = cache.recency.pop_first() else {
+                break;
+            };
+            cache.entries.remove(&evicted);
+        }
+        cache.clock += 1;
+        let seen = cache.clock;
+        cache.recency.insert(seen, fdk_id.to_string());
+        cache.entries.insert(
+            fdk_id.to_string(),
+            Entry {
+                graph_hash,
+                events,
+                seen,
+            },
+        );
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entries
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Graph assessed for an fdkId, recorded as its last assessed graph once the
+/// records of its MQA events are delivered, so a graph whose events are lost
+/// is assessed again when its message is consumed again.
+#[derive(Debug)]
+pub struct AssessedGraph {
+    graphs: Arc<UnchangedGraphs>,
+    fdk_id: String,
+    graph_hash: String,
+    events: Vec<RoutedEvent>,
+}
+
+impl AssessedGraph {
+    pub fn new(
+        graphs: Arc<UnchangedGraphs>,
+        fdk_id: String,
+        graph_hash: String,
+        events: Vec<RoutedEvent>,
+    ) -> Self {
+        AssessedGraph {
+            graphs,
+            fdk_id,
+            graph_hash,
+            events,
+        }
+    }
+
+    /// Records the graph as the last assessed for its fdkId.
+    pub fn record(self) {
+        self.graphs
+            .insert(&self.fdk_id, self.graph_hash, &self.events);
+    }
+}
+
+/// Hex encoded SHA-256 of the bytes of a graph, so that any change to the
+/// graph, also to how its blank nodes are connected, has it assessed again.
+pub fn graph_hash(graph: &str) -> String {
+    format!("{:x}", Sha256::digest(graph.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::{MQAEventType, MqaEvent};
+
+    fn mqa_event(fdk_id: &str) -> RoutedEvent {
+        let mqa_event = MqaEvent {
+            event_type: MQAEventType::PropertiesChecked,
+            fdk_id: fdk_id.to_string(),
+            graph: String::new(),
+            timestamp: 1,
+            source_timestamp: None,
+            graph_hash: None,
+            metrics_revision: None,
+            graph_encoding: None,
+            removed_assessments: None,
+            input_quads: None,
+            output_quads: None,
+        };
+        RoutedEvent {
+            mqa_event,
+            publishers: vec!["https://publisher.foo".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_unchanged() {
+        let graphs = UnchangedGraphs::new(SkipUnchanged::Emit, 10);
+        assert!(graphs.unchanged("fdk-id", "hash").is_none());
+
+        graphs.insert("fdk-id", "hash".to_string(), &[mqa_event("fdk-id")]);
+        let events = graphs.unchanged("fdk-id", "hash").unwrap();
+        assert_eq!(1, events.len());
+        assert_eq!("fdk-id", events[0].mqa_event.fdk_id);
+        assert_eq!(vec!["https://publisher.foo"], events[0].publishers);
+        assert!(graphs.unchanged("fdk-id", "changed").is_none());
+        assert!(graphs.unchanged("other-fdk-id", "hash").is_none());
+
+        graphs.insert("fdk-id", "changed".to_string(), &[mqa_event("fdk-id")]);
+        assert!(graphs.unchanged("fdk-id", "hash").is_none());
+        assert!(graphs.unchanged("fdk-id", "changed").is_some());
+        assert_eq!(1, graphs.len());
+    }
+
+    #[test]
+    fn test_silent_keeps_no_events() {
+        let graphs = UnchangedGraphs::new(SkipUnchanged::Silent, 10);
+        graphs.insert("fdk-id", "hash".to_string(), &[mqa_event("fdk-id")]);
+
+        assert_eq!(Some(0), graphs.unchanged("fdk-id", "hash").map(|e| e.len()));
+    }
+
+    #[test]
+    fn test_least_recently_seen_evicted() {
+        let graphs = UnchangedGraphs::new(SkipUnchanged::Silent, 2);
+        graphs.insert("a", "hash-a".to_string(), &[]);
+        graphs.insert("b", "hash-b".to_string(), &[]);
+        // Seeing a makes b the least recently seen.
+        assert!(graphs.unchanged("a", "hash-a").is_some());
+        graphs.insert("c", "hash-c".to_string(), &[]);
+
+        assert_eq!(2, graphs.len());
+        assert!(graphs.unchanged("a", "hash-a").is_some());
+        assert!(graphs.unchanged("b", "hash-b").is_none());
+        assert!(graphs.unchanged("c", "hash-c").is_some());
+
+        let graphs = UnchangedGraphs::new(SkipUnchanged::Silent, 0);
+        graphs.insert("a", "hash-a".to_string(), &[]);
+        assert!(graphs.is_empty());
+    }
+
+    #[test]
+    fn test_assessed_graph_recorded() {
+        let graphs = Arc::new(UnchangedGraphs::new(SkipUnchanged::Emit, 10));
+        let assessed = AssessedGraph::new(
+            graphs.clone(),
+            "fdk-id".to_string(),
+            "hash".to_string(),
+            vec![mqa_event("fdk-id")],
+        );
+        assert!(graphs.is_empty());
+
+        assessed.record();
+        assert_eq!(Some(1), graphs.unchanged("fdk-id", "hash").map(|e| e.len()));
+    }
+
+    #[test]
+    fn test_graph_hash() {
+        let graph = "<https://dataset.foo> a <http://www.w3.org/ns/dcat#Dataset> .";
+        assert_eq!(graph_hash(graph), graph_hash(graph));
+        assert_ne!(
+            graph_hash(graph),
+            graph_hash("<https://dataset.bar> a <http://www.w3.org/ns/dcat#Dataset> .")
+        );
+        // Graphs differing only in how their blank nodes are connected.
+        assert_ne!(
+            graph_hash("_:a <https://p.foo> _:b . _:b <https://p.foo> _:a ."),
+            graph_hash("_:a <https://p.foo> _:a . _:b <https://p.foo> _:b .")
+        );
+    }
+
+    #[test]
+    fn test_parse_skip_unchanged() {
+        assert_eq!(SkipUnchanged::Emit, "emit".parse().unwrap());
+        assert_eq!(SkipUnchanged::Silent, "silent".parse().unwrap());
+        assert!("always".parse::<SkipUnchanged>().is_err());
+    }
+}
@@ -1,8 +1,34 @@
+pub mod assess_api;
+pub mod assessment_graph;
+mod codec;
+pub mod compliance;
+pub mod config_check;
+pub mod custom_metrics;
+pub mod diff;
 pub mod error;
+mod format_classification;
+mod format_inference;
 pub mod kafka;
+pub mod log_level;
 mod metrics;
+pub mod metrics_catalog;
+pub mod metrics_registry;
+mod mqa_model;
+pub mod post_processing;
 pub mod prometheus_metrics;
+mod proxy;
 mod rdf;
 mod reference_data;
 pub mod schemas;
+pub mod store_pool;
+pub mod supervisor;
+#[cfg(any(test, feature = "bench-utils"))]
+pub mod testdata;
 pub mod vocab;
+
+pub use metrics::{
+    assess_with_options, assess_with_summary, assessment_version,
+    parse_rdf_graph_and_calculate_metrics, AssessmentOptions, GraphLayout, GraphStats,
+    Measurement, MeasurementSummary, METRICS_REVISION,
+};
+pub use reference_data::REFERENCE_DATA_PROFILES;
@@ -0,0 +1,85 @@
+use std::env;
+
+use actix_web::{http::StatusCode, web, HttpRequest, HttpResponse};
+use lazy_static::lazy_static;
+use tracing_subscriber::{
+    fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Registry,
+};
+
+lazy_static! {
+    pub static ref ADMIN_TOKEN: Option<String> = env::var("ADMIN_TOKEN").ok();
+}
+
+pub const ADMIN_TOKEN_HEADER: &str = "x-admin-token";
+
+/// Handle replacing the log filter of the running service.
+pub type LogLevelHandle = reload::Handle<EnvFilter, Registry>;
+
+/// Installs the global JSON logger, filtered by the `RUST_LOG` directives,
+/// and returns the handle changing them at runtime.
+pub fn init_tracing() -> LogLevelHandle {
+    let (filter, handle) = reload::Layer::new(EnvFilter::from_default_env());
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(
+            fmt::layer()
+                .json()
+                .with_target(false)
+                .with_current_span(false),
+        )
+        .init();
+    handle
+}
+
+/// Handle and token of the log level endpoint.
+#[derive(Clone)]
+struct LogLevelAdmin {
+    handle: LogLevelHandle,
+    token: Option<String>,
+}
+
+/// Registers `PUT /admin/log-level`, replacing the log filter with the
+/// `RUST_LOG` style directives of the request body. When `token` is set,
+/// requests must carry it in the `x-admin-token` header.
+pub fn configure(cfg: &mut web::ServiceConfig, handle: LogLevelHandle, token: Option<String>) {
+    cfg.service(
+        web::resource("/admin/log-level")
+            .app_data(web::Data::new(LogLevelAdmin { handle, token }))
+            .route(web::put().to(set_log_level)),
+    );
+}
+
+async fn set_log_level(
+    request: HttpRequest,
+    body: String,
+    admin: web::Data<LogLevelAdmin>,
+) -> HttpResponse {
+    if let Some(token) = &admin.token {
+        let authorized = request
+            .headers()
+            .get(ADMIN_TOKEN_HEADER)
+            .is_some_and(|v| v.as_bytes() == token.as_bytes());
+        if !authorized {
+            return HttpResponse::Unauthorized().finish();
+        }
+    }
+
+    let directives = body.trim();
+    let filter = match EnvFilter::try_new(directives) {
+        Ok(filter) => filter,
+        Err(e) => {
+            return HttpResponse::build(StatusCode::BAD_REQUEST)
+                .body(format!("invalid log directives '{}': {}", directives, e))
+        }
+    };
+    match admin.handle.reload(filter) {
+        Ok(_) => {
+            tracing::info!(directives, "log level changed");
+            HttpResponse::Ok().body(directives.to_string())
+        }
+        Err(e) => {
+            tracing::error!(error = e.to_string(), "failed to change log level");
+            HttpResponse::InternalServerError().body(e.to_string())
+        }
+    }
+}
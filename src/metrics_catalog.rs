@@ -0,0 +1,222 @@
+use std::{env, str::FromStr};
+
+use actix_web::{web, HttpResponse};
+use lazy_static::lazy_static;
+use serde_derive::Serialize;
+
+use crate::{error::Error, metrics::AssessmentOptions, metrics_registry::registry};
+
+lazy_static! {
+    pub static ref LIST_METRICS: Option<CatalogFormat> = env::var("LIST_METRICS").ok().map(|v| {
+        v.parse().unwrap_or_else(|e: Error| {
+            tracing::error!(error = e.to_string(), "invalid metrics list format");
+            std::process::exit(1);
+        })
+    });
+}
+
+/// Columns of the metrics catalog as a table.
+const TABLE_HEADER: [&str; 6] = [
+    "METRIC",
+    "TARGETS",
+    "DIMENSION",
+    "DATATYPE",
+    "WEIGHT",
+    "ENABLED",
+];
+
+/// Metric of the registry, as listed to integrators.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CatalogEntry {
+    pub metric: String,
+    /// Kinds of nodes the metric is measured on.
+    pub targets: Vec<&'static str>,
+    pub dimension: String,
+    pub datatype: String,
+    pub weight: u32,
+    /// Whether the metric is measured with the options of the catalog.
+    pub enabled: bool,
+}
+
+/// Format the metrics catalog is printed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatalogFormat {
+    Json,
+    Table,
+}
+
+impl FromStr for CatalogFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(CatalogFormat::Json),
+            "table" => Ok(CatalogFormat::Table),
+            _ => Err(format!(
+                "invalid metrics list format '{}', expected 'json' or 'table'",
+                s
+            )
+            .into()),
+        }
+    }
+}
+
+/// Metrics of the registry, in registry order, enabled as by the options.
+pub fn metrics_catalog(options: &AssessmentOptions) -> Vec<CatalogEntry> {
+    registry()
+        .iter()
+        .map(|descriptor| CatalogEntry {
+            metric: descriptor.metric.as_str().to_string(),
+            targets: descriptor
+                .levels
+                .iter()
+                .map(|level| level.as_str())
+                .collect(),
+            dimension: descriptor.dimension.as_str().to_string(),
+            datatype: descriptor.datatype.as_str().to_string(),
+            weight: descriptor.weight,
+            enabled: options.measures(descriptor.metric),
+        })
+        .collect()
+}
+
+/// The metrics catalog in the given format.
+pub fn format_catalog(catalog: &[CatalogEntry], format: CatalogFormat) -> Result<String, Error> {
+    match format {
+        CatalogFormat::Json => Ok(serde_json::to_string_pretty(catalog)?),
+        CatalogFormat::Table => Ok(catalog_table(catalog)),
+    }
+}
+
+/// The metrics catalog as a table of left-aligned columns, one row per
+/// metric.
+fn catalog_table(catalog: &[CatalogEntry]) -> String {
+    let rows = std::iter::once(TABLE_HEADER.map(str::to_string).to_vec())
+        .chain(catalog.iter().map(|entry| {
+            vec![
+                entry.metric.clone(),
+                entry.targets.join(","),
+                entry.dimension.clone(),
+                entry.datatype.clone(),
+                entry.weight.to_string(),
+                entry.enabled.to_string(),
+            ]
+        }))
+        .collect::<Vec<Vec<String>>>();
+    let widths = (0..TABLE_HEADER.len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect::<Vec<usize>>();
+
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect::<Vec<String>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Registers `GET /metrics-catalog`, listing the given catalog as JSON.
+pub fn configure(cfg: &mut web::ServiceConfig, catalog: Vec<CatalogEntry>) {
+    cfg.service(
+        web::resource("/metrics-catalog")
+            .app_data(web::Data::new(catalog))
+            .route(web::get().to(list_metrics)),
+    );
+}
+
+async fn list_metrics(catalog: web::Data<Vec<CatalogEntry>>) -> HttpResponse {
+    HttpResponse::Ok().json(catalog.get_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{metrics_registry::tests::measured_metrics, vocab::dcat_mqa};
+
+    fn entry<'a>(catalog: &'a [CatalogEntry], metric: &str) -> &'a CatalogEntry {
+        catalog.iter().find(|entry| entry.metric == metric).unwrap()
+    }
+
+    #[test]
+    fn test_catalog_json_lists_measured_metrics() {
+        let json = format_catalog(
+            &metrics_catalog(&AssessmentOptions::default()),
+            CatalogFormat::Json,
+        )
+        .unwrap();
+        let listed = serde_json::from_str::<Vec<serde_json::Value>>(&json)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry["metric"].as_str().unwrap().to_string())
+            .collect::<Vec<String>>();
+
+        let measured = measured_metrics();
+        assert!(measured.contains(dcat_mqa::URL_HTTPS.as_str()));
+        for metric in measured {
+            assert!(listed.contains(&metric), "{} is not listed", metric);
+        }
+        assert_eq!(registry().len(), listed.len());
+    }
+
+    #[test]
+    fn test_catalog_entry() {
+        let catalog = metrics_catalog(&AssessmentOptions::default());
+
+        assert_eq!(
+            &CatalogEntry {
+                metric: dcat_mqa::DATE_ISSUED_AVAILABILITY.as_str().to_string(),
+                targets: vec!["dataset", "distribution"],
+                dimension: dcat_mqa::CONTEXTUALITY.as_str().to_string(),
+                datatype: "http://www.w3.org/2001/XMLSchema#boolean".to_string(),
+                weight: 5,
+                enabled: true,
+            },
+            entry(&catalog, dcat_mqa::DATE_ISSUED_AVAILABILITY.as_str())
+        );
+    }
+
+    #[test]
+    fn test_catalog_enabled_by_options() {
+        let catalog = metrics_catalog(&AssessmentOptions {
+            measure_language_tags: false,
+            ..Default::default()
+        });
+
+        assert!(!entry(&catalog, dcat_mqa::KEYWORD_LANGUAGE_TAGGED.as_str()).enabled);
+        assert!(!entry(&catalog, dcat_mqa::DESCRIPTION_LANGUAGE_TAGGED.as_str()).enabled);
+        assert!(entry(&catalog, dcat_mqa::KEYWORD_AVAILABILITY.as_str()).enabled);
+    }
+
+    #[test]
+    fn test_catalog_table() {
+        let catalog = metrics_catalog(&AssessmentOptions::default());
+        let table = format_catalog(&catalog, CatalogFormat::Table).unwrap();
+        let lines = table.lines().collect::<Vec<&str>>();
+
+        assert_eq!(catalog.len() + 1, lines.len());
+        assert!(lines[0].starts_with("METRIC "));
+        assert!(lines[0].ends_with("ENABLED"));
+        // Columns are aligned.
+        let column = lines[0].find("TARGETS").unwrap();
+        assert!(lines[1..].iter().all(|line| line[..column].ends_with("  ")));
+        assert!(lines[1].starts_with(&catalog[0].metric));
+    }
+
+    #[test]
+    fn test_catalog_format() {
+        assert_eq!(CatalogFormat::Json, "json".parse().unwrap());
+        assert_eq!(CatalogFormat::Table, "table".parse().unwrap());
+        assert!("yaml".parse::<CatalogFormat>().is_err());
+    }
+}
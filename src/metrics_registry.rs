@@ -0,0 +1,472 @@
+use oxigraph::model::{vocab::xsd, NamedNodeRef};
+
+use crate::vocab::{dcat, dcat_mqa, dcterms};
+
+/// Kind of node a metric is measured on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Dataset,
+    Distribution,
+    DataService,
+}
+
+impl Level {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Level::Dataset => "dataset",
+            Level::Distribution => "distribution",
+            Level::DataService => "dataService",
+        }
+    }
+}
+
+/// Description of an MQA metric.
+#[derive(Debug, Clone, Copy)]
+pub struct MetricDescriptor {
+    pub metric: NamedNodeRef<'static>,
+    /// Datatype of the values of its measurements.
+    pub datatype: NamedNodeRef<'static>,
+    pub dimension: NamedNodeRef<'static>,
+    /// Weight in the score of its dimension, following the data.europa.eu MQA
+    /// methodology, and 0 for metrics that methodology does not score.
+    pub weight: u32,
+    pub levels: &'static [Level],
+    /// Properties of which any one being present makes the measurement true,
+    /// for metrics measuring availability. Empty for other metrics.
+    pub properties: &'static [NamedNodeRef<'static>],
+}
+
+const fn boolean(
+    metric: NamedNodeRef<'static>,
+    dimension: NamedNodeRef<'static>,
+    weight: u32,
+    levels: &'static [Level],
+    properties: &'static [NamedNodeRef<'static>],
+) -> MetricDescriptor {
+    MetricDescriptor {
+        metric,
+        datatype: xsd::BOOLEAN,
+        dimension,
+        weight,
+        levels,
+        properties,
+    }
+}
+
+const DATASET: &[Level] = &[Level::Dataset];
+const DISTRIBUTION: &[Level] = &[Level::Distribution];
+const DATASET_AND_DISTRIBUTION: &[Level] = &[Level::Dataset, Level::Distribution];
+const DATA_SERVICE: &[Level] = &[Level::DataService];
+
+static REGISTRY: [MetricDescriptor; 38] = [
+    // Findability
+    boolean(
+        dcat_mqa::KEYWORD_AVAILABILITY,
+        dcat_mqa::FINDABILITY,
+        30,
+        DATASET,
+        &[dcat::KEYWORD, dcterms::SUBJECT],
+    ),
+    boolean(
+        dcat_mqa::CATEGORY_AVAILABILITY,
+        dcat_mqa::FINDABILITY,
+        30,
+        DATASET,
+        &[dcat::THEME],
+    ),
+    boolean(
+        dcat_mqa::SPATIAL_AVAILABILITY,
+        dcat_mqa::FINDABILITY,
+        20,
+        DATASET,
+        &[dcterms::SPATIAL],
+    ),
+    boolean(
+        dcat_mqa::TEMPORAL_AVAILABILITY,
+        dcat_mqa::FINDABILITY,
+        20,
+        DATASET,
+        &[dcterms::TEMPORAL],
+    ),
+    boolean(
+        dcat_mqa::SPATIAL_VOCABULARY_ALIGNMENT,
+        dcat_mqa::FINDABILITY,
+        0,
+        DATASET,
+        &[],
+    ),
+    boolean(
+        dcat_mqa::TEMPORAL_COMPLETENESS,
+        dcat_mqa::FINDABILITY,
+        0,
+        DATASET,
+        &[],
+    ),
+    boolean(
+        dcat_mqa::TITLE_AVAILABILITY,
+        dcat_mqa::FINDABILITY,
+        0,
+        DISTRIBUTION,
+        &[dcterms::TITLE],
+    ),
+    boolean(
+        dcat_mqa::DESCRIPTION_AVAILABILITY,
+        dcat_mqa::FINDABILITY,
+        0,
+        DISTRIBUTION,
+        &[dcterms::DESCRIPTION],
+    ),
+    boolean(
+        dcat_mqa::KEYWORD_LANGUAGE_TAGGED,
+        dcat_mqa::FINDABILITY,
+        0,
+        DATASET,
+        &[],
+    ),
+    boolean(
+        dcat_mqa::DESCRIPTION_LANGUAGE_TAGGED,
+        dcat_mqa::FINDABILITY,
+        0,
+        DATASET,
+        &[],
+    ),
+    // Accessibility
+    boolean(
+        dcat_mqa::DISTRIBUTION_AVAILABILITY,
+        dcat_mqa::ACCESSIBILITY,
+        0,
+        DATASET,
+        &[],
+    ),
+    boolean(
+        dcat_mqa::NO_DUPLICATE_DISTRIBUTIONS,
+        dcat_mqa::ACCESSIBILITY,
+        0,
+        DATASET,
+        &[],
+    ),
+    boolean(
+        dcat_mqa::DOWNLOAD_URL_AVAILABILITY,
+        dcat_mqa::ACCESSIBILITY,
+        20,
+        DISTRIBUTION,
+        &[dcat::DOWNLOAD_URL],
+    ),
+    boolean(
+        dcat_mqa::URL_HTTPS,
+        dcat_mqa::ACCESSIBILITY,
+        0,
+        DISTRIBUTION,
+        &[],
+    ),
+    boolean(
+        dcat_mqa::ENDPOINT_URL_AVAILABILITY,
+        dcat_mqa::ACCESSIBILITY,
+        0,
+        DATA_SERVICE,
+        &[dcat::ENDPOINT_URL],
+    ),
+    boolean(
+        dcat_mqa::ENDPOINT_DESCRIPTION_AVAILABILITY,
+        dcat_mqa::ACCESSIBILITY,
+        0,
+        DATA_SERVICE,
+        &[dcat::ENDPOINT_DESCRIPTION],
+    ),
+    // Interoperability
+    boolean(
+        dcat_mqa::FORMAT_AVAILABILITY,
+        dcat_mqa::INTEROPERABILITY,
+        20,
+        DISTRIBUTION,
+        &[],
+    ),
+    boolean(
+        dcat_mqa::MEDIA_TYPE_AVAILABILITY,
+        dcat_mqa::INTEROPERABILITY,
+        10,
+        DISTRIBUTION,
+        &[dcat::MEDIA_TYPE],
+    ),
+    boolean(
+        dcat_mqa::FORMAT_MEDIA_TYPE_VOCABULARY_ALIGNMENT,
+        dcat_mqa::INTEROPERABILITY,
+        10,
+        DISTRIBUTION,
+        &[],
+    ),
+    boolean(
+        dcat_mqa::FORMAT_MEDIA_TYPE_NON_PROPRIETARY,
+        dcat_mqa::INTEROPERABILITY,
+        20,
+        DISTRIBUTION,
+        &[],
+    ),
+    boolean(
+        dcat_mqa::FORMAT_MEDIA_TYPE_MACHINE_INTERPRETABLE,
+        dcat_mqa::INTEROPERABILITY,
+        20,
+        DISTRIBUTION,
+        &[],
+    ),
+    boolean(
+        dcat_mqa::FORMAT_MEDIA_TYPE_CONSISTENCY,
+        dcat_mqa::INTEROPERABILITY,
+        0,
+        DISTRIBUTION,
+        &[],
+    ),
+    boolean(
+        dcat_mqa::AT_LEAST_FOUR_STARS,
+        dcat_mqa::INTEROPERABILITY,
+        0,
+        DISTRIBUTION,
+        &[],
+    ),
+    boolean(
+        dcat_mqa::STRUCTURAL_COMPLIANCE,
+        dcat_mqa::INTEROPERABILITY,
+        0,
+        DATASET,
+        &[],
+    ),
+    // Reusability
+    boolean(
+        dcat_mqa::LICENSE_AVAILABILITY,
+        dcat_mqa::REUSABILITY,
+        20,
+        DISTRIBUTION,
+        &[dcterms::LICENSE],
+    ),
+    boolean(
+        dcat_mqa::KNOWN_LICENSE,
+        dcat_mqa::REUSABILITY,
+        10,
+        DISTRIBUTION,
+        &[],
+    ),
+    boolean(
+        dcat_mqa::OPEN_LICENSE,
+        dcat_mqa::REUSABILITY,
+        0,
+        DISTRIBUTION,
+        &[],
+    ),
+    boolean(
+        dcat_mqa::ACCESS_RIGHTS_AVAILABILITY,
+        dcat_mqa::REUSABILITY,
+        10,
+        DATASET,
+        &[dcterms::ACCESS_RIGHTS],
+    ),
+    boolean(
+        dcat_mqa::ACCESS_RIGHTS_VOCABULARY_ALIGNMENT,
+        dcat_mqa::REUSABILITY,
+        5,
+        DATASET,
+        &[],
+    ),
+    boolean(
+        dcat_mqa::CONTACT_POINT_AVAILABILITY,
+        dcat_mqa::REUSABILITY,
+        20,
+        DATASET,
+        &[dcat::CONTACT_POINT],
+    ),
+    boolean(
+        dcat_mqa::PUBLISHER_AVAILABILITY,
+        dcat_mqa::REUSABILITY,
+        10,
+        DATASET,
+        &[dcterms::PUBLISHER],
+    ),
+    boolean(
+        dcat_mqa::PUBLISHER_VOCABULARY_ALIGNMENT,
+        dcat_mqa::REUSABILITY,
+        0,
+        DATASET,
+        &[],
+    ),
+    // Contextuality
+    boolean(
+        dcat_mqa::RIGHTS_AVAILABILITY,
+        dcat_mqa::CONTEXTUALITY,
+        5,
+        DISTRIBUTION,
+        &[dcterms::RIGHTS],
+    ),
+    boolean(
+        dcat_mqa::BYTE_SIZE_AVAILABILITY,
+        dcat_mqa::CONTEXTUALITY,
+        5,
+        DISTRIBUTION,
+        &[dcat::BYTE_SIZE],
+    ),
+    boolean(
+        dcat_mqa::DATE_ISSUED_AVAILABILITY,
+        dcat_mqa::CONTEXTUALITY,
+        5,
+        DATASET_AND_DISTRIBUTION,
+        &[dcterms::ISSUED],
+    ),
+    boolean(
+        dcat_mqa::DATE_MODIFIED_AVAILABILITY,
+        dcat_mqa::CONTEXTUALITY,
+        5,
+        DATASET_AND_DISTRIBUTION,
+        &[dcterms::MODIFIED],
+    ),
+    boolean(
+        dcat_mqa::ACCRUAL_PERIODICITY_AVAILABILITY,
+        dcat_mqa::CONTEXTUALITY,
+        0,
+        DATASET,
+        &[dcterms::ACCRUAL_PERIODICITY],
+    ),
+    boolean(
+        dcat_mqa::CONFORMS_TO_AVAILABILITY,
+        dcat_mqa::CONTEXTUALITY,
+        0,
+        DATASET_AND_DISTRIBUTION,
+        &[dcterms::CONFORMS_TO],
+    ),
+];
+
+/// All MQA metrics.
+pub fn registry() -> &'static [MetricDescriptor] {
+    &REGISTRY
+}
+
+/// Descriptor of a metric, if it is a known MQA metric.
+pub fn descriptor(metric: NamedNodeRef) -> Option<&'static MetricDescriptor> {
+    REGISTRY
+        .iter()
+        .find(|descriptor| descriptor.metric == metric)
+}
+
+/// Metrics measuring the availability of properties of nodes of a level.
+pub fn availability_metrics(level: Level) -> impl Iterator<Item = &'static MetricDescriptor> {
+    REGISTRY.iter().filter(move |descriptor| {
+        !descriptor.properties.is_empty() && descriptor.levels.contains(&level)
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::collections::{HashMap, HashSet};
+
+    /// Sections of the `dcat_mqa` vocabulary that do not declare metrics.
+    const NON_METRIC_SECTIONS: [&str; 3] = ["Assessment", "Dimensions", "Stars"];
+
+    /// Constants of the `dcat_mqa` vocabulary, with the section they are
+    /// declared in and their IRI.
+    fn vocabulary_constants() -> HashMap<String, (String, String)> {
+        let vocab = include_str!("vocab.rs");
+        let module = vocab
+            .split("pub mod dcat_mqa {")
+            .nth(1)
+            .and_then(|module| module.split("\n}").next())
+            .unwrap();
+
+        let mut constants = HashMap::new();
+        let mut section = String::new();
+        let mut name = None;
+        for line in module.lines().map(str::trim) {
+            if let Some(comment) = line.strip_prefix("// ") {
+                section = comment.to_string();
+            }
+            if let Some(declaration) = line.strip_prefix("pub const ") {
+                name = declaration.split(':').next().map(str::to_string);
+            }
+            if let (Some(iri), Some(constant)) = (line.split("n!(\"").nth(1), &name) {
+                let iri = iri.split('"').next().unwrap().to_string();
+                constants.insert(constant.clone(), (section.clone(), iri));
+                name = None;
+            }
+        }
+        constants
+    }
+
+    /// IRIs of the metrics `metrics.rs` refers to, which are those measured.
+    pub(crate) fn measured_metrics() -> HashSet<String> {
+        let constants = vocabulary_constants();
+        include_str!("metrics.rs")
+            .split("dcat_mqa::")
+            .skip(1)
+            .map(|reference| {
+                reference
+                    .chars()
+                    .take_while(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || *c == '_')
+                    .collect::<String>()
+            })
+            .filter_map(|name| {
+                let (section, iri) = &constants[&name];
+                (!NON_METRIC_SECTIONS.contains(&section.as_str())).then(|| iri.clone())
+            })
+            .collect()
+    }
+
+    fn is_registered(iri: &str) -> bool {
+        registry()
+            .iter()
+            .any(|descriptor| descriptor.metric.as_str() == iri)
+    }
+
+    #[test]
+    fn test_registry_covers_vocabulary_metrics() {
+        let constants = vocabulary_constants();
+        assert!(constants.contains_key("KEYWORD_AVAILABILITY"));
+
+        for (name, (section, iri)) in constants {
+            if !NON_METRIC_SECTIONS.contains(&section.as_str()) {
+                assert!(is_registered(&iri), "{} is not registered", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_registry_covers_measured_metrics() {
+        let measured = measured_metrics();
+        assert!(measured.contains(dcat_mqa::KNOWN_LICENSE.as_str()));
+
+        for iri in measured {
+            assert!(is_registered(&iri), "{} is not registered", iri);
+        }
+    }
+
+    #[test]
+    fn test_metrics_registered_once() {
+        let metrics = registry()
+            .iter()
+            .map(|descriptor| descriptor.metric)
+            .collect::<HashSet<_>>();
+        assert_eq!(registry().len(), metrics.len());
+    }
+
+    #[test]
+    fn test_availability_metrics() {
+        let dataset = availability_metrics(Level::Dataset)
+            .map(|descriptor| descriptor.metric)
+            .collect::<HashSet<_>>();
+        assert!(dataset.contains(&dcat_mqa::KEYWORD_AVAILABILITY));
+        assert!(dataset.contains(&dcat_mqa::DATE_ISSUED_AVAILABILITY));
+        assert!(!dataset.contains(&dcat_mqa::BYTE_SIZE_AVAILABILITY));
+        assert!(!dataset.contains(&dcat_mqa::SPATIAL_VOCABULARY_ALIGNMENT));
+
+        let distribution = availability_metrics(Level::Distribution)
+            .map(|descriptor| descriptor.metric)
+            .collect::<HashSet<_>>();
+        assert!(distribution.contains(&dcat_mqa::BYTE_SIZE_AVAILABILITY));
+        assert!(distribution.contains(&dcat_mqa::DATE_ISSUED_AVAILABILITY));
+        assert!(!distribution.contains(&dcat_mqa::KEYWORD_AVAILABILITY));
+    }
+
+    #[test]
+    fn test_descriptor() {
+        let descriptor = descriptor(dcat_mqa::KNOWN_LICENSE).unwrap();
+        assert_eq!(xsd::BOOLEAN, descriptor.datatype);
+        assert_eq!(dcat_mqa::REUSABILITY, descriptor.dimension);
+        assert!(super::descriptor(dcat_mqa::FIVE_STARS).is_none());
+    }
+}
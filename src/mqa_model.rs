@@ -0,0 +1,71 @@
+use oxigraph::model::NamedNodeRef;
+
+use crate::{metrics_registry::descriptor, vocab::dcat_mqa};
+
+/// MQA dimensions.
+const DIMENSIONS: [NamedNodeRef<'static>; 5] = [
+    dcat_mqa::FINDABILITY,
+    dcat_mqa::ACCESSIBILITY,
+    dcat_mqa::INTEROPERABILITY,
+    dcat_mqa::REUSABILITY,
+    dcat_mqa::CONTEXTUALITY,
+];
+
+/// Dimension a metric belongs to, if it is a known MQA metric.
+pub fn metric_dimension(metric: NamedNodeRef) -> Option<NamedNodeRef<'static>> {
+    descriptor(metric).map(|descriptor| descriptor.dimension)
+}
+
+/// Whether a node is an MQA dimension or one of its metrics.
+pub fn is_dimension_or_metric(node: NamedNodeRef) -> bool {
+    DIMENSIONS.iter().any(|dimension| *dimension == node) || descriptor(node).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics_registry::registry;
+
+    #[test]
+    fn test_is_dimension_or_metric() {
+        assert!(is_dimension_or_metric(dcat_mqa::REUSABILITY));
+        assert!(is_dimension_or_metric(dcat_mqa::OPEN_LICENSE));
+        assert!(!is_dimension_or_metric(dcat_mqa::DATASET_ASSESSMENT_CLASS));
+    }
+
+    #[test]
+    fn test_metric_dimension() {
+        assert_eq!(
+            Some(dcat_mqa::FINDABILITY),
+            metric_dimension(dcat_mqa::KEYWORD_AVAILABILITY)
+        );
+        assert_eq!(
+            Some(dcat_mqa::INTEROPERABILITY),
+            metric_dimension(dcat_mqa::AT_LEAST_FOUR_STARS)
+        );
+        assert_eq!(
+            Some(dcat_mqa::CONTEXTUALITY),
+            metric_dimension(dcat_mqa::BYTE_SIZE_AVAILABILITY)
+        );
+    }
+
+    #[test]
+    fn test_metrics_in_dimensions() {
+        for descriptor in registry() {
+            assert!(
+                DIMENSIONS.contains(&descriptor.dimension),
+                "{} is not in an MQA dimension",
+                descriptor.metric
+            );
+        }
+    }
+
+    #[test]
+    fn test_unknown_metric_has_no_dimension() {
+        assert_eq!(
+            None,
+            metric_dimension(NamedNodeRef::new_unchecked("https://metric.foo"))
+        );
+        assert_eq!(None, metric_dimension(dcat_mqa::FIVE_STARS));
+    }
+}
@@ -0,0 +1,173 @@
+use std::{fmt, sync::Arc, time::Instant};
+
+use oxigraph::{
+    model::{vocab::xsd, Literal, NamedNode, NamedNodeRef, Term},
+    store::Store,
+};
+
+use crate::{
+    error::Error,
+    metrics::GraphLayout,
+    rdf::{add_property, is_http_iri},
+    vocab::{dcat_mqa, dcterms, prov},
+};
+
+/// Step enriching the MQA graph of an assessment after its metrics are
+/// calculated and before it is validated and serialized, e.g. with results of
+/// other validations.
+pub trait PostProcessor: Send + Sync {
+    /// Name identifying the processor in logs and errors.
+    fn name(&self) -> &str;
+
+    /// Adds to the output store, given the input graph in the input store.
+    /// An error fails the assessment.
+    fn process(&self, input: &Store, output: &Store, ctx: &AssessmentContext) -> Result<(), Error>;
+}
+
+/// What post-processors know about the assessment they run after.
+#[derive(Debug, Clone)]
+pub struct AssessmentContext<'a> {
+    /// Assessed datasets, each with the IRI of its assessment.
+    pub assessments: Vec<(NamedNode, NamedNode)>,
+    /// FDK id of the assessed graph, when known.
+    pub fdk_id: Option<&'a str>,
+    /// Catalog and harvest the assessed graph comes from.
+    pub source: &'a SourceContext,
+    /// Graphs of the output the assessments are written to.
+    pub graph_layout: GraphLayout,
+    /// Start of the assessment, before the input graph was parsed.
+    pub start_time: Instant,
+}
+
+/// Identifiers of the catalog and the harvest an assessed graph comes from,
+/// when known, like from the headers of a dataset event. Each is an IRI or
+/// any other identifier.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceContext {
+    pub catalog_id: Option<String>,
+    pub harvest_id: Option<String>,
+    /// Profile of the reference data the graph is assessed with, the main
+    /// profile when none is given.
+    pub reference_data_profile: Option<String>,
+    /// IRI relative IRIs of the graph are resolved against, the default base
+    /// IRI of the assessment options when none is given.
+    pub base_iri: Option<String>,
+}
+
+/// Post-processors run in order of registration.
+#[derive(Clone, Default)]
+pub struct PostProcessors(Vec<Arc<dyn PostProcessor>>);
+
+impl PostProcessors {
+    pub fn register(&mut self, processor: impl PostProcessor + 'static) {
+        self.0.push(Arc::new(processor));
+    }
+
+    /// Runs every processor, failing with the first error.
+    pub(crate) fn run(
+        &self,
+        input: &Store,
+        output: &Store,
+        ctx: &AssessmentContext,
+    ) -> Result<(), Error> {
+        for processor in &self.0 {
+            run(processor.as_ref(), input, output, ctx)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for PostProcessors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.0.iter().map(|processor| processor.name()))
+            .finish()
+    }
+}
+
+pub(crate) fn run(
+    processor: &dyn PostProcessor,
+    input: &Store,
+    output: &Store,
+    ctx: &AssessmentContext,
+) -> Result<(), Error> {
+    processor
+        .process(input, output, ctx)
+        .map_err(|e| Error::PostProcessorFailed {
+            processor: processor.name().to_string(),
+            message: e.to_string(),
+        })
+}
+
+/// Adds the milliseconds from the start of the assessment to each dataset
+/// assessment, run when timing is emitted.
+pub struct ProcessingDuration;
+
+impl PostProcessor for ProcessingDuration {
+    fn name(&self) -> &str {
+        "processing-duration"
+    }
+
+    fn process(
+        &self,
+        _input: &Store,
+        output: &Store,
+        ctx: &AssessmentContext,
+    ) -> Result<(), Error> {
+        let millis = Literal::new_typed_literal(
+            ctx.start_time.elapsed().as_millis().to_string(),
+            xsd::INTEGER,
+        );
+        for (_, assessment) in &ctx.assessments {
+            add_property(
+                assessment.as_ref().into(),
+                dcat_mqa::PROCESSING_DURATION_MILLIS,
+                millis.as_ref().into(),
+                ctx.graph_layout.graph_of(assessment.as_ref()),
+                output,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Adds the catalog of the assessed graph to each dataset assessment as
+/// `dcterms:isPartOf`, and its harvest as `prov:wasInfluencedBy`, each an IRI
+/// or else a literal identifier.
+pub struct SourceIdentifiers;
+
+impl PostProcessor for SourceIdentifiers {
+    fn name(&self) -> &str {
+        "source-identifiers"
+    }
+
+    fn process(
+        &self,
+        _input: &Store,
+        output: &Store,
+        ctx: &AssessmentContext,
+    ) -> Result<(), Error> {
+        for (property, id) in [
+            (dcterms::IS_PART_OF, &ctx.source.catalog_id),
+            (prov::WAS_INFLUENCED_BY, &ctx.source.harvest_id),
+        ] {
+            let Some(id) = id else {
+                continue;
+            };
+            let object = match NamedNodeRef::new(id) {
+                Ok(iri) if is_http_iri(id) => Term::from(iri.into_owned()),
+                _ => Term::from(Literal::new_simple_literal(id)),
+            };
+            for (_, assessment) in &ctx.assessments {
+                add_property(
+                    assessment.as_ref().into(),
+                    property,
+                    object.as_ref(),
+                    ctx.graph_layout.graph_of(assessment.as_ref()),
+                    output,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
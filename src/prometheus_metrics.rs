@@ -1,5 +1,8 @@
 use lazy_static::lazy_static;
-use prometheus::{Encoder, Histogram, HistogramOpts, IntCounterVec, Opts, Registry};
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec, Opts,
+    Registry,
+};
 
 use crate::error::Error;
 
@@ -7,22 +10,251 @@ lazy_static! {
     pub static ref REGISTRY: Registry = Registry::new();
     pub static ref PROCESSED_MESSAGES: IntCounterVec = IntCounterVec::new(
         Opts::new("processed_messages", "Processed Messages"),
-        &["status"]
+        &["topic", "status", "dry_run"]
     )
     .unwrap_or_else(|e| {
         tracing::error!(error = e.to_string(), "processed_messages metric error");
         std::process::exit(1);
     });
-    pub static ref PROCESSING_TIME: Histogram = Histogram::with_opts(HistogramOpts {
-        common_opts: Opts::new("processing_time", "Event Processing Times"),
+    pub static ref PROCESSING_ERRORS: IntCounterVec = IntCounterVec::new(
+        Opts::new("processing_errors", "Processing Errors").namespace("fdk_mqa_property_checker"),
+        &["topic", "code", "dry_run"]
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "processing_errors metric error");
+        std::process::exit(1);
+    });
+    pub static ref PROCESSING_TIME: HistogramVec = HistogramVec::new(
+        HistogramOpts {
+            common_opts: Opts::new("processing_time", "Event Processing Times"),
+            buckets: vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 100.0],
+        },
+        &["topic"]
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "processing_time");
+        std::process::exit(1);
+    });
+    pub static ref ASSESSMENT_TIME: Histogram = Histogram::with_opts(HistogramOpts {
+        common_opts: Opts::new("assessment_time", "Assessment Calculation Times"),
         buckets: vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 100.0],
     })
     .unwrap_or_else(|e| {
-        tracing::error!(error = e.to_string(), "processing_time");
+        tracing::error!(error = e.to_string(), "assessment_time metric error");
+        std::process::exit(1);
+    });
+    pub static ref CONSUMER_LAG: IntGaugeVec = IntGaugeVec::new(
+        Opts::new("consumer_lag", "Consumer Lag").namespace("fdk_mqa_property_checker"),
+        &["topic", "partition"]
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "consumer_lag metric error");
+        std::process::exit(1);
+    });
+    pub static ref ASSIGNED_PARTITIONS: IntGaugeVec = IntGaugeVec::new(
+        Opts::new("assigned_partitions", "Assigned Partitions")
+            .namespace("fdk_mqa_property_checker"),
+        &["worker"]
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "assigned_partitions metric error");
+        std::process::exit(1);
+    });
+    pub static ref WORKER_RESTARTS: IntCounterVec = IntCounterVec::new(
+        Opts::new("worker_restarts_total", "Worker Restarts").namespace("fdk_mqa_property_checker"),
+        &["worker"]
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "worker_restarts_total metric error");
+        std::process::exit(1);
+    });
+    pub static ref STORE_RECYCLES: IntCounterVec = IntCounterVec::new(
+        Opts::new("store_recycles_total", "Store Recycles").namespace("fdk_mqa_property_checker"),
+        &["worker"]
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "store_recycles_total metric error");
+        std::process::exit(1);
+    });
+    pub static ref LAST_GRAPH_QUADS: IntGaugeVec = IntGaugeVec::new(
+        Opts::new("last_graph_quads", "Quads of Last Assessed Graph")
+            .namespace("fdk_mqa_property_checker"),
+        &["worker"]
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "last_graph_quads metric error");
+        std::process::exit(1);
+    });
+    pub static ref ASSESS_REQUESTS: IntCounterVec = IntCounterVec::new(
+        Opts::new("assess_requests_total", "Assess API Requests")
+            .namespace("fdk_mqa_property_checker"),
+        &["status"]
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "assess_requests_total metric error");
+        std::process::exit(1);
+    });
+    pub static ref LAST_PROCESSED_OFFSET: IntGaugeVec = IntGaugeVec::new(
+        Opts::new("last_processed_offset", "Last Processed Offset")
+            .namespace("fdk_mqa_property_checker"),
+        &["topic", "partition"]
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "last_processed_offset metric error");
+        std::process::exit(1);
+    });
+    pub static ref PRODUCER_QUEUE_FULL_RETRIES: IntCounter = IntCounter::with_opts(
+        Opts::new(
+            "producer_queue_full_retries_total",
+            "Sends Retried Because the Producer Queue Was Full"
+        )
+        .namespace("fdk_mqa_property_checker"),
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(
+            error = e.to_string(),
+            "producer_queue_full_retries_total metric error"
+        );
+        std::process::exit(1);
+    });
+    pub static ref REFERENCE_DATA_TIER: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "reference_data_tier",
+            "Reference Data Source Serving the Last Lookup"
+        )
+        .namespace("fdk_mqa_property_checker"),
+        &["collection", "tier"]
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "reference_data_tier metric error");
+        std::process::exit(1);
+    });
+    pub static ref SKIPPED_EVENTS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "skipped_events_total",
+            "Dataset Events Skipped Because of Their Type"
+        )
+        .namespace("fdk_mqa_property_checker"),
+        &["event_type"]
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "skipped_events_total metric error");
+        std::process::exit(1);
+    });
+    pub static ref SKIPPED_UNCHANGED: IntCounter = IntCounter::with_opts(
+        Opts::new(
+            "skipped_unchanged_total",
+            "Dataset Events Not Assessed Because Their Graph Is Unchanged"
+        )
+        .namespace("fdk_mqa_property_checker"),
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(
+            error = e.to_string(),
+            "skipped_unchanged_total metric error"
+        );
+        std::process::exit(1);
+    });
+    pub static ref RATE_LIMITED_EVENTS: IntCounter = IntCounter::with_opts(
+        Opts::new(
+            "rate_limited_events_total",
+            "Events Delayed Because Their Publisher Was Over Its Rate"
+        )
+        .namespace("fdk_mqa_property_checker"),
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(
+            error = e.to_string(),
+            "rate_limited_events_total metric error"
+        );
+        std::process::exit(1);
+    });
+    pub static ref REFERENCE_DATA_INVALID_RESPONSES: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "reference_data_invalid_response_total",
+            "Reference Data Responses That Are Not a Usable Collection"
+        )
+        .namespace("fdk_mqa_property_checker"),
+        &["endpoint"]
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(
+            error = e.to_string(),
+            "reference_data_invalid_response_total metric error"
+        );
+        std::process::exit(1);
+    });
+    pub static ref REFERENCE_DATA_BREAKER_STATE: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "reference_data_breaker_state",
+            "Circuit Breaker State of Reference Data Endpoints"
+        )
+        .namespace("fdk_mqa_property_checker"),
+        &["endpoint", "state"]
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(
+            error = e.to_string(),
+            "reference_data_breaker_state metric error"
+        );
+        std::process::exit(1);
+    });
+    pub static ref REFERENCE_DATA_THROTTLED: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "reference_data_throttled",
+            "Reference Data Endpoints Last Responding 429 or 503"
+        )
+        .namespace("fdk_mqa_property_checker"),
+        &["endpoint"]
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(
+            error = e.to_string(),
+            "reference_data_throttled metric error"
+        );
+        std::process::exit(1);
+    });
+    pub static ref OVERSIZED_OUTPUT_GRAPHS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "oversized_output_graphs_total",
+            "Output Graphs Exceeding the Maximum Size"
+        )
+        .namespace("fdk_mqa_property_checker"),
+        &["action"]
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(
+            error = e.to_string(),
+            "oversized_output_graphs_total metric error"
+        );
+        std::process::exit(1);
+    });
+    pub static ref INPUT_GRAPH_QUADS: Histogram = Histogram::with_opts(HistogramOpts {
+        common_opts: Opts::new("input_graph_quads", "Quads of Assessed Graphs")
+            .namespace("fdk_mqa_property_checker"),
+        buckets: GRAPH_QUADS_BUCKETS.to_vec(),
+    })
+    .unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "input_graph_quads metric error");
+        std::process::exit(1);
+    });
+    pub static ref OUTPUT_GRAPH_QUADS: Histogram = Histogram::with_opts(HistogramOpts {
+        common_opts: Opts::new("output_graph_quads", "Quads of MQA Graphs")
+            .namespace("fdk_mqa_property_checker"),
+        buckets: GRAPH_QUADS_BUCKETS.to_vec(),
+    })
+    .unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "output_graph_quads metric error");
         std::process::exit(1);
     });
 }
 
+/// Buckets of the numbers of quads of graphs, from single datasets to large
+/// catalogs.
+const GRAPH_QUADS_BUCKETS: [f64; 9] = [
+    10.0, 50.0, 100.0, 500.0, 1000.0, 5000.0, 10000.0, 50000.0, 100000.0,
+];
+
 pub fn register_metrics() {
     REGISTRY
         .register(Box::new(PROCESSED_MESSAGES.clone()))
@@ -31,12 +263,186 @@ pub fn register_metrics() {
             std::process::exit(1);
         });
 
+    REGISTRY
+        .register(Box::new(PROCESSING_ERRORS.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(error = e.to_string(), "processing_errors collector error");
+            std::process::exit(1);
+        });
+
     REGISTRY
         .register(Box::new(PROCESSING_TIME.clone()))
         .unwrap_or_else(|e| {
             tracing::error!(error = e.to_string(), "response_time collector error");
             std::process::exit(1);
         });
+
+    REGISTRY
+        .register(Box::new(ASSESSMENT_TIME.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(error = e.to_string(), "assessment_time collector error");
+            std::process::exit(1);
+        });
+
+    REGISTRY
+        .register(Box::new(CONSUMER_LAG.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(error = e.to_string(), "consumer_lag collector error");
+            std::process::exit(1);
+        });
+
+    REGISTRY
+        .register(Box::new(ASSIGNED_PARTITIONS.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(error = e.to_string(), "assigned_partitions collector error");
+            std::process::exit(1);
+        });
+
+    REGISTRY
+        .register(Box::new(WORKER_RESTARTS.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(
+                error = e.to_string(),
+                "worker_restarts_total collector error"
+            );
+            std::process::exit(1);
+        });
+
+    REGISTRY
+        .register(Box::new(STORE_RECYCLES.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(
+                error = e.to_string(),
+                "store_recycles_total collector error"
+            );
+            std::process::exit(1);
+        });
+
+    REGISTRY
+        .register(Box::new(LAST_GRAPH_QUADS.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(error = e.to_string(), "last_graph_quads collector error");
+            std::process::exit(1);
+        });
+
+    REGISTRY
+        .register(Box::new(ASSESS_REQUESTS.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(
+                error = e.to_string(),
+                "assess_requests_total collector error"
+            );
+            std::process::exit(1);
+        });
+
+    REGISTRY
+        .register(Box::new(LAST_PROCESSED_OFFSET.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(
+                error = e.to_string(),
+                "last_processed_offset collector error"
+            );
+            std::process::exit(1);
+        });
+
+    REGISTRY
+        .register(Box::new(PRODUCER_QUEUE_FULL_RETRIES.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(
+                error = e.to_string(),
+                "producer_queue_full_retries_total collector error"
+            );
+            std::process::exit(1);
+        });
+
+    REGISTRY
+        .register(Box::new(REFERENCE_DATA_TIER.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(error = e.to_string(), "reference_data_tier collector error");
+            std::process::exit(1);
+        });
+
+    REGISTRY
+        .register(Box::new(OVERSIZED_OUTPUT_GRAPHS.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(
+                error = e.to_string(),
+                "oversized_output_graphs_total collector error"
+            );
+            std::process::exit(1);
+        });
+
+    REGISTRY
+        .register(Box::new(SKIPPED_EVENTS.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(
+                error = e.to_string(),
+                "skipped_events_total collector error"
+            );
+            std::process::exit(1);
+        });
+
+    REGISTRY
+        .register(Box::new(SKIPPED_UNCHANGED.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(
+                error = e.to_string(),
+                "skipped_unchanged_total collector error"
+            );
+            std::process::exit(1);
+        });
+
+    REGISTRY
+        .register(Box::new(RATE_LIMITED_EVENTS.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(
+                error = e.to_string(),
+                "rate_limited_events_total collector error"
+            );
+            std::process::exit(1);
+        });
+
+    REGISTRY
+        .register(Box::new(REFERENCE_DATA_INVALID_RESPONSES.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(
+                error = e.to_string(),
+                "reference_data_invalid_response_total collector error"
+            );
+            std::process::exit(1);
+        });
+
+    REGISTRY
+        .register(Box::new(REFERENCE_DATA_BREAKER_STATE.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(
+                error = e.to_string(),
+                "reference_data_breaker_state collector error"
+            );
+            std::process::exit(1);
+        });
+    REGISTRY
+        .register(Box::new(REFERENCE_DATA_THROTTLED.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(
+                error = e.to_string(),
+                "reference_data_throttled collector error"
+            );
+            std::process::exit(1);
+        });
+    REGISTRY
+        .register(Box::new(INPUT_GRAPH_QUADS.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(error = e.to_string(), "input_graph_quads collector error");
+            std::process::exit(1);
+        });
+
+    REGISTRY
+        .register(Box::new(OUTPUT_GRAPH_QUADS.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(error = e.to_string(), "output_graph_quads collector error");
+            std::process::exit(1);
+        });
 }
 
 pub fn get_metrics() -> Result<String, Error> {
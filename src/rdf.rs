@@ -1,20 +1,161 @@
-use oxigraph::io::{RdfFormat, RdfParser};
+use oxigraph::io::{RdfFormat, RdfParseError, RdfParser, RdfSerializer};
 use oxigraph::model::vocab::{rdf, xsd};
 use oxigraph::model::*;
-use oxigraph::store::{QuadIter, SerializerError, StorageError, Store};
+use oxigraph::store::{LoaderError, QuadIter, StorageError, Store};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::sync::{Mutex, MutexGuard};
 
 use crate::error::Error;
-use crate::vocab::{dcat, dcat_mqa, dcterms, dqv, prov};
+use crate::format_classification::classify_format;
+use crate::metrics::assessment_version;
+use crate::metrics_registry::descriptor;
+use crate::mqa_model::is_dimension_or_metric;
+use crate::vocab::{dcat, dcat_mqa, dcterms, dqv, oa, prov};
+
+/// Lines shown before and after the line of a parse error.
+const SNIPPET_CONTEXT_LINES: usize = 2;
+/// Characters shown of each line around the column of a parse error.
+const SNIPPET_WIDTH: usize = 120;
 
 /// Parse Turtle RDF and load into store.
 pub fn parse_turtle(store: &Store, turtle: String) -> Result<(), Error> {
-    store.load_from_reader(
-        RdfParser::from_format(RdfFormat::Turtle)
-            .without_named_graphs()
-            .with_default_graph(GraphNameRef::DefaultGraph),
-        turtle.to_string().as_bytes().as_ref()
-    )?;
-    Ok(())
+    parse_turtle_with_base(store, turtle, None)
+}
+
+/// Parse Turtle RDF, resolving relative IRIs against the base IRI when
+/// given, and load into store. Without a base, relative IRIs are invalid
+/// Turtle, whose details name the relative subject IRIs of the graph.
+pub fn parse_turtle_with_base(
+    store: &Store,
+    turtle: String,
+    base_iri: Option<&str>,
+) -> Result<(), Error> {
+    let mut parser = RdfParser::from_format(RdfFormat::Turtle)
+        .without_named_graphs()
+        .with_default_graph(GraphNameRef::DefaultGraph);
+    if let Some(base_iri) = base_iri {
+        parser = parser.with_base_iri(base_iri)?;
+    }
+    store
+        .load_from_reader(parser, turtle.as_bytes())
+        .map_err(|e| match e {
+            LoaderError::Parsing(RdfParseError::Syntax(_)) => Error::InvalidTurtle {
+                fdk_id: None,
+                details: relative_iris_details(format_parse_error(&turtle, &e), &turtle, base_iri),
+            },
+            e => e.into(),
+        })
+}
+
+/// Details of a parse error followed, when the graph was parsed without a
+/// base IRI, by the relative subject IRIs it has.
+fn relative_iris_details(details: String, turtle: &str, base_iri: Option<&str>) -> String {
+    if base_iri.is_some() {
+        return details;
+    }
+    let relative = relative_subject_iris(turtle);
+    if relative.is_empty() {
+        return details;
+    }
+    tracing::warn!(
+        count = relative.len(),
+        "graph has relative IRIs and no base IRI to resolve them against"
+    );
+    let iris = relative
+        .iter()
+        .map(|iri| format!("<{iri}>"))
+        .collect::<Vec<String>>()
+        .join(", ");
+    format!("{details}\nrelative IRIs without a base IRI: {iris}")
+}
+
+/// Subject IRIs of a Turtle graph that have no scheme, and so cannot be
+/// resolved without a base IRI. The graph is parsed without validating IRIs,
+/// skipping the statements it fails to parse.
+pub fn relative_subject_iris(turtle: &str) -> BTreeSet<String> {
+    RdfParser::from_format(RdfFormat::Turtle)
+        .unchecked()
+        .for_reader(turtle.as_bytes())
+        .filter_map(Result::ok)
+        .filter_map(|quad| match quad.subject {
+            Subject::NamedNode(node) if !has_scheme(node.as_str()) => Some(node.into_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether an IRI starts with a scheme, a letter followed by letters, digits,
+/// `+`, `-` or `.`, and a colon.
+fn has_scheme(iri: &str) -> bool {
+    iri.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+/// Message of an error parsing Turtle followed, when its location is known,
+/// by the offending line and the lines around it, with a caret under the
+/// error. Control characters are escaped, and long lines are cut around the
+/// column of the error.
+pub fn format_parse_error(turtle: &str, err: &LoaderError) -> String {
+    let location = match err {
+        LoaderError::Parsing(RdfParseError::Syntax(e)) => e.location(),
+        _ => None,
+    };
+    let lines = turtle.split('\n').collect::<Vec<&str>>();
+    let (line, column) = match location {
+        Some(location) if (location.start.line as usize) < lines.len() => {
+            (location.start.line as usize, location.start.column as usize)
+        }
+        _ => return err.to_string(),
+    };
+
+    let first = line.saturating_sub(SNIPPET_CONTEXT_LINES);
+    let last = (line + SNIPPET_CONTEXT_LINES).min(lines.len() - 1);
+    let number_width = (last + 1).to_string().len();
+    let start = column.saturating_sub(SNIPPET_WIDTH / 2);
+
+    let mut message = err.to_string();
+    for (i, text) in lines.iter().enumerate().take(last + 1).skip(first) {
+        let marker = if i == line { '>' } else { ' ' };
+        let snippet = snippet_line(text, start);
+        message.push_str(&format!("\n{marker} {:>number_width$} | {snippet}", i + 1));
+        if i == line {
+            let caret = snippet_line(&text.chars().take(column).collect::<String>(), start)
+                .chars()
+                .count();
+            message.push_str(&format!(
+                "\n  {:number_width$} | {}^",
+                "",
+                " ".repeat(caret)
+            ));
+        }
+    }
+    message
+}
+
+/// The characters of a line from `start`, at most [`SNIPPET_WIDTH`] of them,
+/// with control characters escaped and cuts marked by an ellipsis.
+fn snippet_line(line: &str, start: usize) -> String {
+    let chars = line.trim_end_matches('\r').chars().collect::<Vec<char>>();
+    let mut snippet = String::new();
+    if start > 0 && start < chars.len() {
+        snippet.push('…');
+    }
+    for c in chars.iter().skip(start).take(SNIPPET_WIDTH) {
+        if c.is_control() {
+            snippet.extend(c.escape_default());
+        } else {
+            snippet.push(*c);
+        }
+    }
+    if chars.len() > start + SNIPPET_WIDTH {
+        snippet.push('…');
+    }
+    snippet
 }
 
 /// Retrieve datasets
@@ -27,6 +168,94 @@ pub fn list_datasets(store: &Store) -> QuadIter {
     )
 }
 
+/// Retrieve dataset series
+pub fn list_dataset_series(store: &Store) -> QuadIter {
+    store.quads_for_pattern(
+        None,
+        Some(rdf::TYPE),
+        Some(dcat::DATASET_SERIES_CLASS.into()),
+        None,
+    )
+}
+
+/// Retrieve datasets referenced from catalogs
+pub fn list_catalog_datasets(store: &Store) -> QuadIter {
+    store.quads_for_pattern(None, Some(dcat::DATASET_PREDICATE), None, None)
+}
+
+/// Retrieve catalogs
+pub fn list_catalogs(store: &Store) -> QuadIter {
+    store.quads_for_pattern(
+        None,
+        Some(rdf::TYPE),
+        Some(dcat::CATALOG_CLASS.into()),
+        None,
+    )
+}
+
+/// Datasets of the catalogs of a graph, ordered by IRI
+pub fn get_catalog_dataset_nodes(store: &Store) -> Vec<NamedNode> {
+    let mut datasets = list_catalogs(store)
+        .filter_map(Result::ok)
+        .flat_map(|catalog| {
+            store
+                .quads_for_pattern(
+                    Some(catalog.subject.as_ref()),
+                    Some(dcat::DATASET_PREDICATE),
+                    None,
+                    None,
+                )
+                .filter_map(|quad| match quad {
+                    Ok(Quad {
+                        object: Term::NamedNode(n),
+                        ..
+                    }) => Some(n),
+                    _ => None,
+                })
+                .collect::<Vec<NamedNode>>()
+        })
+        .collect::<Vec<NamedNode>>();
+    datasets.sort();
+    datasets.dedup();
+    datasets
+}
+
+/// Member datasets of a dataset series, linked with dcat:inSeries, ordered by IRI
+pub fn get_series_member_nodes(series: NamedNodeRef, store: &Store) -> Vec<NamedNode> {
+    let mut members = store
+        .quads_for_pattern(None, Some(dcat::IN_SERIES), Some(series.into()), None)
+        .filter_map(|quad| match quad {
+            Ok(Quad {
+                subject: Subject::NamedNode(n),
+                ..
+            }) if n.as_ref() != series => Some(n),
+            _ => None,
+        })
+        .collect::<Vec<NamedNode>>();
+    members.sort();
+    members.dedup();
+    members
+}
+
+/// Dataset series of a graph together with its member datasets, the series
+/// with the first IRI of those having members in the graph
+pub fn get_dataset_series_with_members(store: &Store) -> Option<(NamedNode, Vec<NamedNode>)> {
+    let mut series = list_dataset_series(store)
+        .filter_map(|quad| match quad {
+            Ok(Quad {
+                subject: Subject::NamedNode(n),
+                ..
+            }) => Some(n),
+            _ => None,
+        })
+        .collect::<Vec<NamedNode>>();
+    series.sort();
+    series.into_iter().find_map(|series| {
+        let members = get_series_member_nodes(series.as_ref(), store);
+        (!members.is_empty()).then_some((series, members))
+    })
+}
+
 /// Retrieve distributions of a dataset
 pub fn list_distributions(dataset: NamedNodeRef, store: &Store) -> QuadIter {
     store.quads_for_pattern(
@@ -37,6 +266,104 @@ pub fn list_distributions(dataset: NamedNodeRef, store: &Store) -> QuadIter {
     )
 }
 
+/// Retrieve download URLs of a distribution
+pub fn list_download_urls(distribution: NamedNodeRef, store: &Store) -> QuadIter {
+    store.quads_for_pattern(
+        Some(distribution.into()),
+        Some(dcat::DOWNLOAD_URL.into()),
+        None,
+        None,
+    )
+}
+
+/// Retrieve access URLs of a distribution
+pub fn list_access_urls(distribution: NamedNodeRef, store: &Store) -> QuadIter {
+    store.quads_for_pattern(
+        Some(distribution.into()),
+        Some(dcat::ACCESS_URL.into()),
+        None,
+        None,
+    )
+}
+
+/// Retrieve data services giving access to a distribution
+pub fn list_access_services(distribution: NamedNodeRef, store: &Store) -> QuadIter {
+    store.quads_for_pattern(
+        Some(distribution.into()),
+        Some(dcat::ACCESS_SERVICE.into()),
+        None,
+        None,
+    )
+}
+
+/// Retrieve dataset publishers
+pub fn list_publishers(dataset: NamedNodeRef, store: &Store) -> QuadIter {
+    store.quads_for_pattern(
+        Some(dataset.into()),
+        Some(dcterms::PUBLISHER.into()),
+        None,
+        None,
+    )
+}
+
+/// Retrieve dataset access rights
+pub fn list_access_rights(dataset: NamedNodeRef, store: &Store) -> QuadIter {
+    store.quads_for_pattern(
+        Some(dataset.into()),
+        Some(dcterms::ACCESS_RIGHTS.into()),
+        None,
+        None,
+    )
+}
+
+/// Retrieve dataset spatial coverage
+pub fn list_spatial(dataset: NamedNodeRef, store: &Store) -> QuadIter {
+    store.quads_for_pattern(
+        Some(dataset.into()),
+        Some(dcterms::SPATIAL.into()),
+        None,
+        None,
+    )
+}
+
+/// Retrieve dataset temporal coverage
+pub fn list_temporal(dataset: NamedNodeRef, store: &Store) -> QuadIter {
+    store.quads_for_pattern(
+        Some(dataset.into()),
+        Some(dcterms::TEMPORAL.into()),
+        None,
+        None,
+    )
+}
+
+/// Check whether a period of time, usually a blank node, has both a start and
+/// an end date typed as xsd:date or xsd:dateTime.
+pub fn is_complete_period_of_time(period: &Term, store: &Store) -> bool {
+    let subject: SubjectRef = match period {
+        Term::NamedNode(node) => node.into(),
+        Term::BlankNode(node) => node.into(),
+        _ => return false,
+    };
+
+    [dcat::START_DATE, dcat::END_DATE]
+        .into_iter()
+        .all(|property| {
+            store
+                .quads_for_pattern(Some(subject), Some(property), None, None)
+                .any(|quad| quad.is_ok_and(|quad| is_date_literal(&quad.object)))
+        })
+}
+
+/// Check whether a term is a literal typed as xsd:date or xsd:dateTime.
+pub fn is_date_literal(term: &Term) -> bool {
+    match term {
+        Term::Literal(literal) => {
+            literal.datatype() == xsd::DATE || literal.datatype() == xsd::DATE_TIME
+        }
+        _ => false,
+    }
+}
+
 /// Retrieve distribution formats
 pub fn list_formats(distribution: NamedNodeRef, store: &Store) -> QuadIter {
     store.quads_for_pattern(
@@ -57,10 +384,10 @@ pub fn list_media_types(distribution: NamedNodeRef, store: &Store) -> QuadIter {
     )
 }
 
-/// Retrieve license
-pub fn list_licenses(distribution: NamedNodeRef, store: &Store) -> QuadIter {
+/// Retrieve licenses of a dataset or distribution
+pub fn list_licenses(subject: NamedNodeRef, store: &Store) -> QuadIter {
     store.quads_for_pattern(
-        Some(distribution.into()),
+        Some(subject.into()),
         Some(dcterms::LICENSE.into()),
         None,
         None,
@@ -69,13 +396,146 @@ pub fn list_licenses(distribution: NamedNodeRef, store: &Store) -> QuadIter {
 
 /// Retrieve dataset namednode
 pub fn get_dataset_node(store: &Store) -> Option<NamedNode> {
-    list_datasets(&store).next().and_then(|d| match d {
-        Ok(Quad {
-            subject: Subject::NamedNode(n),
-            ..
-        }) => Some(n),
-        _ => None,
-    })
+    let dataset = list_datasets(&store)
+        .filter_map(|d| match d {
+            Ok(Quad {
+                subject: Subject::NamedNode(n),
+                ..
+            }) => Some(n),
+            _ => None,
+        })
+        .find(|n| !is_distribution_of_other_node(n.as_ref(), store))
+        .map(|n| (n, "rdf:type dcat:Dataset"))
+        .or_else(|| {
+            list_dataset_series(&store)
+                .find_map(|d| match d {
+                    Ok(Quad {
+                        subject: Subject::NamedNode(n),
+                        ..
+                    }) => Some(n),
+                    _ => None,
+                })
+                .map(|n| (n, "rdf:type dcat:DatasetSeries"))
+        })
+        .or_else(|| {
+            list_catalog_datasets(&store)
+                .find_map(|d| match d {
+                    Ok(Quad {
+                        object: Term::NamedNode(n),
+                        ..
+                    }) => Some(n),
+                    _ => None,
+                })
+                .map(|n| (n, "dcat:dataset"))
+        });
+
+    if let Some((node, rule)) = &dataset {
+        tracing::debug!(dataset = node.as_str(), rule, "dataset node found");
+    }
+    dataset.map(|(node, _)| node)
+}
+
+/// IRI compared when looking for duplicate dataset nodes, with the scheme and
+/// host lowercased and a trailing `/` of the path removed.
+fn normalize_dataset_iri(iri: &str) -> String {
+    let Some((scheme, rest)) = iri.split_once("://") else {
+        return iri.to_string();
+    };
+    let (authority, rest) = rest.split_at(rest.find(['/', '?', '#']).unwrap_or(rest.len()));
+    let (path, suffix) = rest.split_at(rest.find(['?', '#']).unwrap_or(rest.len()));
+    format!(
+        "{}://{}{}{}",
+        scheme.to_ascii_lowercase(),
+        authority.to_ascii_lowercase(),
+        path.strip_suffix('/').unwrap_or(path),
+        suffix
+    )
+}
+
+/// Merge dataset nodes whose IRIs only differ by a trailing `/` or the case of
+/// the host, as some harvesters duplicate them, into the first of them in IRI
+/// order, which is then assessed with the union of their properties. The
+/// store is changed in place, so it holds the graph as assessed rather than
+/// as received. Returns the remaining dataset nodes, ordered by IRI.
+pub fn deduplicate_dataset_nodes(store: &Store) -> Result<Vec<NamedNode>, StorageError> {
+    let mut datasets = list_datasets(store)
+        .filter_map(|quad| match quad {
+            Ok(Quad {
+                subject: Subject::NamedNode(n),
+                ..
+            }) => Some(n),
+            _ => None,
+        })
+        .collect::<Vec<NamedNode>>();
+    datasets.sort();
+    datasets.dedup();
+
+    let mut firsts: HashMap<String, NamedNode> = HashMap::new();
+    let mut deduplicated = Vec::new();
+    for dataset in datasets {
+        match firsts.get(&normalize_dataset_iri(dataset.as_str())) {
+            Some(first) => {
+                tracing::warn!(
+                    dataset = first.as_str(),
+                    duplicate = dataset.as_str(),
+                    "merging duplicate dataset nodes"
+                );
+                merge_node(dataset.as_ref(), first.as_ref(), store)?;
+            }
+            None => {
+                firsts.insert(normalize_dataset_iri(dataset.as_str()), dataset.clone());
+                deduplicated.push(dataset);
+            }
+        }
+    }
+    Ok(deduplicated)
+}
+
+/// Move the triples of a node, and those referring to it, to another node.
+/// An assessment of the node is dropped if the other node has one.
+fn merge_node(node: NamedNodeRef, into: NamedNodeRef, store: &Store) -> Result<(), StorageError> {
+    let has_assessment = has_property(into.into(), dcat_mqa::HAS_ASSESSMENT, store);
+    let about = store
+        .quads_for_pattern(Some(node.into()), None, None, None)
+        .collect::<Result<Vec<Quad>, _>>()?;
+    for quad in about {
+        if !(has_assessment && quad.predicate == dcat_mqa::HAS_ASSESSMENT) {
+            store.insert(QuadRef::new(
+                into,
+                quad.predicate.as_ref(),
+                quad.object.as_ref(),
+                quad.graph_name.as_ref(),
+            ))?;
+        }
+        store.remove(&quad)?;
+    }
+
+    let referring = store
+        .quads_for_pattern(None, None, Some(node.into()), None)
+        .collect::<Result<Vec<Quad>, _>>()?;
+    for quad in referring {
+        store.insert(QuadRef::new(
+            quad.subject.as_ref(),
+            quad.predicate.as_ref(),
+            into,
+            quad.graph_name.as_ref(),
+        ))?;
+        store.remove(&quad)?;
+    }
+    Ok(())
+}
+
+/// Check whether some other node links to node with dcat:distribution.
+pub fn is_distribution_of_other_node(node: NamedNodeRef, store: &Store) -> bool {
+    store
+        .quads_for_pattern(None, Some(dcat::DISTRIBUTION), Some(node.into()), None)
+        .any(|quad| match quad {
+            Ok(Quad {
+                subject: Subject::NamedNode(subject),
+                ..
+            }) => subject.as_ref() != node,
+            _ => true,
+        })
 }
 
 /// Extract assessment of node.
@@ -88,23 +548,43 @@ pub fn node_assessment(store: &Store, node: NamedNodeRef) -> Result<NamedNode, E
             None,
         )
         .next()
-        .ok_or(Error::from(format!(
-            "assessment not found for node '{}'",
-            node,
-        )))?
+        .ok_or(Error::AssessmentNotFound {
+            node: node.as_str().to_string(),
+        })?
         .map(|d| match d {
             Quad {
                 object: Term::NamedNode(n),
                 ..
             } => Ok(n),
-            _ => Err(format!(
-                "assessment of node '{}' is not a named node: '{}'",
-                node, d.object
-            )
-            .into()),
+            _ => Err(Error::InvalidAssessmentNode {
+                node: node.as_str().to_string(),
+                object: d.object.to_string(),
+            }),
         })?
 }
 
+/// Whether an IRI is absolute, with an http(s) scheme and a non-empty authority.
+pub fn is_http_iri(iri: &str) -> bool {
+    let rest = match iri.split_once("://") {
+        Some((scheme, rest))
+            if scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https") =>
+        {
+            rest
+        }
+        _ => return false,
+    };
+    !rest.starts_with(['/', '?', '#']) && !rest.is_empty()
+}
+
+/// Mint a stable assessment IRI for a node under the given base.
+pub fn mint_assessment(base_iri: &str, node: NamedNodeRef) -> Result<NamedNode, Error> {
+    Ok(NamedNode::new(format!(
+        "{}{:x}",
+        base_iri,
+        Sha256::digest(node.as_str())
+    ))?)
+}
+
 pub fn has_property(subject: SubjectRef, property: NamedNodeRef, store: &Store) -> bool {
     store
         .quads_for_pattern(Some(subject), Some(property), None, None)
@@ -112,24 +592,174 @@ pub fn has_property(subject: SubjectRef, property: NamedNodeRef, store: &Store)
         > 0
 }
 
+/// Kinds of values a property of a subject has.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PropertyValues {
+    pub iri: bool,
+    pub literal: bool,
+    pub blank_node: bool,
+}
+
+/// Kinds of values a property of the subject has, in a single scan of its
+/// values.
+pub fn property_values(
+    subject: SubjectRef,
+    property: NamedNodeRef,
+    store: &Store,
+) -> PropertyValues {
+    store
+        .quads_for_pattern(Some(subject), Some(property), None, None)
+        .filter_map(Result::ok)
+        .fold(PropertyValues::default(), |values, quad| PropertyValues {
+            iri: values.iri || quad.object.is_named_node(),
+            literal: values.literal || quad.object.is_literal(),
+            blank_node: values.blank_node || quad.object.is_blank_node(),
+        })
+}
+
+/// Whether a property of the subject has an IRI value.
+pub fn has_iri_property(subject: SubjectRef, property: NamedNodeRef, store: &Store) -> bool {
+    property_values(subject, property, store).iri
+}
+
+/// Whether a property of the subject has a literal value.
+pub fn has_literal_property(subject: SubjectRef, property: NamedNodeRef, store: &Store) -> bool {
+    property_values(subject, property, store).literal
+}
+
+/// Number of literals of a property, and how many of them have a language tag.
+pub fn literals_with_language(
+    subject: SubjectRef,
+    predicate: NamedNodeRef,
+    store: &Store,
+) -> (usize, usize) {
+    store
+        .quads_for_pattern(Some(subject), Some(predicate), None, None)
+        .filter_map(Result::ok)
+        .filter_map(|quad| match quad.object {
+            Term::Literal(literal) => Some(literal),
+            _ => None,
+        })
+        .fold((0, 0), |(total, tagged), literal| {
+            (
+                total + 1,
+                tagged + usize::from(literal.language().is_some()),
+            )
+        })
+}
+
+/// Destination of the quads of an MQA graph: a store, inserting each quad in a
+/// transaction of its own, or a [`QuadBuffer`] inserting them all at once.
+pub trait QuadSink {
+    fn insert<'a>(&self, quad: impl Into<QuadRef<'a>>) -> Result<bool, StorageError>;
+}
+
+impl QuadSink for Store {
+    fn insert<'a>(&self, quad: impl Into<QuadRef<'a>>) -> Result<bool, StorageError> {
+        Store::insert(self, quad)
+    }
+}
+
+impl<S: QuadSink> QuadSink for &S {
+    fn insert<'a>(&self, quad: impl Into<QuadRef<'a>>) -> Result<bool, StorageError> {
+        (**self).insert(quad)
+    }
+}
+
+/// Quads collected for a store, inserted with one call when flushed rather
+/// than taking the locks of the store for each quad. Blank nodes of
+/// measurements and annotations are minted when buffered, so they can be
+/// referred to before the buffer is flushed.
+#[derive(Debug, Default)]
+pub struct QuadBuffer {
+    quads: Mutex<Vec<Quad>>,
+}
+
+impl QuadBuffer {
+    pub fn new() -> Self {
+        QuadBuffer::default()
+    }
+
+    fn quads(&self) -> MutexGuard<'_, Vec<Quad>> {
+        self.quads.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn len(&self) -> usize {
+        self.quads().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.quads().is_empty()
+    }
+
+    /// Inserts the buffered quads into a store, leaving the buffer empty.
+    pub fn flush(&self, store: &Store) -> Result<(), StorageError> {
+        let quads = std::mem::take(&mut *self.quads());
+        store.extend(quads)
+    }
+}
+
+impl QuadSink for QuadBuffer {
+    /// Buffers a quad, which is always reported as new as the store it is
+    /// flushed to is not known.
+    fn insert<'a>(&self, quad: impl Into<QuadRef<'a>>) -> Result<bool, StorageError> {
+        self.quads().push(quad.into().into_owned());
+        Ok(true)
+    }
+}
+
+/// Storage error with the metric and node being written, which the error of
+/// the store does not tell.
+pub fn storage_error(
+    source: StorageError,
+    metric: Option<NamedNodeRef>,
+    node: SubjectRef,
+) -> Error {
+    Error::Storage {
+        source,
+        metric: metric.map(|metric| metric.as_str().to_string()),
+        node: Some(match node {
+            SubjectRef::NamedNode(node) => node.as_str().to_string(),
+            node => node.to_string(),
+        }),
+    }
+}
+
 pub fn add_property(
     subject: SubjectRef,
     property: NamedNodeRef,
     object: TermRef,
-    store: &Store,
-) -> Result<(), StorageError> {
-    store.insert(Quad::new(subject, property, object, GraphName::DefaultGraph).as_ref())?;
+    graph: GraphNameRef,
+    store: &impl QuadSink,
+) -> Result<(), Error> {
+    store
+        .insert(Quad::new(subject, property, object, graph).as_ref())
+        .map_err(|e| storage_error(e, None, subject))?;
     Ok(())
 }
 
-pub fn add_five_star_annotation(store: &Store) -> Result<BlankNode, StorageError> {
+/// Add the five-star annotation of a distribution assessment, targeting it.
+pub fn add_five_star_annotation(
+    dist_assessment: NamedNodeRef,
+    graph: GraphNameRef,
+    store: &impl QuadSink,
+) -> Result<BlankNode, StorageError> {
     let five_star_annotation_node = BlankNode::default();
     store.insert(
         Quad::new(
             five_star_annotation_node.as_ref(),
             rdf::TYPE,
             dqv::QUALITY_ANNOTATION_CLASS,
-            GraphName::DefaultGraph,
+            graph,
+        )
+        .as_ref(),
+    )?;
+    store.insert(
+        Quad::new(
+            five_star_annotation_node.as_ref(),
+            oa::HAS_TARGET,
+            dist_assessment,
+            graph,
         )
         .as_ref(),
     )?;
@@ -137,166 +767,1914 @@ pub fn add_five_star_annotation(store: &Store) -> Result<BlankNode, StorageError
     Ok(five_star_annotation_node)
 }
 
-pub fn get_five_star_annotation(store: &Store) -> Option<BlankNode> {
-    store
-        .quads_for_pattern(
+/// Five-star annotations targeting any of the distribution assessments, in
+/// their order and each once.
+pub fn get_five_star_annotations(
+    store: &Store,
+    dist_assessments: &[NamedNode],
+) -> Result<Vec<BlankNode>, StorageError> {
+    let mut annotations = Vec::new();
+    for dist_assessment in dist_assessments {
+        for quad in store.quads_for_pattern(
             None,
-            Some(rdf::TYPE),
-            Some(dqv::QUALITY_ANNOTATION_CLASS.into()),
+            Some(oa::HAS_TARGET),
+            Some(dist_assessment.as_ref().into()),
             None,
-        )
-        .next()
-        .and_then(|r| match r {
-            Ok(Quad {
-                subject: Subject::BlankNode(n),
-                ..
-            }) => Some(n),
-            _ => None,
-        })
+        ) {
+            let Subject::BlankNode(annotation) = quad?.subject else {
+                continue;
+            };
+            let is_five_star_annotation = store
+                .quads_for_pattern(
+                    Some(annotation.as_ref().into()),
+                    Some(rdf::TYPE),
+                    Some(dqv::QUALITY_ANNOTATION_CLASS.into()),
+                    None,
+                )
+                .next()
+                .is_some();
+            if is_five_star_annotation && !annotations.contains(&annotation) {
+                annotations.push(annotation);
+            }
+        }
+    }
+    Ok(annotations)
 }
 
 pub fn add_derived_from(
     quality_annotation: NamedOrBlankNodeRef,
     derived_from: NamedOrBlankNodeRef,
-    store: &Store,
+    graph: GraphNameRef,
+    store: &impl QuadSink,
 ) -> Result<(), StorageError> {
     store.insert(
         Quad::new(
             quality_annotation,
             prov::WAS_DERIVED_FROM,
             derived_from,
-            GraphName::DefaultGraph,
+            graph,
         )
         .as_ref(),
     )?;
     Ok(())
 }
 
-/// Insert dataset assessment into store
-pub fn insert_dataset_assessment(
-    dataset_assessment: NamedNodeRef,
-    dataset: NamedNodeRef,
-    store: &Store,
-) -> Result<(), Error> {
-    store.insert(&Quad::new(
-        dataset_assessment.clone(),
-        rdf::TYPE,
-        dcat_mqa::DATASET_ASSESSMENT_CLASS,
-        GraphName::DefaultGraph,
-    ))?;
-    store.insert(&Quad::new(
-        dataset_assessment.clone(),
-        dcat_mqa::ASSESSMENT_OF,
-        dataset,
-        GraphName::DefaultGraph,
-    ))?;
-
-    Ok(())
+/// Add an annotation of a quality measurement with the given motivation.
+fn add_annotation(
+    measurement: BlankNodeRef,
+    motivation: NamedNodeRef,
+    graph: GraphNameRef,
+    store: &impl QuadSink,
+) -> Result<BlankNode, StorageError> {
+    let annotation = BlankNode::default();
+    store
+        .insert(Quad::new(annotation.as_ref(), rdf::TYPE, oa::ANNOTATION_CLASS, graph).as_ref())?;
+    store.insert(Quad::new(annotation.as_ref(), oa::HAS_TARGET, measurement, graph).as_ref())?;
+    store.insert(Quad::new(annotation.as_ref(), oa::MOTIVATED_BY, motivation, graph).as_ref())?;
+    Ok(annotation)
 }
 
-/// Insert distribution assessment into store
-pub fn insert_distribution_assessment(
+/// Add an annotation explaining a quality measurement, given as
+/// `(language, text)` pairs.
+pub fn add_measurement_annotation(
+    measurement: BlankNodeRef,
+    explanations: &[(&str, &str)],
+    graph: GraphNameRef,
+    store: &impl QuadSink,
+) -> Result<BlankNode, StorageError> {
+    let annotation = add_annotation(measurement, oa::DESCRIBING, graph, store)?;
+    for (language, text) in explanations {
+        store.insert(
+            Quad::new(
+                annotation.as_ref(),
+                oa::BODY_VALUE,
+                Literal::new_language_tagged_literal_unchecked(*text, *language),
+                graph,
+            )
+            .as_ref(),
+        )?;
+    }
+    Ok(annotation)
+}
+
+/// Add an annotation naming a value, such as an IRI not found in a
+/// controlled vocabulary, that did not satisfy a quality measurement.
+pub fn add_violation_annotation(
+    measurement: BlankNodeRef,
+    offending_iri: NamedNodeRef,
+    graph: GraphNameRef,
+    store: &impl QuadSink,
+) -> Result<BlankNode, StorageError> {
+    let annotation = add_annotation(measurement, oa::ASSESSING, graph, store)?;
+    store.insert(
+        Quad::new(
+            annotation.as_ref(),
+            oa::HAS_BODY,
+            Literal::new_typed_literal(offending_iri.as_str(), xsd::ANY_URI),
+            graph,
+        )
+        .as_ref(),
+    )?;
+    Ok(annotation)
+}
+
+/// Add an annotation naming a property with a number of values outside the
+/// cardinality required by a quality measurement, with that number as its
+/// `rdf:value`.
+pub fn add_cardinality_annotation(
+    measurement: BlankNodeRef,
+    predicate: NamedNodeRef,
+    count: usize,
+    graph: GraphNameRef,
+    store: &impl QuadSink,
+) -> Result<BlankNode, StorageError> {
+    let annotation = add_violation_annotation(measurement, predicate, graph, store)?;
+    store.insert(
+        Quad::new(
+            annotation.as_ref(),
+            rdf::VALUE,
+            Literal::new_typed_literal(count.to_string(), xsd::INTEGER),
+            graph,
+        )
+        .as_ref(),
+    )?;
+    Ok(annotation)
+}
+
+/// Add an annotation of an assessment warning about a value of the assessed
+/// node that is present but could not be used, identified by a
+/// machine-readable `code` and explained by an English `message`.
+pub fn add_processing_warning(
+    assessment: NamedNodeRef,
+    code: &str,
+    message: &str,
+    graph: GraphNameRef,
+    store: &impl QuadSink,
+) -> Result<BlankNode, StorageError> {
+    let annotation = BlankNode::default();
+    store
+        .insert(Quad::new(annotation.as_ref(), rdf::TYPE, oa::ANNOTATION_CLASS, graph).as_ref())?;
+    store.insert(Quad::new(annotation.as_ref(), oa::HAS_TARGET, assessment, graph).as_ref())?;
+    store
+        .insert(Quad::new(annotation.as_ref(), oa::MOTIVATED_BY, oa::ASSESSING, graph).as_ref())?;
+    store.insert(
+        Quad::new(
+            annotation.as_ref(),
+            dcterms::IDENTIFIER,
+            Literal::new_simple_literal(code),
+            graph,
+        )
+        .as_ref(),
+    )?;
+    store.insert(
+        Quad::new(
+            annotation.as_ref(),
+            oa::BODY_VALUE,
+            Literal::new_language_tagged_literal_unchecked(message, "en"),
+            graph,
+        )
+        .as_ref(),
+    )?;
+    Ok(annotation)
+}
+
+/// Add a node describing a format inferred from the file extension of a
+/// download URL, for measurements to be derived from.
+pub fn add_format_inference(
+    format: NamedNodeRef,
+    graph: GraphNameRef,
+    store: &impl QuadSink,
+) -> Result<BlankNode, StorageError> {
+    let inference = BlankNode::default();
+    store.insert(Quad::new(inference.as_ref(), dcterms::FORMAT, format, graph).as_ref())?;
+    store.insert(
+        Quad::new(
+            inference.as_ref(),
+            dcterms::DESCRIPTION,
+            Literal::new_simple_literal(
+                "Format inferred from the file extension of dcat:downloadURL",
+            ),
+            graph,
+        )
+        .as_ref(),
+    )?;
+    Ok(inference)
+}
+
+/// Insert dataset assessment into a graph of the store, identified by the FDK
+/// id of the dataset when known.
+pub fn insert_dataset_assessment(
+    dataset_assessment: NamedNodeRef,
+    dataset: NamedNodeRef,
+    fdk_id: Option<&str>,
+    graph: GraphNameRef,
+    store: &impl QuadSink,
+) -> Result<(), Error> {
+    let insert = |quad: &Quad| {
+        store
+            .insert(quad)
+            .map_err(|e| storage_error(e, None, dataset.into()))
+    };
+    insert(&Quad::new(
+        dataset_assessment.clone(),
+        rdf::TYPE,
+        dcat_mqa::DATASET_ASSESSMENT_CLASS,
+        graph,
+    ))?;
+    insert(&Quad::new(
+        dataset_assessment.clone(),
+        dcat_mqa::ASSESSMENT_OF,
+        dataset,
+        graph,
+    ))?;
+    insert(&Quad::new(
+        dataset_assessment,
+        dcat_mqa::ASSESSMENT_VERSION,
+        Literal::new_simple_literal(assessment_version()),
+        graph,
+    ))?;
+    if let Some(fdk_id) = fdk_id {
+        insert(&Quad::new(
+            dataset_assessment,
+            dcterms::IDENTIFIER,
+            Literal::new_simple_literal(fdk_id),
+            graph,
+        ))?;
+    }
+
+    Ok(())
+}
+
+/// Insert distribution assessment, and its link from the dataset assessment,
+/// into a graph of the store
+pub fn insert_distribution_assessment(
     dataset_assessment: NamedNodeRef,
     distribution_assessment: NamedNodeRef,
     distribution: NamedNodeRef,
-    store: &Store,
+    graph: GraphNameRef,
+    store: &impl QuadSink,
 ) -> Result<(), Error> {
-    store.insert(&Quad::new(
+    let insert = |quad: &Quad| {
+        store
+            .insert(quad)
+            .map_err(|e| storage_error(e, None, distribution.into()))
+    };
+    insert(&Quad::new(
         distribution_assessment,
         rdf::TYPE,
         dcat_mqa::DISTRIBUTION_ASSESSMENT_CLASS,
-        GraphName::DefaultGraph,
+        graph,
     ))?;
-    store.insert(&Quad::new(
+    insert(&Quad::new(
         distribution_assessment.clone(),
         dcat_mqa::ASSESSMENT_OF,
         distribution,
-        GraphName::DefaultGraph,
+        graph,
     ))?;
-    store.insert(&Quad::new(
+    insert(&Quad::new(
+        distribution_assessment,
+        dcat_mqa::ASSESSMENT_VERSION,
+        Literal::new_simple_literal(assessment_version()),
+        graph,
+    ))?;
+    insert(&Quad::new(
         dataset_assessment,
         dcat_mqa::HAS_DISTRIBUTION_ASSESSMENT,
         distribution_assessment,
-        GraphName::DefaultGraph,
+        graph,
     ))?;
 
     Ok(())
 }
 
-/// Add quality measurement to metric store
-pub fn add_quality_measurement(
+/// Value of a quality measurement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MeasurementValue {
+    Bool(bool),
+    Int(i64),
+    Str(String),
+}
+
+impl MeasurementValue {
+    /// Typed literal representing the value, booleans in the canonical
+    /// lexical form `true` or `false`
+    pub fn to_literal(&self) -> Literal {
+        match self {
+            MeasurementValue::Bool(value) => {
+                Literal::new_typed_literal(if *value { "true" } else { "false" }, xsd::BOOLEAN)
+            }
+            MeasurementValue::Int(value) => {
+                Literal::new_typed_literal(format!("{}", value), xsd::INTEGER)
+            }
+            MeasurementValue::Str(value) => Literal::new_typed_literal(value, xsd::STRING),
+        }
+    }
+}
+
+/// Boolean value of a measurement value read back from a graph. Accepts the
+/// lexical forms `true`, `false`, `1` and `0`, typed as xsd:boolean or plain.
+pub fn boolean_literal_value(term: &Term) -> Option<bool> {
+    match term {
+        Term::Literal(literal)
+            if literal.datatype() == xsd::BOOLEAN || literal.datatype() == xsd::STRING =>
+        {
+            match literal.value() {
+                "true" | "1" => Some(true),
+                "false" | "0" => Some(false),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Add boolean quality measurement to a graph of the metric store
+pub fn add_boolean_quality_measurement(
     metric: NamedNodeRef,
     target: NamedNodeRef,
     computed_on: NamedNodeRef,
     value: bool,
-    store: &Store,
+    graph: GraphNameRef,
+    store: &impl QuadSink,
+) -> Result<BlankNode, Error> {
+    add_quality_measurement(
+        metric,
+        target,
+        computed_on,
+        MeasurementValue::Bool(value),
+        graph,
+        store,
+    )
+}
+
+/// Add quality measurement to a graph of the metric store
+pub fn add_quality_measurement(
+    metric: NamedNodeRef,
+    target: NamedNodeRef,
+    computed_on: NamedNodeRef,
+    value: MeasurementValue,
+    graph: GraphNameRef,
+    store: &impl QuadSink,
+) -> Result<BlankNode, Error> {
+    insert_quality_measurement(metric, target, computed_on, value, graph, store)
+        .map_err(|e| storage_error(e, Some(metric), computed_on.into()))
+}
+
+fn insert_quality_measurement(
+    metric: NamedNodeRef,
+    target: NamedNodeRef,
+    computed_on: NamedNodeRef,
+    value: MeasurementValue,
+    graph: GraphNameRef,
+    store: &impl QuadSink,
 ) -> Result<BlankNode, StorageError> {
     let measurement = BlankNode::default();
-    let value_term = Term::Literal(Literal::new_typed_literal(
-        format!("{}", value),
-        xsd::BOOLEAN,
-    ));
+    let value_term = Term::Literal(value.to_literal());
 
     store.insert(
         Quad::new(
             measurement.as_ref(),
             rdf::TYPE,
             dqv::QUALITY_MEASUREMENT_CLASS,
-            GraphName::DefaultGraph,
-        )
-        .as_ref(),
-    )?;
-    store.insert(
-        Quad::new(
-            measurement.as_ref(),
-            dqv::IS_MEASUREMENT_OF,
-            metric,
-            GraphName::DefaultGraph,
-        )
-        .as_ref(),
-    )?;
-    store.insert(
-        Quad::new(
-            measurement.as_ref(),
-            dqv::COMPUTED_ON,
-            computed_on,
-            GraphName::DefaultGraph,
-        )
-        .as_ref(),
-    )?;
-    store.insert(
-        Quad::new(
-            measurement.as_ref(),
-            dqv::VALUE,
-            value_term,
-            GraphName::DefaultGraph,
+            graph,
         )
         .as_ref(),
     )?;
+    store
+        .insert(Quad::new(measurement.as_ref(), dqv::IS_MEASUREMENT_OF, metric, graph).as_ref())?;
+    store.insert(Quad::new(measurement.as_ref(), dqv::COMPUTED_ON, computed_on, graph).as_ref())?;
+    store.insert(Quad::new(measurement.as_ref(), dqv::VALUE, value_term, graph).as_ref())?;
     store.insert(
         Quad::new(
             target,
             dcat_mqa::CONTAINS_QUALITY_MEASUREMENT,
             measurement.as_ref(),
-            GraphName::DefaultGraph,
+            graph,
         )
         .as_ref(),
     )?;
+    if let Some(descriptor) = descriptor(metric) {
+        store.insert(Quad::new(metric, dqv::IN_DIMENSION, descriptor.dimension, graph).as_ref())?;
+        store.insert(
+            Quad::new(metric, dqv::EXPECTED_DATA_TYPE, descriptor.datatype, graph).as_ref(),
+        )?;
+    }
 
     Ok(measurement)
 }
 
-/// Dump graph as turtle string
-pub fn dump_graph_as_turtle(store: &Store) -> Result<Vec<u8>, SerializerError> {
-    let mut buffer = Vec::new();
-    store.dump_graph_to_writer(GraphNameRef::DefaultGraph, RdfFormat::Turtle, &mut buffer)?;
-    Ok(buffer)
+/// Classes of the nodes an MQA graph describes.
+const OUTPUT_CLASSES: [NamedNodeRef<'static>; 5] = [
+    dcat_mqa::DATASET_ASSESSMENT_CLASS,
+    dcat_mqa::DISTRIBUTION_ASSESSMENT_CLASS,
+    dqv::QUALITY_MEASUREMENT_CLASS,
+    dqv::QUALITY_ANNOTATION_CLASS,
+    oa::ANNOTATION_CLASS,
+];
+
+/// Check that every subject of an MQA graph is an assessment, a quality
+/// measurement or annotation, an annotation explaining a measurement, an MQA
+/// dimension or metric, or a node
+/// measurements are derived from, catching triples of the input graph written
+/// to the output graph.
+pub fn validate_output_graph(store: &Store) -> Result<(), Error> {
+    let mut validated = HashSet::new();
+    for quad in store.iter() {
+        let subject = quad?.subject;
+        if validated.contains(&subject) {
+            continue;
+        }
+        if !is_output_subject(subject.as_ref(), store)? {
+            return Err(Error::UnexpectedOutputSubject {
+                subject: subject.to_string(),
+            });
+        }
+        validated.insert(subject);
+    }
+    Ok(())
+}
+
+fn is_output_subject(subject: SubjectRef, store: &Store) -> Result<bool, StorageError> {
+    if let SubjectRef::NamedNode(node) = subject {
+        if is_dimension_or_metric(node) {
+            return Ok(true);
+        }
+    }
+    for class in OUTPUT_CLASSES {
+        if store
+            .quads_for_pattern(Some(subject), Some(rdf::TYPE), Some(class.into()), None)
+            .next()
+            .transpose()?
+            .is_some()
+        {
+            return Ok(true);
+        }
+    }
+    Ok(store
+        .quads_for_pattern(
+            None,
+            Some(prov::WAS_DERIVED_FROM),
+            Some(subject.into()),
+            None,
+        )
+        .next()
+        .transpose()?
+        .is_some())
+}
+
+/// Prefixes of the vocabularies used in MQA graphs.
+const PREFIXES: [(&str, &str); 8] = [
+    ("dcat", "http://www.w3.org/ns/dcat#"),
+    ("dcterms", "http://purl.org/dc/terms/"),
+    ("dqv", "http://www.w3.org/ns/dqv#"),
+    ("dcatnomqa", "https://data.norge.no/vocabulary/dcatno-mqa#"),
+    ("prov", "http://www.w3.org/ns/prov#"),
+    ("oa", "http://www.w3.org/ns/oa#"),
+    ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+    ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+];
+
+/// Dump graph as turtle string, using prefixed names of the MQA vocabularies
+pub fn dump_graph_as_turtle(store: &Store) -> Result<Vec<u8>, Error> {
+    dump_in_serialization_order(store, RdfFormat::Turtle, Some(GraphNameRef::DefaultGraph))
+}
+
+/// Dump all graphs of the store as TriG string, using prefixed names of the
+/// MQA vocabularies
+pub fn dump_graph_as_trig(store: &Store) -> Result<Vec<u8>, Error> {
+    dump_in_serialization_order(store, RdfFormat::TriG, None)
+}
+
+fn dump_in_serialization_order(
+    store: &Store,
+    format: RdfFormat,
+    graph: Option<GraphNameRef>,
+) -> Result<Vec<u8>, Error> {
+    let mut serializer = RdfSerializer::from_format(format);
+    for (name, iri) in PREFIXES {
+        serializer = serializer.with_prefix(name, iri)?;
+    }
+    let mut writer = serializer.for_writer(Vec::new());
+    for quad in serialization_order(store, graph)? {
+        writer.serialize_quad(&quad)?;
+    }
+    Ok(writer.finish()?)
+}
+
+/// Quads of a graph, or of all graphs, in the order they are serialized: by
+/// graph, and within a graph grouped by the assessment they describe, dataset
+/// assessments before distribution assessments in order of their IRIs. Each
+/// assessment comes before its measurements and annotations, found by
+/// following links to blank nodes, except the five-star annotations a
+/// dataset assessment links to, which are grouped with the distribution
+/// assessments they target. Quads about no assessment, like those describing
+/// metrics, come last.
+fn serialization_order(store: &Store, graph: Option<GraphNameRef>) -> Result<Vec<Quad>, Error> {
+    let mut assessments = Vec::new();
+    for (rank, class) in [
+        dcat_mqa::DATASET_ASSESSMENT_CLASS,
+        dcat_mqa::DISTRIBUTION_ASSESSMENT_CLASS,
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        for quad in store.quads_for_pattern(None, Some(rdf::TYPE), Some(class.into()), graph) {
+            if let Subject::NamedNode(assessment) = quad?.subject {
+                assessments.push((rank, assessment));
+            }
+        }
+    }
+    assessments.sort();
+
+    // Position of each subject in the order, assigned breadth first from the
+    // assessments.
+    let mut positions: HashMap<Subject, usize> = HashMap::new();
+    for (_, assessment) in assessments {
+        let mut queue = VecDeque::from([Subject::from(assessment)]);
+        while let Some(subject) = queue.pop_front() {
+            if positions.contains_key(&subject) {
+                continue;
+            }
+            positions.insert(subject.clone(), positions.len());
+            for quad in store.quads_for_pattern(Some(subject.as_ref()), None, None, graph) {
+                let quad = quad?;
+                if let Term::BlankNode(node) = quad.object {
+                    if quad.predicate != dcat_mqa::CONTAINS_QUALITY_ANNOTATION {
+                        queue.push_back(node.into());
+                    }
+                }
+            }
+            for quad in store.quads_for_pattern(None, None, Some(subject.as_ref().into()), graph) {
+                if let Subject::BlankNode(node) = quad?.subject {
+                    queue.push_back(node.into());
+                }
+            }
+        }
+    }
+
+    let mut quads = store
+        .quads_for_pattern(None, None, None, graph)
+        .collect::<Result<Vec<_>, _>>()?;
+    quads.sort_by_cached_key(|quad| {
+        (
+            match &quad.graph_name {
+                GraphName::DefaultGraph => None,
+                graph_name => Some(graph_name.to_string()),
+            },
+            positions.get(&quad.subject).copied().unwrap_or(usize::MAX),
+            quad.subject.to_string(),
+        )
+    });
+    Ok(quads)
 }
 
-/// Check if format is RDF
-pub fn is_rdf_format(format: &str) -> bool {
-    match format.to_lowercase().as_str() {
-        "rdf" | "turtle" | "ntriples" | "n3" | "nq" | "json-ld" | "jsonld" => true,
+/// Check if any of the distribution formats is RDF
+pub fn has_rdf_format(distribution: NamedNodeRef, store: &Store) -> bool {
+    list_formats(distribution, store).any(|format| match format {
+        Ok(Quad {
+            object: Term::NamedNode(nn),
+            ..
+        }) => classify_format(nn.as_str()).rdf,
+        Ok(Quad {
+            object: Term::Literal(literal),
+            ..
+        }) => classify_format(literal.value()).rdf,
         _ => false,
+    })
+}
+
+/// Hex encoded SHA-256 of the sorted N-Triples lines of a store.
+///
+/// This is not full RDF canonicalization: every blank node is written with
+/// the same placeholder label, so relabeling blank nodes does not change the
+/// hash, but graphs that differ only in how their blank nodes are connected
+/// hash identically.
+pub fn canonical_hash(store: &Store) -> Result<String, StorageError> {
+    let mut lines = store
+        .iter()
+        .map(|quad| quad.map(|q| canonical_line(&q)))
+        .collect::<Result<Vec<String>, StorageError>>()?;
+    lines.sort_unstable();
+
+    let mut hasher = Sha256::new();
+    for line in lines {
+        hasher.update(line.as_bytes());
+        hasher.update(b"\n");
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn canonical_line(quad: &Quad) -> String {
+    let subject = match &quad.subject {
+        Subject::BlankNode(_) => "_:b".to_string(),
+        subject => subject.to_string(),
+    };
+    let object = match &quad.object {
+        Term::BlankNode(_) => "_:b".to_string(),
+        object => object.to_string(),
+    };
+    match &quad.graph_name {
+        GraphName::DefaultGraph => format!("{} {} {} .", subject, quad.predicate, object),
+        graph_name => format!("{} {} {} {} .", subject, quad.predicate, object, graph_name),
+    }
+}
+
+/// N-Triples of the default graph of a store, one line per triple in sorted
+/// order, for comparing graphs in tests and diffs rather than for payloads.
+///
+/// Blank nodes are labeled `_:c0`, `_:c1`, ... in an order given by the
+/// triples around them, refined with the labels of neighbouring blank nodes
+/// until that no longer tells more of them apart. Equal graphs get equal
+/// dumps regardless of insertion order and blank node labels.
+pub fn dump_graph_canonical(store: &Store) -> Result<String, StorageError> {
+    let triples = store
+        .quads_for_pattern(None, None, None, Some(GraphNameRef::DefaultGraph))
+        .map(|quad| quad.map(Triple::from))
+        .collect::<Result<Vec<Triple>, StorageError>>()?;
+
+    let mut signatures = triples
+        .iter()
+        .flat_map(|triple| {
+            let subject = match &triple.subject {
+                Subject::BlankNode(node) => Some(node.clone()),
+                _ => None,
+            };
+            let object = match &triple.object {
+                Term::BlankNode(node) => Some(node.clone()),
+                _ => None,
+            };
+            subject.into_iter().chain(object)
+        })
+        .map(|node| (node, String::new()))
+        .collect::<HashMap<BlankNode, String>>();
+
+    let mut distinct = 0;
+    loop {
+        let refined = refine_blank_node_signatures(&triples, &signatures);
+        let refined_distinct = refined.values().collect::<HashSet<_>>().len();
+        signatures = refined;
+        if refined_distinct <= distinct {
+            break;
+        }
+        distinct = refined_distinct;
+    }
+
+    let mut ordered = signatures.into_iter().collect::<Vec<_>>();
+    ordered.sort_by(|(_, a), (_, b)| a.cmp(b));
+    let labels = ordered
+        .into_iter()
+        .enumerate()
+        .map(|(index, (node, _))| (node, format!("c{}", index)))
+        .collect::<HashMap<BlankNode, String>>();
+
+    let mut lines = triples
+        .iter()
+        .map(|triple| {
+            format!(
+                "{} {} {} .",
+                canonical_subject(&triple.subject, &labels),
+                triple.predicate,
+                canonical_term(&triple.object, &labels)
+            )
+        })
+        .collect::<Vec<String>>();
+    lines.sort_unstable();
+
+    Ok(lines.into_iter().map(|line| line + "\n").collect())
+}
+
+/// Hash of the triples around each blank node, with neighbouring blank nodes
+/// written as their current signature.
+fn refine_blank_node_signatures(
+    triples: &[Triple],
+    signatures: &HashMap<BlankNode, String>,
+) -> HashMap<BlankNode, String> {
+    let mut neighbourhoods: HashMap<&BlankNode, Vec<String>> = HashMap::new();
+    for triple in triples {
+        let subject = canonical_subject(&triple.subject, signatures);
+        let object = canonical_term(&triple.object, signatures);
+        if let Subject::BlankNode(node) = &triple.subject {
+            neighbourhoods
+                .entry(node)
+                .or_default()
+                .push(format!("> {} {}", triple.predicate, object));
+        }
+        if let Term::BlankNode(node) = &triple.object {
+            neighbourhoods
+                .entry(node)
+                .or_default()
+                .push(format!("< {} {}", subject, triple.predicate));
+        }
+    }
+
+    neighbourhoods
+        .into_iter()
+        .map(|(node, mut neighbourhood)| {
+            neighbourhood.sort_unstable();
+            let mut hasher = Sha256::new();
+            hasher.update(signatures[node].as_bytes());
+            for line in neighbourhood {
+                hasher.update(line.as_bytes());
+                hasher.update(b"\n");
+            }
+            (node.clone(), format!("{:x}", hasher.finalize()))
+        })
+        .collect()
+}
+
+fn canonical_subject(subject: &Subject, labels: &HashMap<BlankNode, String>) -> String {
+    match subject {
+        Subject::BlankNode(node) => format!("_:{}", labels[node]),
+        subject => subject.to_string(),
+    }
+}
+
+fn canonical_term(term: &Term, labels: &HashMap<BlankNode, String>) -> String {
+    match term {
+        Term::BlankNode(node) => format!("_:{}", labels[node]),
+        term => term.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Error of loading the given Turtle.
+    fn loader_error(turtle: &str) -> LoaderError {
+        Store::new()
+            .unwrap()
+            .load_from_reader(RdfFormat::Turtle, turtle.as_bytes())
+            .unwrap_err()
+    }
+
+    /// The snippet lines of a formatted parse error.
+    fn snippet(turtle: &str) -> Vec<String> {
+        format_parse_error(turtle, &loader_error(turtle))
+            .lines()
+            .skip(1)
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Index of the caret of a snippet, which follows the offending line.
+    fn caret(snippet: &[String]) -> (usize, usize) {
+        let line = snippet.iter().position(|line| line.ends_with('^')).unwrap();
+        (line, snippet[line].find('^').unwrap())
+    }
+
+    #[test]
+    fn test_parse_error_on_first_line() {
+        let turtle = "<https://a> <https://b> foo .\n<https://a> <https://b> <https://c> .\n<https://a> <https://b> <https://d> .\n<https://a> <https://b> <https://e> .\n";
+        let snippet = snippet(turtle);
+
+        assert!(snippet[0].starts_with("> 1 | <https://a> <https://b> foo ."));
+        let (line, caret) = caret(&snippet);
+        assert_eq!(1, line);
+        assert!(caret < snippet[0].len());
+        assert!(snippet[2].starts_with("  2 | "));
+        assert!(snippet[3].starts_with("  3 | "));
+        assert_eq!(4, snippet.len());
+    }
+
+    #[test]
+    fn test_parse_error_on_last_line() {
+        let turtle = "<https://a> <https://b> <https://c> .\n<https://a> <https://b> <https://d> .\n<https://a> <https://b> <https://e> .\n<https://a> <https://b> \"x\u{1b}\ty\" foo .";
+        let snippet = snippet(turtle);
+
+        assert!(snippet[0].starts_with("  2 | "));
+        assert!(snippet[1].starts_with("  3 | "));
+        assert_eq!(
+            "> 4 | <https://a> <https://b> \"x\\u{1b}\\ty\" foo .",
+            snippet[2]
+        );
+        assert_eq!((3, 4), (caret(&snippet).0, snippet.len()));
+    }
+
+    #[test]
+    fn test_parse_error_in_long_line() {
+        let turtle = format!(
+            "<https://a> <https://b> \"{}\" foo \"{}\" .",
+            "x".repeat(500),
+            "y".repeat(500)
+        );
+        let snippet = snippet(&turtle);
+
+        assert_eq!(2, snippet.len());
+        let line = snippet[0].trim_start_matches("> 1 | ");
+        assert!(line.starts_with('…'));
+        assert!(line.ends_with('…'));
+        assert_eq!(SNIPPET_WIDTH + 2, line.chars().count());
+        assert!(line.contains("foo"));
+
+        // The caret is under the offending column of the cut line.
+        let (_, caret) = caret(&snippet);
+        let column = snippet[0].chars().count() - line.chars().count() + SNIPPET_WIDTH / 2 + 1;
+        assert_eq!(column, snippet[1][..caret].chars().count());
+    }
+
+    #[test]
+    fn test_parse_turtle_invalid() {
+        let result = parse_turtle(&Store::new().unwrap(), "<https://a> foo .".to_string());
+
+        match result {
+            Err(Error::InvalidTurtle { fdk_id, details }) => {
+                assert_eq!(None, fdk_id);
+                assert!(details.contains("> 1 | <https://a> foo ."));
+            }
+            _ => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    const RELATIVE_TURTLE: &str = r#"
+        @prefix dcat: <http://www.w3.org/ns/dcat#> .
+        <datasets/1> a dcat:Dataset ;
+            dcat:distribution <datasets/1/distributions/1> .
+        <datasets/1/distributions/1> a dcat:Distribution .
+        "#;
+
+    #[test]
+    fn test_parse_turtle_with_base() {
+        let store = Store::new().unwrap();
+        parse_turtle_with_base(
+            &store,
+            RELATIVE_TURTLE.to_string(),
+            Some("https://data.foo/"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                "<https://data.foo/datasets/1/distributions/1> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://www.w3.org/ns/dcat#Distribution>",
+                "<https://data.foo/datasets/1> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://www.w3.org/ns/dcat#Dataset>",
+                "<https://data.foo/datasets/1> <http://www.w3.org/ns/dcat#distribution> <https://data.foo/datasets/1/distributions/1>",
+            ],
+            sorted_quads(&store)
+        );
+        assert!(parse_turtle_with_base(&store, String::new(), Some("data.foo")).is_err());
+    }
+
+    #[test]
+    fn test_parse_turtle_with_relative_iris_without_base() {
+        let result = parse_turtle(&Store::new().unwrap(), RELATIVE_TURTLE.to_string());
+
+        match result {
+            Err(Error::InvalidTurtle { details, .. }) => assert!(details.ends_with(
+                "relative IRIs without a base IRI: <datasets/1>, <datasets/1/distributions/1>"
+            )),
+            _ => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_has_scheme() {
+        assert!(has_scheme("https://data.foo"));
+        assert!(has_scheme("urn:uuid:6e8bc430-9c3a-11d9-9669-0800200c9a66"));
+        assert!(has_scheme("git+ssh://data.foo"));
+        assert!(!has_scheme("datasets/1"));
+        assert!(!has_scheme("/datasets/1:2"));
+        assert!(!has_scheme("1datasets:2"));
+        assert!(!has_scheme(""));
+    }
+
+    fn sorted_quads(store: &Store) -> Vec<String> {
+        let mut quads = store
+            .iter()
+            .map(|quad| quad.unwrap().to_string())
+            .collect::<Vec<String>>();
+        quads.sort();
+        quads
+    }
+
+    #[test]
+    fn test_turtle_with_prefixes_round_trip() {
+        let store = Store::new().unwrap();
+        parse_turtle(
+            &store,
+            include_str!("../tests/data/mqa_event.ttl").to_string(),
+        )
+        .unwrap();
+
+        let turtle = String::from_utf8(dump_graph_as_turtle(&store).unwrap()).unwrap();
+        assert!(
+            turtle.contains("@prefix dcatnomqa: <https://data.norge.no/vocabulary/dcatno-mqa#> .")
+        );
+        assert!(turtle.contains("dqv:isMeasurementOf dcatnomqa:"));
+
+        let parsed = Store::new().unwrap();
+        parse_turtle(&parsed, turtle).unwrap();
+        assert_eq!(sorted_quads(&store), sorted_quads(&parsed));
+    }
+
+    #[test]
+    fn test_validate_output_graph() {
+        let store = Store::new().unwrap();
+        parse_turtle(
+            &store,
+            include_str!("../tests/data/mqa_event.ttl").to_string(),
+        )
+        .unwrap();
+        let inference = add_format_inference(
+            NamedNodeRef::new_unchecked("http://foo/csv"),
+            GraphNameRef::DefaultGraph,
+            &store,
+        )
+        .unwrap();
+        let annotation = get_five_star_annotations(
+            &store,
+            &[NamedNode::new_unchecked("http://dist.foo.assessment.no")],
+        )
+        .unwrap()
+        .remove(0);
+        add_derived_from(
+            annotation.as_ref().into(),
+            inference.as_ref().into(),
+            GraphNameRef::DefaultGraph,
+            &store,
+        )
+        .unwrap();
+
+        assert!(validate_output_graph(&store).is_ok());
+    }
+
+    #[test]
+    fn test_validate_output_graph_with_stray_triple() {
+        let store = Store::new().unwrap();
+        parse_turtle(
+            &store,
+            include_str!("../tests/data/mqa_event.ttl").to_string(),
+        )
+        .unwrap();
+        add_property(
+            NamedNodeRef::new_unchecked("https://dataset.foo").into(),
+            dcterms::TITLE,
+            Literal::new_simple_literal("Dataset").as_ref().into(),
+            GraphNameRef::DefaultGraph,
+            &store,
+        )
+        .unwrap();
+
+        let result = validate_output_graph(&store);
+        assert!(
+            matches!(
+                &result,
+                Err(Error::UnexpectedOutputSubject { subject }) if subject == "<https://dataset.foo>"
+            ),
+            "{:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_turtle_with_prefixes_smaller() {
+        let store = Store::new().unwrap();
+        parse_turtle(
+            &store,
+            include_str!("../tests/data/mqa_event.ttl").to_string(),
+        )
+        .unwrap();
+
+        let mut without_prefixes = Vec::new();
+        store
+            .dump_graph_to_writer(
+                GraphNameRef::DefaultGraph,
+                RdfFormat::Turtle,
+                &mut without_prefixes,
+            )
+            .unwrap();
+        let with_prefixes = dump_graph_as_turtle(&store).unwrap();
+
+        // At least 30% smaller.
+        assert!(
+            with_prefixes.len() * 10 < without_prefixes.len() * 7,
+            "{} bytes with prefixes, {} without",
+            with_prefixes.len(),
+            without_prefixes.len()
+        );
+    }
+
+    #[test]
+    fn test_serialization_order() {
+        let store = Store::new().unwrap();
+        parse_turtle(
+            &store,
+            r#"
+            @prefix dcatnomqa: <https://data.norge.no/vocabulary/dcatno-mqa#> .
+            @prefix dqv: <http://www.w3.org/ns/dqv#> .
+            @prefix oa: <http://www.w3.org/ns/oa#> .
+
+            dcatnomqa:metric a dqv:Metric .
+            _:measurement dqv:computedOn <https://distribution.assessment.foo/b> ;
+                dqv:value true .
+            <https://distribution.assessment.foo/b> a dcatnomqa:DistributionAssessment .
+            _:fiveStars oa:hasTarget <https://distribution.assessment.foo/a> .
+            <https://dataset.assessment.foo> a dcatnomqa:DatasetAssessment ;
+                dcatnomqa:containsQualityAnnotation _:fiveStars ;
+                dcatnomqa:hasDistributionAssessment <https://distribution.assessment.foo/b> ,
+                    <https://distribution.assessment.foo/a> .
+            <https://distribution.assessment.foo/a> a dcatnomqa:DistributionAssessment .
+            "#
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut subjects = serialization_order(&store, Some(GraphNameRef::DefaultGraph))
+            .unwrap()
+            .into_iter()
+            .map(|quad| match quad.subject {
+                Subject::NamedNode(node) => node.as_str().to_string(),
+                _ if quad.predicate == oa::HAS_TARGET => "five stars".to_string(),
+                _ => "measurement".to_string(),
+            })
+            .collect::<Vec<_>>();
+        subjects.dedup();
+
+        assert_eq!(
+            vec![
+                "https://dataset.assessment.foo",
+                "https://distribution.assessment.foo/a",
+                "five stars",
+                "https://distribution.assessment.foo/b",
+                "measurement",
+                "https://data.norge.no/vocabulary/dcatno-mqa#metric",
+            ],
+            subjects
+        );
+    }
+
+    #[test]
+    fn test_literals_with_language() {
+        let store = Store::new().unwrap();
+        parse_turtle(
+            &store,
+            r#"
+            @prefix dcat: <http://www.w3.org/ns/dcat#> .
+            @prefix dct: <http://purl.org/dc/terms/> .
+
+            <https://dataset.foo> dcat:keyword "oda"@nb , "aid"@en , "bistand" ;
+                dct:description "Bistand"@nb ;
+                dct:subject <https://subject.foo> .
+            "#
+            .to_string(),
+        )
+        .unwrap();
+        let dataset = NamedNodeRef::new_unchecked("https://dataset.foo");
+
+        assert_eq!(
+            (3, 2),
+            literals_with_language(dataset.into(), dcat::KEYWORD, &store)
+        );
+        assert_eq!(
+            (1, 1),
+            literals_with_language(dataset.into(), dcterms::DESCRIPTION, &store)
+        );
+        assert_eq!(
+            (0, 0),
+            literals_with_language(dataset.into(), dcterms::SUBJECT, &store)
+        );
+    }
+
+    #[test]
+    fn test_has_iri_and_literal_property() {
+        let store = Store::new().unwrap();
+        parse_turtle(
+            &store,
+            r#"
+            @prefix dcat: <http://www.w3.org/ns/dcat#> .
+            @prefix dct: <http://purl.org/dc/terms/> .
+
+            <https://dataset.foo> dct:accessRights "public" ;
+                dcat:theme <http://publications.europa.eu/resource/authority/data-theme/GOVE> ;
+                dct:license [ a dct:LicenseDocument ] .
+            "#
+            .to_string(),
+        )
+        .unwrap();
+        let dataset = NamedNodeRef::new_unchecked("https://dataset.foo");
+
+        assert!(!has_iri_property(
+            dataset.into(),
+            dcterms::ACCESS_RIGHTS,
+            &store
+        ));
+        assert!(has_literal_property(
+            dataset.into(),
+            dcterms::ACCESS_RIGHTS,
+            &store
+        ));
+        assert!(has_iri_property(dataset.into(), dcat::THEME, &store));
+        assert!(!has_literal_property(dataset.into(), dcat::THEME, &store));
+        assert!(!has_iri_property(dataset.into(), dcterms::LICENSE, &store));
+        assert!(!has_literal_property(
+            dataset.into(),
+            dcterms::LICENSE,
+            &store
+        ));
+        assert_eq!(
+            PropertyValues {
+                iri: false,
+                literal: false,
+                blank_node: true,
+            },
+            property_values(dataset.into(), dcterms::LICENSE, &store)
+        );
+    }
+
+    #[test]
+    fn test_measurement_value_datatypes() {
+        let store = Store::new().unwrap();
+        let target = NamedNodeRef::new_unchecked("https://assessment.foo");
+        let node = NamedNodeRef::new_unchecked("https://dataset.foo");
+
+        for (value, lexical, datatype) in [
+            (MeasurementValue::Bool(true), "true", xsd::BOOLEAN),
+            (MeasurementValue::Bool(false), "false", xsd::BOOLEAN),
+            (MeasurementValue::Int(-42), "-42", xsd::INTEGER),
+            (MeasurementValue::Str("foo".to_string()), "foo", xsd::STRING),
+        ] {
+            let measurement = add_quality_measurement(
+                dcat_mqa::BYTE_SIZE_AVAILABILITY,
+                target,
+                node,
+                value,
+                GraphNameRef::DefaultGraph,
+                &store,
+            )
+            .unwrap();
+
+            let object = store
+                .quads_for_pattern(
+                    Some(measurement.as_ref().into()),
+                    Some(dqv::VALUE),
+                    None,
+                    None,
+                )
+                .next()
+                .unwrap()
+                .unwrap()
+                .object;
+            match object {
+                Term::Literal(literal) => {
+                    assert_eq!(lexical, literal.value());
+                    assert_eq!(datatype, literal.datatype());
+                }
+                _ => panic!("measurement value is not a literal"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_boolean_literal_value() {
+        for (lexical, expected) in [("true", true), ("1", true), ("false", false), ("0", false)] {
+            let typed = Literal::new_typed_literal(lexical, xsd::BOOLEAN);
+            let plain = Literal::new_simple_literal(lexical);
+            assert_eq!(Some(expected), boolean_literal_value(&typed.into()));
+            assert_eq!(Some(expected), boolean_literal_value(&plain.into()));
+        }
+
+        for literal in [
+            Literal::new_typed_literal("yes", xsd::BOOLEAN),
+            Literal::new_typed_literal("TRUE", xsd::BOOLEAN),
+            Literal::new_typed_literal(" true", xsd::BOOLEAN),
+            Literal::new_typed_literal("", xsd::BOOLEAN),
+            Literal::new_simple_literal("2"),
+            Literal::new_typed_literal("1", xsd::INTEGER),
+            Literal::new_language_tagged_literal_unchecked("true", "en"),
+        ] {
+            assert_eq!(
+                None,
+                boolean_literal_value(&literal.clone().into()),
+                "{} should not be a boolean",
+                literal
+            );
+        }
+        assert_eq!(
+            None,
+            boolean_literal_value(&NamedNode::new_unchecked("https://true.foo").into())
+        );
+    }
+
+    #[test]
+    fn test_is_http_iri() {
+        for iri in [
+            "http://assessment.foo",
+            "https://assessment.foo/bar#baz",
+            "HTTPS://assessment.foo",
+        ] {
+            assert!(is_http_iri(iri), "{} should be an http IRI", iri);
+        }
+        for iri in [
+            "",
+            "urn:uuid:6e8bc430-9c3a-11d9-9669-0800200c9a66",
+            "urn:uuid:",
+            "/assessments/foo",
+            "assessment.foo",
+            "javascript:alert(1)",
+            "https://",
+            "https:///foo",
+            "ftp://assessment.foo",
+        ] {
+            assert!(!is_http_iri(iri), "{} should not be an http IRI", iri);
+        }
+    }
+
+    #[test]
+    fn test_unknown_metric_has_no_dimension() {
+        let store = Store::new().unwrap();
+        let metric = NamedNodeRef::new_unchecked("https://metric.foo");
+
+        add_boolean_quality_measurement(
+            metric,
+            NamedNodeRef::new_unchecked("https://assessment.foo"),
+            NamedNodeRef::new_unchecked("https://dataset.foo"),
+            true,
+            GraphNameRef::DefaultGraph,
+            &store,
+        )
+        .unwrap();
+
+        assert_eq!(
+            0,
+            store
+                .quads_for_pattern(None, Some(dqv::IN_DIMENSION), None, None)
+                .count()
+        );
+    }
+
+    /// Sink of which the insert with the given number, counting from 1, fails.
+    struct FailingSink {
+        fail_at: usize,
+        inserts: std::sync::atomic::AtomicUsize,
+    }
+
+    impl FailingSink {
+        fn new(fail_at: usize) -> Self {
+            FailingSink {
+                fail_at,
+                inserts: Default::default(),
+            }
+        }
+    }
+
+    impl QuadSink for FailingSink {
+        fn insert<'a>(&self, _quad: impl Into<QuadRef<'a>>) -> Result<bool, StorageError> {
+            let insert = self
+                .inserts
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst)
+                + 1;
+            if insert == self.fail_at {
+                return Err(StorageError::Io(std::io::Error::other("insert failed")));
+            }
+            Ok(true)
+        }
+    }
+
+    #[test]
+    fn test_storage_error_names_metric_and_node() {
+        let graph = GraphNameRef::DefaultGraph;
+        let assessment = NamedNodeRef::new_unchecked("https://assessment.foo");
+        let dataset = NamedNodeRef::new_unchecked("https://dataset.foo");
+
+        // The third insert writes the node the measurement is computed on.
+        let error = add_boolean_quality_measurement(
+            dcat_mqa::KEYWORD_AVAILABILITY,
+            assessment,
+            dataset,
+            true,
+            graph,
+            &FailingSink::new(3),
+        )
+        .unwrap_err();
+        match &error {
+            Error::Storage { metric, node, .. } => {
+                assert_eq!(
+                    Some(dcat_mqa::KEYWORD_AVAILABILITY.as_str()),
+                    metric.as_deref()
+                );
+                assert_eq!(Some(dataset.as_str()), node.as_deref());
+            }
+            _ => panic!("unexpected error: {}", error),
+        }
+        assert_eq!(
+            "storage failed writing measurement of 'https://data.norge.no/vocabulary/dcatno-mqa#keywordAvailability' on 'https://dataset.foo': insert failed",
+            error.to_string()
+        );
+
+        let error = insert_distribution_assessment(
+            assessment,
+            NamedNodeRef::new_unchecked("https://distribution.assessment.foo"),
+            NamedNodeRef::new_unchecked("https://distribution.foo"),
+            graph,
+            &FailingSink::new(4),
+        )
+        .unwrap_err();
+        assert_eq!(
+            "storage failed writing on 'https://distribution.foo': insert failed",
+            error.to_string()
+        );
+
+        let error = add_property(
+            assessment.into(),
+            dcat_mqa::HAS_MEMBER_ASSESSMENT,
+            dataset.into(),
+            graph,
+            &FailingSink::new(1),
+        )
+        .unwrap_err();
+        assert_eq!("storage", error.code());
+        assert!(error.to_string().contains("'https://assessment.foo'"));
+
+        assert!(add_property(
+            assessment.into(),
+            dcat_mqa::HAS_MEMBER_ASSESSMENT,
+            dataset.into(),
+            graph,
+            &FailingSink::new(2),
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_turtle_distribution_has_rdf_format() {
+        let store = Store::new().unwrap();
+        parse_turtle(
+            &store,
+            r#"
+            <https://distribution.foo> <http://purl.org/dc/terms/format>
+                <http://publications.europa.eu/resource/authority/file-type/CSV> ,
+                <http://publications.europa.eu/resource/authority/file-type/RDF_TURTLE> .
+            <https://distribution.bar> <http://purl.org/dc/terms/format>
+                <http://publications.europa.eu/resource/authority/file-type/CSV> .
+            "#
+            .to_string(),
+        )
+        .unwrap();
+
+        assert!(has_rdf_format(
+            NamedNodeRef::new_unchecked("https://distribution.foo"),
+            &store
+        ));
+        assert!(!has_rdf_format(
+            NamedNodeRef::new_unchecked("https://distribution.bar"),
+            &store
+        ));
+    }
+
+    fn turtle_hash(turtle: &str) -> String {
+        let store = Store::new().unwrap();
+        parse_turtle(&store, turtle.to_string()).unwrap();
+        canonical_hash(&store).unwrap()
+    }
+
+    #[test]
+    fn test_canonical_hash_stable() {
+        let turtle = r#"
+            @prefix dct: <http://purl.org/dc/terms/> .
+            <https://dataset.foo> dct:title "Title" ; dct:publisher <https://publisher.foo> .
+            "#;
+
+        assert_eq!(turtle_hash(turtle), turtle_hash(turtle));
+    }
+
+    #[test]
+    fn test_canonical_hash_ignores_order_and_blank_node_labels() {
+        let a = turtle_hash(
+            r#"
+            @prefix dct: <http://purl.org/dc/terms/> .
+            <https://dataset.foo> dct:title "Title" ; dct:temporal _:a .
+            _:a dct:issued "2020" .
+            "#,
+        );
+        let b = turtle_hash(
+            r#"
+            @prefix dct: <http://purl.org/dc/terms/> .
+            _:other dct:issued "2020" .
+            <https://dataset.foo> dct:temporal _:other .
+            <https://dataset.foo> dct:title "Title" .
+            "#,
+        );
+
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_canonical_hash_changed_literal() {
+        let a = turtle_hash(r#"<https://dataset.foo> <http://purl.org/dc/terms/title> "A" ."#);
+        let b = turtle_hash(r#"<https://dataset.foo> <http://purl.org/dc/terms/title> "B" ."#);
+
+        assert_ne!(a, b);
+    }
+
+    fn turtle_dump(turtle: &str) -> String {
+        let store = Store::new().unwrap();
+        parse_turtle(&store, turtle.to_string()).unwrap();
+        dump_graph_canonical(&store).unwrap()
+    }
+
+    #[test]
+    fn test_canonical_dump_ignores_order_and_blank_node_labels() {
+        let a = turtle_dump(
+            r#"
+            @prefix dqv: <http://www.w3.org/ns/dqv#> .
+            @prefix prov: <http://www.w3.org/ns/prov#> .
+            <https://assessment.foo> dqv:hasQualityMeasurement _:a , _:b .
+            _:a dqv:isMeasurementOf <https://metric.foo> ; dqv:value true .
+            _:b dqv:isMeasurementOf <https://metric.bar> ; dqv:value false .
+            _:c prov:wasDerivedFrom _:a .
+            "#,
+        );
+        let b = turtle_dump(
+            r#"
+            @prefix dqv: <http://www.w3.org/ns/dqv#> .
+            @prefix prov: <http://www.w3.org/ns/prov#> .
+            _:annotation prov:wasDerivedFrom _:foo .
+            _:bar dqv:value false ; dqv:isMeasurementOf <https://metric.bar> .
+            <https://assessment.foo> dqv:hasQualityMeasurement _:bar .
+            _:foo dqv:value true ; dqv:isMeasurementOf <https://metric.foo> .
+            <https://assessment.foo> dqv:hasQualityMeasurement _:foo .
+            "#,
+        );
+
+        assert_eq!(a, b);
+        assert_eq!(7, a.lines().count());
+        assert!(a.lines().all(|line| line.ends_with(" .")));
+        assert!(a.contains("_:c0") && a.contains("_:c2") && !a.contains("_:c3"));
+    }
+
+    #[test]
+    fn test_canonical_dump_detects_blank_node_connections() {
+        let a = turtle_dump(
+            r#"
+            @prefix dqv: <http://www.w3.org/ns/dqv#> .
+            @prefix prov: <http://www.w3.org/ns/prov#> .
+            _:a dqv:isMeasurementOf <https://metric.foo> .
+            _:b dqv:isMeasurementOf <https://metric.bar> .
+            _:c prov:wasDerivedFrom _:a .
+            "#,
+        );
+        let b = turtle_dump(
+            r#"
+            @prefix dqv: <http://www.w3.org/ns/dqv#> .
+            @prefix prov: <http://www.w3.org/ns/prov#> .
+            _:a dqv:isMeasurementOf <https://metric.foo> .
+            _:b dqv:isMeasurementOf <https://metric.bar> .
+            _:c prov:wasDerivedFrom _:b .
+            "#,
+        );
+
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_canonical_dump_detects_changed_triple() {
+        let turtle = r#"
+            @prefix dqv: <http://www.w3.org/ns/dqv#> .
+            <https://assessment.foo> dqv:hasQualityMeasurement [
+                dqv:isMeasurementOf <https://metric.foo> ;
+                dqv:value true
+            ] .
+            "#;
+
+        assert_ne!(
+            turtle_dump(turtle),
+            turtle_dump(&turtle.replace("true", "false"))
+        );
+        assert_ne!(
+            turtle_dump(turtle),
+            turtle_dump(&turtle.replace("dqv:value true", ""))
+        );
+    }
+
+    fn temporal_of(turtle: &str) -> (Store, Term) {
+        let store = Store::new().unwrap();
+        parse_turtle(&store, turtle.to_string()).unwrap();
+        let temporal = list_temporal(n!("https://dataset.foo"), &store)
+            .next()
+            .unwrap()
+            .unwrap()
+            .object;
+        (store, temporal)
+    }
+
+    #[test]
+    fn test_complete_period_of_time() {
+        let (store, temporal) = temporal_of(
+            r#"
+            @prefix dcat: <http://www.w3.org/ns/dcat#> .
+            @prefix dct: <http://purl.org/dc/terms/> .
+            @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+
+            <https://dataset.foo> dct:temporal [
+                a dct:PeriodOfTime ;
+                dcat:startDate "2020-01-01"^^xsd:date ;
+                dcat:endDate "2020-12-31T00:00:00"^^xsd:dateTime
+            ] .
+            "#,
+        );
+
+        assert!(is_complete_period_of_time(&temporal, &store));
+    }
+
+    #[test]
+    fn test_incomplete_period_of_time() {
+        let (store, temporal) = temporal_of(
+            r#"
+            @prefix dcat: <http://www.w3.org/ns/dcat#> .
+            @prefix dct: <http://purl.org/dc/terms/> .
+            @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+
+            <https://dataset.foo> dct:temporal [
+                a dct:PeriodOfTime ;
+                dcat:startDate "2020-01-01"^^xsd:date ;
+                dcat:endDate "next year"
+            ] .
+            "#,
+        );
+
+        assert!(!is_complete_period_of_time(&temporal, &store));
+    }
+
+    #[test]
+    fn test_dataset_node_ignores_distributions() {
+        let store = Store::new().unwrap();
+        parse_turtle(
+            &store,
+            r#"
+            @prefix dcat: <http://www.w3.org/ns/dcat#> .
+
+            <https://distribution.foo> a dcat:Dataset, dcat:Distribution ;
+                dcat:distribution <https://distribution.foo> .
+            <https://dataset.foo> a dcat:Dataset ;
+                dcat:distribution <https://distribution.foo> .
+            "#
+            .to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            Some(NamedNode::new_unchecked("https://dataset.foo")),
+            get_dataset_node(&store)
+        );
+    }
+
+    #[test]
+    fn test_self_referencing_dataset_node() {
+        let store = Store::new().unwrap();
+        parse_turtle(
+            &store,
+            r#"
+            @prefix dcat: <http://www.w3.org/ns/dcat#> .
+
+            <https://dataset.foo> a dcat:Dataset ;
+                dcat:distribution <https://dataset.foo> .
+            "#
+            .to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            Some(NamedNode::new_unchecked("https://dataset.foo")),
+            get_dataset_node(&store)
+        );
+    }
+
+    fn dataset_node_of(turtle: &str) -> Option<NamedNode> {
+        let store = Store::new().unwrap();
+        parse_turtle(&store, turtle.to_string()).unwrap();
+        get_dataset_node(&store)
+    }
+
+    #[test]
+    fn test_dataset_series_node() {
+        assert_eq!(
+            Some(NamedNode::new_unchecked("https://series.foo")),
+            dataset_node_of(
+                r#"
+                @prefix dcat: <http://www.w3.org/ns/dcat#> .
+                <https://series.foo> a dcat:DatasetSeries .
+                "#
+            )
+        );
+    }
+
+    #[test]
+    fn test_catalog_dataset_node() {
+        assert_eq!(
+            Some(NamedNode::new_unchecked("https://dataset.foo")),
+            dataset_node_of(
+                r#"
+                @prefix dcat: <http://www.w3.org/ns/dcat#> .
+                <https://catalog.foo> a dcat:Catalog ;
+                    dcat:dataset <https://dataset.foo> .
+                "#
+            )
+        );
+    }
+
+    #[test]
+    fn test_typed_dataset_preferred() {
+        assert_eq!(
+            Some(NamedNode::new_unchecked("https://dataset.foo")),
+            dataset_node_of(
+                r#"
+                @prefix dcat: <http://www.w3.org/ns/dcat#> .
+                <https://catalog.foo> dcat:dataset <https://other.foo> .
+                <https://series.foo> a dcat:DatasetSeries .
+                <https://dataset.foo> a dcat:Dataset .
+                "#
+            )
+        );
+    }
+
+    #[test]
+    fn test_catalog_dataset_nodes() {
+        let store = Store::new().unwrap();
+        parse_turtle(
+            &store,
+            r#"
+            @prefix dcat: <http://www.w3.org/ns/dcat#> .
+            <https://catalog.foo> a dcat:Catalog ;
+                dcat:dataset <https://dataset.foo/2> , <https://dataset.foo/1> , [ a dcat:Dataset ] .
+            <https://other-catalog.foo> a dcat:Catalog ;
+                dcat:dataset <https://dataset.foo/1> .
+            <https://untyped-catalog.foo> dcat:dataset <https://dataset.foo/3> .
+            "#
+            .to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                NamedNode::new_unchecked("https://dataset.foo/1"),
+                NamedNode::new_unchecked("https://dataset.foo/2"),
+            ],
+            get_catalog_dataset_nodes(&store)
+        );
+    }
+
+    #[test]
+    fn test_no_dataset_node() {
+        assert_eq!(
+            None,
+            dataset_node_of(
+                r#"
+                @prefix dcat: <http://www.w3.org/ns/dcat#> .
+                <https://distribution.foo> a dcat:Distribution .
+                "#
+            )
+        );
+    }
+
+    /// Writes an assessment of a dataset with distributions, each with the
+    /// quads written per distribution when assessing.
+    fn write_assessment(distributions: usize, sink: &impl QuadSink) {
+        let graph = GraphNameRef::DefaultGraph;
+        let dataset_assessment = NamedNodeRef::new_unchecked("https://dataset.assessment.foo");
+        let dataset = NamedNodeRef::new_unchecked("https://dataset.foo");
+        insert_dataset_assessment(dataset_assessment, dataset, Some("fdk-id"), graph, sink)
+            .unwrap();
+        add_boolean_quality_measurement(
+            dcat_mqa::KEYWORD_AVAILABILITY,
+            dataset_assessment,
+            dataset,
+            true,
+            graph,
+            sink,
+        )
+        .unwrap();
+
+        for i in 0..distributions {
+            let assessment =
+                NamedNode::new_unchecked(format!("https://distribution.assessment.foo/{}", i));
+            let distribution = NamedNode::new_unchecked(format!("https://distribution.foo/{}", i));
+            insert_distribution_assessment(
+                dataset_assessment,
+                assessment.as_ref(),
+                distribution.as_ref(),
+                graph,
+                sink,
+            )
+            .unwrap();
+            let measurements = [
+                dcat_mqa::MEDIA_TYPE_AVAILABILITY,
+                dcat_mqa::DOWNLOAD_URL_AVAILABILITY,
+                dcat_mqa::FORMAT_AVAILABILITY,
+                dcat_mqa::KNOWN_LICENSE,
+                dcat_mqa::OPEN_LICENSE,
+                dcat_mqa::AT_LEAST_FOUR_STARS,
+            ]
+            .into_iter()
+            .map(|metric| {
+                add_boolean_quality_measurement(
+                    metric,
+                    assessment.as_ref(),
+                    distribution.as_ref(),
+                    i % 2 == 0,
+                    graph,
+                    sink,
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+            add_measurement_annotation(
+                measurements[3].as_ref(),
+                &[("en", "Unknown license")],
+                graph,
+                sink,
+            )
+            .unwrap();
+            let annotation = add_five_star_annotation(assessment.as_ref(), graph, sink).unwrap();
+            add_derived_from(
+                annotation.as_ref().into(),
+                measurements[4].as_ref().into(),
+                graph,
+                sink,
+            )
+            .unwrap();
+            add_property(
+                annotation.as_ref().into(),
+                oa::HAS_BODY,
+                dcat_mqa::ZERO_STARS.into(),
+                graph,
+                sink,
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_buffered_quads_equal_unbuffered() {
+        let unbuffered = Store::new().unwrap();
+        write_assessment(3, &unbuffered);
+
+        let buffer = QuadBuffer::new();
+        write_assessment(3, &buffer);
+        assert!(!buffer.is_empty());
+        let buffered = Store::new().unwrap();
+        assert!(buffered.is_empty().unwrap());
+        buffer.flush(&buffered).unwrap();
+
+        assert!(buffer.is_empty());
+        assert_eq!(
+            dump_graph_canonical(&unbuffered).unwrap(),
+            dump_graph_canonical(&buffered).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_buffered_quads_faster_than_unbuffered() {
+        let start_time = std::time::Instant::now();
+        let unbuffered = Store::new().unwrap();
+        write_assessment(200, &unbuffered);
+        let unbuffered_elapsed = start_time.elapsed();
+
+        let start_time = std::time::Instant::now();
+        let buffered = Store::new().unwrap();
+        let buffer = QuadBuffer::new();
+        write_assessment(200, &buffer);
+        buffer.flush(&buffered).unwrap();
+        let buffered_elapsed = start_time.elapsed();
+
+        tracing::info!(
+            unbuffered_millis = unbuffered_elapsed.as_millis(),
+            buffered_millis = buffered_elapsed.as_millis(),
+            "inserted 200 distribution assessments"
+        );
+        assert_eq!(unbuffered.len().unwrap(), buffered.len().unwrap());
+        // Generous, as timings vary between machines and test runs.
+        assert!(buffered_elapsed < unbuffered_elapsed * 2);
+    }
+
+    fn deduplicated(turtle: &str) -> (Store, Vec<NamedNode>) {
+        let store = Store::new().unwrap();
+        parse_turtle(&store, turtle.to_string()).unwrap();
+        let datasets = deduplicate_dataset_nodes(&store).unwrap();
+        (store, datasets)
+    }
+
+    fn has_triple(store: &Store, subject: &str, predicate: NamedNodeRef, object: &str) -> bool {
+        store
+            .contains(QuadRef::new(
+                NamedNodeRef::new_unchecked(subject),
+                predicate,
+                NamedNodeRef::new_unchecked(object),
+                GraphNameRef::DefaultGraph,
+            ))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_deduplicate_trailing_slash() {
+        let (store, datasets) = deduplicated(
+            r#"
+            @prefix dcat: <http://www.w3.org/ns/dcat#> .
+            @prefix dct: <http://purl.org/dc/terms/> .
+            @prefix dcatnomqa: <https://data.norge.no/vocabulary/dcatno-mqa#> .
+
+            <https://catalog.foo> dcat:dataset <https://dataset.foo/> .
+            <https://dataset.foo> a dcat:Dataset ;
+                dcatnomqa:hasAssessment <https://dataset.assessment.foo> ;
+                dct:title "Dataset" .
+            <https://dataset.foo/> a dcat:Dataset ;
+                dcatnomqa:hasAssessment <https://other.assessment.foo> ;
+                dcat:distribution <https://distribution.foo> .
+            "#,
+        );
+
+        assert_eq!(
+            vec![NamedNode::new_unchecked("https://dataset.foo")],
+            datasets
+        );
+        assert!(has_triple(
+            &store,
+            "https://dataset.foo",
+            dcat::DISTRIBUTION,
+            "https://distribution.foo"
+        ));
+        assert!(has_property(
+            NamedNodeRef::new_unchecked("https://dataset.foo").into(),
+            dcterms::TITLE,
+            &store
+        ));
+        assert!(has_triple(
+            &store,
+            "https://catalog.foo",
+            dcat::DATASET_PREDICATE,
+            "https://dataset.foo"
+        ));
+        // The assessment of the first node is kept.
+        assert_eq!(
+            NamedNode::new_unchecked("https://dataset.assessment.foo"),
+            node_assessment(&store, NamedNodeRef::new_unchecked("https://dataset.foo")).unwrap()
+        );
+        assert!(!has_property(
+            NamedNodeRef::new_unchecked("https://dataset.foo/").into(),
+            rdf::TYPE,
+            &store
+        ));
+    }
+
+    #[test]
+    fn test_deduplicate_different_datasets() {
+        let (store, datasets) = deduplicated(
+            r#"
+            @prefix dcat: <http://www.w3.org/ns/dcat#> .
+            <https://dataset.foo/1> a dcat:Dataset ;
+                dcat:distribution <https://distribution.foo/1> .
+            <https://dataset.foo/1/2> a dcat:Dataset .
+            <https://dataset.foo/2> a dcat:Dataset .
+            "#,
+        );
+
+        assert_eq!(
+            vec![
+                NamedNode::new_unchecked("https://dataset.foo/1"),
+                NamedNode::new_unchecked("https://dataset.foo/1/2"),
+                NamedNode::new_unchecked("https://dataset.foo/2"),
+            ],
+            datasets
+        );
+        assert!(!has_property(
+            NamedNodeRef::new_unchecked("https://dataset.foo/2").into(),
+            dcat::DISTRIBUTION,
+            &store
+        ));
+    }
+
+    #[test]
+    fn test_deduplicate_three_way() {
+        let (store, datasets) = deduplicated(
+            r#"
+            @prefix dcat: <http://www.w3.org/ns/dcat#> .
+            <https://Dataset.FOO/ds> a dcat:Dataset ;
+                dcat:distribution <https://distribution.foo/1> .
+            <https://dataset.foo/ds> a dcat:Dataset ;
+                dcat:distribution <https://distribution.foo/2> .
+            <https://dataset.foo/ds/> a dcat:Dataset ;
+                dcat:distribution <https://distribution.foo/3> .
+            <https://dataset.foo/DS> a dcat:Dataset .
+            "#,
+        );
+
+        // Paths are case sensitive.
+        assert_eq!(
+            vec![
+                NamedNode::new_unchecked("https://Dataset.FOO/ds"),
+                NamedNode::new_unchecked("https://dataset.foo/DS"),
+            ],
+            datasets
+        );
+        for distribution in [
+            "https://distribution.foo/1",
+            "https://distribution.foo/2",
+            "https://distribution.foo/3",
+        ] {
+            assert!(has_triple(
+                &store,
+                "https://Dataset.FOO/ds",
+                dcat::DISTRIBUTION,
+                distribution
+            ));
+        }
     }
 }
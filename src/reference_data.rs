@@ -1,15 +1,85 @@
-use cached::proc_macro::cached;
-use http::{HeaderMap, HeaderValue};
+use http::{
+    header::{CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RETRY_AFTER},
+    HeaderMap, HeaderValue, StatusCode,
+};
 use lazy_static::lazy_static;
+use reqwest::{Client, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
 use serde_derive::Deserialize;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::{
+    sync::{Semaphore, SemaphorePermit},
+    time::Instant,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::Error,
+    kafka::seek::{days_from_civil, days_in_month, digits},
+    prometheus_metrics::{
+        REFERENCE_DATA_BREAKER_STATE, REFERENCE_DATA_INVALID_RESPONSES, REFERENCE_DATA_THROTTLED,
+        REFERENCE_DATA_TIER,
+    },
+    proxy::{ProxyConfig, PROXY},
+};
 
 lazy_static! {
     pub static ref REFERENCE_DATA_BASE_URL: String = env::var("REFERENCE_DATA_BASE_URL")
         .unwrap_or("https://data.norge.no".to_string());
     pub static ref REFERENCE_DATA_API_KEY: String =
         env::var("REFERENCE_DATA_API_KEY").unwrap_or("".to_string());
+    pub static ref DISK_CACHE: Option<DiskCache> =
+        env::var("REFERENCE_DATA_CACHE_DIR")
+            .ok()
+            .map(|dir| DiskCache {
+                dir: PathBuf::from(dir),
+                max_age: Duration::from_secs(
+                    env::var("REFERENCE_DATA_CACHE_MAX_AGE")
+                        .ok()
+                        .and_then(|v| v.parse().ok())
+                        .unwrap_or(7 * 24 * 60 * 60),
+                ),
+            });
+    static ref STRIP_QUERY_AND_FRAGMENT: bool =
+        env::var("REFERENCE_DATA_STRIP_QUERY_AND_FRAGMENT")
+            .map(|v| v != "false")
+            .unwrap_or(true);
+    static ref EMBEDDED_FALLBACK: bool = env::var("REFERENCE_DATA_EMBEDDED_FALLBACK")
+        .map(|v| v != "false")
+        .unwrap_or(true);
+    static ref VALIDATED_RESPONSES: Mutex<HashMap<String, ValidatedResponse>> =
+        Mutex::new(HashMap::new());
+    static ref REQUEST_PERMITS: Semaphore = Semaphore::new(
+        (positive_var("REFERENCE_DATA_MAX_CONCURRENT_REQUESTS", 4) as usize)
+            .min(Semaphore::MAX_PERMITS)
+    );
+    static ref BREAKER: Breaker = Breaker::new(
+        positive_var("REFERENCE_DATA_BREAKER_FAILURES", 5),
+        Duration::from_secs(positive_var("REFERENCE_DATA_BREAKER_COOLDOWN", 60)),
+    );
+    static ref RETRY_AFTER_CAP: Duration =
+        Duration::from_secs(positive_var("REFERENCE_DATA_RETRY_AFTER_CAP", 30));
+    static ref CLIENT: Client = build_client(
+        Duration::from_secs(10),
+        Duration::from_secs(5),
+        PROXY.as_ref(),
+        &REFERENCE_DATA_API_KEY,
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "reference data client error");
+        std::process::exit(1);
+    });
+    pub static ref REFERENCE_DATA_PROFILES: ReferenceDataProfiles =
+        ReferenceDataProfiles::from_env().unwrap_or_else(|e| {
+            tracing::error!(error = e.to_string(), "invalid reference data profiles");
+            std::process::exit(1);
+        });
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -53,120 +123,2395 @@ pub struct OpenLicense {
     pub code: String,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct Location {
+    pub uri: String,
+}
+
+/// Reference data collection as responded by the reference data service. An
+/// empty collection is almost certainly an upstream bug, and is rejected.
+trait Collection {
+    fn is_empty(&self) -> bool;
+}
+
+impl Collection for MediaTypeCollection {
+    fn is_empty(&self) -> bool {
+        self.media_types.is_empty()
+    }
+}
+
+impl Collection for FileTypeCollection {
+    fn is_empty(&self) -> bool {
+        self.file_types.is_empty()
+    }
+}
+
+impl Collection for OpenLicenseCollection {
+    fn is_empty(&self) -> bool {
+        self.open_licenses.is_empty()
+    }
+}
+
+/// Spatial authority, a single named list of locations.
+impl Collection for HashMap<String, Vec<Location>> {
+    fn is_empty(&self) -> bool {
+        self.values().all(|locations| locations.is_empty())
+    }
+}
+
+/// Spatial authorities recognized as aligned vocabularies: Kartverket
+/// administrative units and the EU countries and places authority lists.
+/// Each endpoint responds with a single named list of locations.
+const LOCATION_PATHS: [&str; 5] = [
+    "/reference-data/geonorge/administrative-enheter/fylker",
+    "/reference-data/geonorge/administrative-enheter/kommuner",
+    "/reference-data/geonorge/administrative-enheter/nasjoner",
+    "/reference-data/eu/countries",
+    "/reference-data/eu/places",
+];
+
+/// Snapshots of reference data bundled in the binary, used as a last resort
+/// when neither the reference data service nor the disk cache has them.
+const EMBEDDED_MEDIA_TYPES: &str = include_str!("../resources/reference-data/media-types.json");
+const EMBEDDED_FILE_TYPES: &str = include_str!("../resources/reference-data/file-types.json");
+const EMBEDDED_OPEN_LICENSES: &str = include_str!("../resources/reference-data/open-licenses.json");
+
+/// Source that served a reference data collection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tier {
+    Live,
+    DiskCache,
+    Embedded,
+}
+
+impl Tier {
+    const ALL: [Tier; 3] = [Tier::Live, Tier::DiskCache, Tier::Embedded];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Tier::Live => "live",
+            Tier::DiskCache => "disk_cache",
+            Tier::Embedded => "embedded",
+        }
+    }
+}
+
+/// Sets the tier gauge of a collection to 1 for the tier that served it, if
+/// any, and to 0 for the others.
+fn record_tier(name: &str, served: Option<Tier>) {
+    for tier in Tier::ALL {
+        REFERENCE_DATA_TIER
+            .with_label_values(&[name, tier.as_str()])
+            .set((served == Some(tier)) as i64);
+    }
+}
+
+/// How long a collection served live is kept before it is fetched again.
+const LIVE_TIME_TO_LIVE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long a collection served by a fallback, or not obtained at all, is
+/// kept, so that the reference data service is soon asked again after a
+/// failed or invalid response.
+const FALLBACK_TIME_TO_LIVE: Duration = Duration::from_secs(5 * 60);
+
+/// A collection kept in memory between assessments. Concurrent lookups of an
+/// expired collection wait for a single fetch.
+struct CachedCollection<T> {
+    entry: tokio::sync::Mutex<Option<(Option<T>, Instant)>>,
+}
+
+impl<T: Clone> CachedCollection<T> {
+    fn new() -> Self {
+        CachedCollection {
+            entry: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// The kept collection, or else the collection fetched and whether it was
+    /// served live.
+    async fn get(&self, fetch: impl Future<Output = (Option<T>, bool)>) -> Option<T> {
+        let mut entry = self.entry.lock().await;
+        if let Some((data, expires)) = entry.as_ref() {
+            if Instant::now() < *expires {
+                return data.clone();
+            }
+        }
+
+        let (data, live) = fetch.await;
+        let time_to_live = if live {
+            LIVE_TIME_TO_LIVE
+        } else {
+            FALLBACK_TIME_TO_LIVE
+        };
+        *entry = Some((data.clone(), Instant::now() + time_to_live));
+        data
+    }
+}
+
+/// Value of a variable that must be a positive integer, or else the default.
+pub(crate) fn positive_var(name: &str, default: u64) -> u64 {
+    env::var(name)
+        .ok()
+        .map(|value| parse_positive(name, &value))
+        .transpose()
+        .unwrap_or_else(|e| {
+            tracing::error!(error = e.to_string(), "invalid positive integer setting");
+            std::process::exit(1);
+        })
+        .unwrap_or(default)
+}
+
+fn parse_positive(name: &str, value: &str) -> Result<u64, Error> {
+    match value.parse::<u64>() {
+        Ok(0) => Err(format!("{} must be greater than 0", name).into()),
+        Ok(value) => Ok(value),
+        Err(e) => Err(format!("invalid {} '{}': {}", name, value, e).into()),
+    }
+}
+
+/// State of the circuit breaker of an endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Requests are sent.
+    Closed,
+    /// Requests are not sent until the cooldown has passed.
+    Open,
+    /// A single probe request is sent to decide whether to close again.
+    HalfOpen,
+}
+
+impl BreakerState {
+    const ALL: [BreakerState; 3] = [
+        BreakerState::Closed,
+        BreakerState::Open,
+        BreakerState::HalfOpen,
+    ];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            BreakerState::Closed => "closed",
+            BreakerState::Open => "open",
+            BreakerState::HalfOpen => "half_open",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct EndpointBreaker {
+    failures: u64,
+    opened: Option<Instant>,
+    probe: Option<Instant>,
+}
+
+/// Circuit breakers of the reference data endpoints, so that an unavailable
+/// reference data service is not hammered by every lookup. An endpoint
+/// failing `failure_threshold` times in a row is not requested during
+/// `cooldown`, its lookups falling back to the disk cache and embedded
+/// snapshots. A single probe request is then sent, closing the breaker on
+/// success and opening it again on failure.
+#[derive(Debug)]
+pub struct Breaker {
+    failure_threshold: u64,
+    cooldown: Duration,
+    endpoints: Mutex<HashMap<String, EndpointBreaker>>,
+}
+
+impl Breaker {
+    pub fn new(failure_threshold: u64, cooldown: Duration) -> Self {
+        Breaker {
+            failure_threshold,
+            cooldown,
+            endpoints: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn endpoints(&self) -> MutexGuard<'_, HashMap<String, EndpointBreaker>> {
+        self.endpoints.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn state_of(&self, breaker: &EndpointBreaker, now: Instant) -> BreakerState {
+        match breaker.opened {
+            None => BreakerState::Closed,
+            Some(opened) if now.saturating_duration_since(opened) < self.cooldown => {
+                BreakerState::Open
+            }
+            Some(_) => BreakerState::HalfOpen,
+        }
+    }
+
+    /// Whether a request may be sent to an endpoint, taking the probe of a
+    /// half-open breaker. A probe that never reports, like a cancelled one,
+    /// is replaced after another cooldown.
+    pub fn allow(&self, endpoint: &str, now: Instant) -> bool {
+        let mut endpoints = self.endpoints();
+        let breaker = endpoints.entry(endpoint.to_string()).or_default();
+        let state = self.state_of(breaker, now);
+        let allowed = match (state, breaker.probe) {
+            (BreakerState::Closed, _) => true,
+            (BreakerState::Open, _) => false,
+            (BreakerState::HalfOpen, Some(probe))
+                if now.saturating_duration_since(probe) < self.cooldown =>
+            {
+                false
+            }
+            (BreakerState::HalfOpen, _) => {
+                breaker.probe = Some(now);
+                true
+            }
+        };
+        record_breaker_state(endpoint, state);
+        allowed
+    }
+
+    /// Records whether a request sent to an endpoint succeeded.
+    pub fn record(&self, endpoint: &str, success: bool, now: Instant) {
+        let mut endpoints = self.endpoints();
+        let breaker = endpoints.entry(endpoint.to_string()).or_default();
+        if success {
+            if breaker.opened.is_some() {
+                tracing::info!("Closing circuit breaker of remote {}", endpoint);
+            }
+            *breaker = EndpointBreaker::default();
+        } else {
+            breaker.failures += 1;
+            breaker.probe = None;
+            if breaker.opened.is_some() || breaker.failures >= self.failure_threshold {
+                if breaker.opened.is_none() {
+                    tracing::warn!(
+                        "Opening circuit breaker of remote {} after {} failures",
+                        endpoint,
+                        breaker.failures
+                    );
+                }
+                breaker.opened = Some(now);
+            }
+        }
+        record_breaker_state(endpoint, self.state_of(breaker, now));
+    }
+
+    /// Opens the breaker of an endpoint at once, like when the service keeps
+    /// throttling requests, counting as a failure.
+    pub fn trip(&self, endpoint: &str, now: Instant) {
+        let mut endpoints = self.endpoints();
+        let breaker = endpoints.entry(endpoint.to_string()).or_default();
+        if breaker.opened.is_none() {
+            tracing::warn!(
+                "Opening circuit breaker of remote {} as it is throttled",
+                endpoint
+            );
+        }
+        breaker.failures += 1;
+        breaker.probe = None;
+        breaker.opened = Some(now);
+        record_breaker_state(endpoint, self.state_of(breaker, now));
+    }
+}
+
+/// Sets the breaker gauge of an endpoint to 1 for its state and to 0 for the
+/// others.
+fn record_breaker_state(endpoint: &str, state: BreakerState) {
+    for s in BreakerState::ALL {
+        REFERENCE_DATA_BREAKER_STATE
+            .with_label_values(&[endpoint, s.as_str()])
+            .set((s == state) as i64);
+    }
+}
+
+/// On-disk copies of fetched reference data, used when the reference data
+/// service cannot be reached, e.g. right after a restart.
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    pub dir: PathBuf,
+    /// Copies older than this are ignored.
+    pub max_age: Duration,
+}
+
+#[derive(Debug, Deserialize)]
+struct DiskCacheEntry<T> {
+    timestamp: u64,
+    data: T,
+}
+
+impl DiskCache {
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", name))
+    }
+
+    /// Writes the raw JSON of a collection, via a temporary file so readers
+    /// never see a partial write.
+    fn write(&self, name: &str, json: &str) -> std::io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let tmp = self.dir.join(format!("{}.{}.tmp", name, Uuid::new_v4()));
+        fs::write(
+            &tmp,
+            format!(r#"{{"timestamp":{},"data":{}}}"#, unix_seconds(), json),
+        )?;
+        fs::rename(tmp, self.path(name))
+    }
+
+    fn read<T: DeserializeOwned>(&self, name: &str) -> Option<T> {
+        let content = fs::read_to_string(self.path(name)).ok()?;
+        let entry = serde_json::from_str::<DiskCacheEntry<T>>(&content)
+            .map_err(|e| tracing::warn!("Cannot read cached {} {}", name, e))
+            .ok()?;
+
+        if unix_seconds().saturating_sub(entry.timestamp) > self.max_age.as_secs() {
+            tracing::warn!("Cached {} is expired", name);
+            return None;
+        }
+        Some(entry.data)
+    }
+}
+
+/// Body of the last successful response from a url, with the validators
+/// sent back to the reference data service on refresh, so an unchanged
+/// collection is answered with 304 Not Modified instead of downloaded again.
+#[derive(Debug, Clone, Default)]
+struct ValidatedResponse {
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+    body: String,
+}
+
+impl ValidatedResponse {
+    fn new(headers: &HeaderMap, body: String) -> Self {
+        ValidatedResponse {
+            etag: headers.get(ETAG).cloned(),
+            last_modified: headers.get(LAST_MODIFIED).cloned(),
+            body,
+        }
+    }
+
+    fn has_validators(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+
+    /// Headers making a request conditional on the collection having changed.
+    fn conditional_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(etag) = &self.etag {
+            headers.insert(IF_NONE_MATCH, etag.clone());
+        }
+        if let Some(last_modified) = &self.last_modified {
+            headers.insert(IF_MODIFIED_SINCE, last_modified.clone());
+        }
+        headers
+    }
+
+    /// The response kept when the collection is not modified, with any
+    /// validators of the 304 response replacing the previous ones.
+    fn revalidated(self, headers: &HeaderMap) -> Self {
+        ValidatedResponse {
+            etag: headers.get(ETAG).cloned().or(self.etag),
+            last_modified: headers.get(LAST_MODIFIED).cloned().or(self.last_modified),
+            body: self.body,
+        }
+    }
+}
+
+fn validated_response(url: &str) -> Option<ValidatedResponse> {
+    VALIDATED_RESPONSES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(url)
+        .cloned()
+}
+
+fn store_validated_response(url: &str, response: ValidatedResponse) {
+    let mut responses = VALIDATED_RESPONSES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if response.has_validators() {
+        responses.insert(url.to_string(), response);
+    } else {
+        responses.remove(url);
+    }
+}
+
+fn unix_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
 pub fn strip_http_scheme(uri: String) -> String {
     uri.replace("http://", "").replace("https://", "")
 }
 
-pub async fn valid_media_type(media_type: String) -> bool {
-    match get_remote_media_types().await {
-        Some(media_types) => media_types.contains_key(strip_http_scheme(media_type).as_str()),
-        None => false,
-    }
+/// Look up a URI in reference data keyed by URIs without scheme. Falls back
+/// to the URI without query string and fragment, unless disabled, and then to
+/// the URI with a trailing `/` added or removed.
+pub fn lookup_normalized<'a, V>(map: &'a HashMap<String, V>, uri: &str) -> Option<&'a V> {
+    lookup(map, uri, *STRIP_QUERY_AND_FRAGMENT)
 }
 
-pub async fn valid_file_type(file_type: String) -> bool {
-    match get_remote_file_types().await {
-        Some(file_types) => file_types.contains_key(strip_http_scheme(file_type).as_str()),
-        None => false,
+fn lookup<'a, V>(
+    map: &'a HashMap<String, V>,
+    uri: &str,
+    strip_query_and_fragment: bool,
+) -> Option<&'a V> {
+    let key = strip_http_scheme(uri.to_string());
+    if let Some(value) = map.get(&key) {
+        return Some(value);
     }
+
+    let key = if strip_query_and_fragment {
+        key.split(['?', '#']).next().unwrap_or_default()
+    } else {
+        key.as_str()
+    };
+    map.get(key).or_else(|| match key.strip_suffix('/') {
+        Some(key) => map.get(key),
+        None => map.get(&format!("{}/", key)),
+    })
+}
+
+/// Reference data collections used by an assessment, obtained once before
+/// assessing so that the checks are plain lookups. Collections that are not
+/// needed, or cannot be fetched, are None and match nothing.
+#[derive(Debug, Clone, Default)]
+pub struct ReferenceData {
+    pub media_types: Option<HashMap<String, MediaType>>,
+    pub file_types: Option<HashMap<String, FileType>>,
+    pub open_licenses: Option<HashMap<String, OpenLicense>>,
+    pub locations: Option<HashMap<String, Location>>,
 }
 
-pub async fn valid_open_license(license: String) -> bool {
-    match get_remote_open_licenses().await {
-        Some(open_licenses) => open_licenses.contains_key(strip_http_scheme(license).as_str()),
-        None => false,
+impl ReferenceData {
+    /// Obtains the reference data of a profile, the main one when none is
+    /// given, like [`ReferenceDataProvider::fetch`].
+    pub async fn fetch(profile: Option<&str>, spatial: bool, distributions: bool) -> Self {
+        REFERENCE_DATA_PROFILES
+            .get(profile)
+            .fetch(spatial, distributions)
+            .await
+    }
+
+    /// Media types, file types and open licenses of the snapshots bundled in
+    /// the binary, without locations, for assessing without the reference
+    /// data service.
+    #[cfg(any(test, feature = "bench-utils"))]
+    pub fn embedded() -> Self {
+        fn collection<T: DeserializeOwned, V>(
+            json: &str,
+            items: impl FnOnce(T) -> Vec<V>,
+            uri: impl Fn(&V) -> &str,
+        ) -> Option<HashMap<String, V>> {
+            let fetched = serde_json::from_str::<T>(json)
+                .ok()
+                .map(|json| (json, Tier::Embedded));
+            keyed(fetched, items, uri).0
+        }
+
+        ReferenceData {
+            media_types: collection(
+                EMBEDDED_MEDIA_TYPES,
+                |json: MediaTypeCollection| json.media_types,
+                |mt| mt.uri.as_str(),
+            ),
+            file_types: collection(
+                EMBEDDED_FILE_TYPES,
+                |json: FileTypeCollection| json.file_types,
+                |ft| ft.uri.as_str(),
+            ),
+            open_licenses: collection(
+                EMBEDDED_OPEN_LICENSES,
+                |json: OpenLicenseCollection| json.open_licenses,
+                |license| license.uri.as_str(),
+            ),
+            locations: None,
+        }
+    }
+
+    /// Whether a format or media type is a known file type or media type.
+    pub fn valid_format(&self, format: &str) -> bool {
+        contains(&self.file_types, format) || contains(&self.media_types, format)
+    }
+
+    /// IANA media type, like `text/csv`, of a known EU file type.
+    pub fn file_type_media_type(&self, file_type: &str) -> Option<&str> {
+        self.file_types
+            .as_ref()
+            .and_then(|map| lookup_normalized(map, file_type))
+            .map(|file_type| file_type.media_type.as_str())
+    }
+
+    /// Name, like `text/csv`, of a known IANA media type.
+    pub fn media_type_name(&self, media_type: &str) -> Option<String> {
+        self.media_types
+            .as_ref()
+            .and_then(|map| lookup_normalized(map, media_type))
+            .map(|media_type| format!("{}/{}", media_type.r#type, media_type.sub_type))
+    }
+
+    pub fn valid_open_license(&self, license: &str) -> bool {
+        contains(&self.open_licenses, license)
     }
+
+    pub fn valid_spatial(&self, spatial: &str) -> bool {
+        contains(&self.locations, spatial)
+    }
+}
+
+fn contains<V>(map: &Option<HashMap<String, V>>, uri: &str) -> bool {
+    map.as_ref()
+        .is_some_and(|map| lookup_normalized(map, uri).is_some())
 }
 
-fn construct_headers() -> HeaderMap {
+fn construct_headers(api_key: &str) -> Result<HeaderMap, Error> {
     let mut headers = HeaderMap::new();
     headers.insert(
         "X-API-KEY",
-        HeaderValue::from_static(&REFERENCE_DATA_API_KEY),
+        HeaderValue::from_str(api_key).map_err(|_| "invalid reference data API key")?,
     );
-    headers
+    Ok(headers)
 }
 
-#[cached(time = 86400)]
-pub async fn get_remote_media_types() -> Option<HashMap<String, MediaType>> {
-    let response = reqwest::Client::new()
-        .get(format!("{}/reference-data/iana/media-types", REFERENCE_DATA_BASE_URL.to_string()).as_str())
-        .headers(construct_headers())
-        .send()
-        .await;
+/// Builds a client for the reference data service, sending the API key with
+/// every request, bounded by the given timeouts, and sending requests through
+/// the proxy if given.
+pub fn build_client(
+    timeout: Duration,
+    connect_timeout: Duration,
+    proxy: Option<&ProxyConfig>,
+    api_key: &str,
+) -> Result<Client, Error> {
+    let builder = Client::builder()
+        .default_headers(construct_headers(api_key)?)
+        .timeout(timeout)
+        .connect_timeout(connect_timeout)
+        .pool_idle_timeout(Duration::from_secs(90));
+    match proxy {
+        Some(proxy) => proxy.apply(builder),
+        None => builder,
+    }
+    .build()
+    .map_err(|e| e.to_string().into())
+}
 
-    match response {
-        Ok(resp) => match resp.json::<MediaTypeCollection>().await {
-            Ok(json) => Some(
-                json.media_types
-                    .into_iter()
-                    .map(|ft| (strip_http_scheme(ft.uri.clone()), ft))
-                    .collect::<HashMap<String, MediaType>>(),
-            ),
-            Err(e) => {
-                tracing::warn!("Cannot get remote media-types {}", e);
-                None
+/// Fetches and deserializes a reference data collection, falling back to the
+/// disk cache if given, and returning None on failure. Requests are limited
+/// to a few at a time, and are not sent while the circuit breaker of the
+/// endpoint is open.
+async fn fetch_reference_data<T: DeserializeOwned + Collection>(
+    client: &Client,
+    breaker: &Breaker,
+    url: &str,
+    name: &str,
+    disk_cache: Option<&DiskCache>,
+) -> Option<(T, Tier)> {
+    if !breaker.allow(name, Instant::now()) {
+        tracing::debug!("Circuit breaker of remote {} is open", name);
+        return read_disk_cache(name, disk_cache);
+    }
+    let fetched = fetch_live::<T>(client, url, name).await;
+    match &fetched {
+        Err(FetchFailure::Throttled) => breaker.trip(name, Instant::now()),
+        _ => breaker.record(name, fetched.is_ok(), Instant::now()),
+    }
+
+    let Ok((json, validated)) = fetched else {
+        return read_disk_cache(name, disk_cache);
+    };
+    // Rewritten also when not modified, renewing its timestamp.
+    if let Some(cache) = disk_cache {
+        if let Err(e) = cache.write(name, &validated.body) {
+            tracing::warn!("Cannot write cached {} {}", name, e);
+        }
+    }
+    store_validated_response(url, validated);
+    record_tier(name, Some(Tier::Live));
+    Some((json, Tier::Live))
+}
+
+/// Why a collection was not fetched from the service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FetchFailure {
+    Failed,
+    /// The service kept answering 429 or 503.
+    Throttled,
+}
+
+/// Requests a reference data collection from the service. A collection
+/// fetched before is requested conditionally, and its previous body is used
+/// when the service answers that it is not modified. Responses that are not
+/// JSON, like an error page of a proxy, and empty collections are failures.
+async fn fetch_live<T: DeserializeOwned + Collection>(
+    client: &Client,
+    url: &str,
+    name: &str,
+) -> Result<(T, ValidatedResponse), FetchFailure> {
+    let previous = validated_response(url);
+    let request = || {
+        let request = client.get(url);
+        match &previous {
+            Some(previous) => request.headers(previous.conditional_headers()),
+            None => request,
+        }
+    };
+    let (response, _permit) =
+        match send_retrying_throttled(request, name, *RETRY_AFTER_CAP, &REQUEST_PERMITS).await {
+            Ok((response, permit)) => (Ok(response), permit),
+            Err(e) => (Err(e), None),
+        };
+
+    let validated = match response {
+        Ok(resp) if is_throttled(resp.status()) => {
+            tracing::warn!("Remote {} throttled with status {}", name, resp.status());
+            return Err(FetchFailure::Throttled);
+        }
+        Ok(resp) if resp.status() == StatusCode::NOT_MODIFIED && previous.is_some() => {
+            tracing::debug!("Remote {} not modified", name);
+            Ok(previous.unwrap_or_default().revalidated(resp.headers()))
+        }
+        Ok(resp) => match resp.error_for_status() {
+            Ok(resp) if !is_json(resp.headers()) => {
+                let content_type = resp
+                    .headers()
+                    .get(CONTENT_TYPE)
+                    .and_then(|value| value.to_str().ok())
+                    .unwrap_or_default()
+                    .to_string();
+                log_invalid_response(name, &format!("content type '{}'", content_type));
+                return Err(FetchFailure::Failed);
+            }
+            Ok(resp) => {
+                let headers = resp.headers().clone();
+                resp.text()
+                    .await
+                    .map(|body| ValidatedResponse::new(&headers, body))
             }
+            Err(e) => Err(e),
         },
+        Err(e) => Err(e),
+    };
+
+    let validated = match validated {
+        Ok(validated) => validated,
         Err(e) => {
-            tracing::warn!("Cannot get remote media-types {}", e);
-            None
+            log_fetch_error(name, e);
+            return Err(FetchFailure::Failed);
+        }
+    };
+
+    match serde_json::from_str::<T>(&validated.body) {
+        Ok(json) if json.is_empty() => {
+            log_invalid_response(name, "empty collection");
+            Err(FetchFailure::Failed)
+        }
+        Ok(json) => Ok((json, validated)),
+        Err(e) => {
+            log_invalid_response(name, &e.to_string());
+            Err(FetchFailure::Failed)
+        }
+    }
+}
+
+/// Delay before retrying a throttled request without a `Retry-After` header.
+const THROTTLED_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Retries of a throttled request before giving up.
+const THROTTLED_RETRIES: u32 = 1;
+
+/// Whether the service asks to retry later, as data.norge.no does when rate
+/// limiting during full harvests.
+fn is_throttled(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE
+}
+
+/// Sends a request, retrying it when throttled after the delay asked for by
+/// the `Retry-After` header, or else after [`THROTTLED_BACKOFF`]. The throttled
+/// response is returned when the delay is longer than `max_delay` or the
+/// retries are used up. Each attempt holds one of `permits`, released while
+/// waiting to retry, and the response is returned with it so that its body is
+/// read holding it.
+async fn send_retrying_throttled<'a>(
+    request: impl Fn() -> RequestBuilder,
+    name: &str,
+    max_delay: Duration,
+    permits: &'a Semaphore,
+) -> reqwest::Result<(Response, Option<SemaphorePermit<'a>>)> {
+    let mut retries = 0;
+    loop {
+        let permit = permits.acquire().await.ok();
+        let response = request().send().await?;
+        let throttled = is_throttled(response.status());
+        REFERENCE_DATA_THROTTLED
+            .with_label_values(&[name])
+            .set(throttled as i64);
+        if !throttled {
+            return Ok((response, permit));
+        }
+
+        let delay = retry_after(response.headers(), SystemTime::now()).unwrap_or(THROTTLED_BACKOFF);
+        if retries == THROTTLED_RETRIES || delay > max_delay {
+            return Ok((response, permit));
         }
+        drop(permit);
+        retries += 1;
+        tracing::warn!(
+            "Remote {} throttled, retrying in {} ms",
+            name,
+            delay.as_millis()
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Delay asked for by the `Retry-After` header of a response, in seconds or
+/// as an HTTP date. A date in the past asks for no delay.
+fn retry_after(headers: &HeaderMap, now: SystemTime) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = parse_http_date(value)?;
+    Some(date.duration_since(now).unwrap_or_default())
+}
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Parse an HTTP date in the IMF-fixdate format, like
+/// `Sun, 06 Nov 1994 08:49:37 GMT`. The obsolete formats are not accepted.
+fn parse_http_date(value: &str) -> Option<SystemTime> {
+    let (_, date) = value.split_once(", ")?;
+    let mut parts = date.split(' ');
+    let (day, month, year, time) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    if parts.next()? != "GMT" || parts.next().is_some() {
+        return None;
+    }
+
+    let month = MONTHS.iter().position(|name| *name == month)? as i64 + 1;
+    let (year, day) = (digits(year, 4)?, digits(day, 2)?);
+    if !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
     }
+    let mut time_parts = time.split(':');
+    let (hour, minute, second) = match (
+        time_parts.next().and_then(|v| digits(v, 2)),
+        time_parts.next().and_then(|v| digits(v, 2)),
+        time_parts.next().and_then(|v| digits(v, 2)),
+        time_parts.next(),
+    ) {
+        (Some(hour), Some(minute), Some(second), None)
+            if hour <= 23 && minute <= 59 && second <= 60 =>
+        {
+            (hour, minute, second)
+        }
+        _ => return None,
+    };
+
+    let seconds = days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second;
+    UNIX_EPOCH.checked_add(Duration::from_secs(u64::try_from(seconds).ok()?))
 }
 
-#[cached(time = 86400)]
-pub async fn get_remote_file_types() -> Option<HashMap<String, FileType>> {
-    let response = reqwest::Client::new()
-        .get(format!("{}/reference-data/eu/file-types", REFERENCE_DATA_BASE_URL.to_string()).as_str())
-        .headers(construct_headers())
+/// Whether a response has the `application/json` content type, parameters
+/// like the charset aside.
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case("application/json"))
+}
+
+fn read_disk_cache<T: DeserializeOwned>(
+    name: &str,
+    disk_cache: Option<&DiskCache>,
+) -> Option<(T, Tier)> {
+    let data = disk_cache.and_then(|cache| cache.read(name));
+    record_tier(name, data.is_some().then_some(Tier::DiskCache));
+    data.map(|data| (data, Tier::DiskCache))
+}
+
+/// Fetches a reference data collection like [`fetch_reference_data`], falling
+/// back to the embedded snapshot if given.
+async fn fetch_with_embedded_fallback<T: DeserializeOwned + Collection>(
+    client: &Client,
+    breaker: &Breaker,
+    url: &str,
+    name: &str,
+    disk_cache: Option<&DiskCache>,
+    embedded: Option<&str>,
+) -> Option<(T, Tier)> {
+    if let Some(fetched) = fetch_reference_data(client, breaker, url, name, disk_cache).await {
+        return Some(fetched);
+    }
+
+    let data = serde_json::from_str::<T>(embedded?)
+        .map_err(|e| tracing::warn!("Cannot parse embedded {} {}", name, e))
+        .ok()?;
+    tracing::warn!("Using embedded {}, which may be outdated", name);
+    record_tier(name, Some(Tier::Embedded));
+    Some((data, Tier::Embedded))
+}
+
+fn log_fetch_error(name: &str, e: reqwest::Error) {
+    if e.is_timeout() {
+        tracing::warn!("Timed out getting remote {} {}", name, e);
+    } else {
+        tracing::warn!("Cannot get remote {} {}", name, e);
+    }
+}
+
+/// Counts a response of the reference data service that is not a usable
+/// collection, like an HTML error page served with status 200.
+fn log_invalid_response(name: &str, reason: &str) {
+    tracing::warn!("Invalid remote {} {}", name, reason);
+    REFERENCE_DATA_INVALID_RESPONSES
+        .with_label_values(&[name])
+        .inc();
+}
+
+/// Checks that the reference data service at a base url responds to a
+/// request for media types.
+pub async fn check_reference_data(base_url: &str) -> Result<(), Error> {
+    CLIENT
+        .get(format!("{}/reference-data/iana/media-types", base_url))
         .send()
-        .await;
+        .await
+        .and_then(|response| response.error_for_status())
+        .map(|_| ())
+        .map_err(|e| format!("reference data service unreachable: {}", e).into())
+}
 
-    match response {
-        Ok(resp) => match resp.json::<FileTypeCollection>().await {
-            Ok(json) => Some(
-                json.file_types
-                    .into_iter()
-                    .map(|ft| (strip_http_scheme(ft.uri.clone()), ft))
-                    .collect::<HashMap<String, FileType>>(),
-            ),
-            Err(e) => {
-                tracing::warn!("Cannot get remote file-types {}", e);
+/// Reference data collection keyed by URIs without scheme, and whether it was
+/// served live.
+fn keyed<T, V>(
+    fetched: Option<(T, Tier)>,
+    items: impl FnOnce(T) -> Vec<V>,
+    uri: impl Fn(&V) -> &str,
+) -> (Option<HashMap<String, V>>, bool) {
+    let live = matches!(fetched, Some((_, Tier::Live)));
+    let map = fetched.map(|(json, _)| {
+        items(json)
+            .into_iter()
+            .map(|item| (strip_http_scheme(uri(&item).to_string()), item))
+            .collect()
+    });
+    (map, live)
+}
+
+/// Name of the profile of the reference data service configured by
+/// `REFERENCE_DATA_BASE_URL` and `REFERENCE_DATA_API_KEY`.
+pub const MAIN_PROFILE: &str = "main";
+
+/// Profile of a reference data service as configured in the JSON file named
+/// by `REFERENCE_DATA_PROFILES`, keyed by the name of the profile.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ProfileConfig {
+    pub base_url: String,
+    #[serde(default)]
+    pub api_key: String,
+    /// Prefix of the names of the collections of the profile, in the disk
+    /// cache, circuit breakers and metrics. The name of the profile when not
+    /// given.
+    pub cache_namespace: Option<String>,
+}
+
+/// Parses the profiles of a `REFERENCE_DATA_PROFILES` file, like
+/// `{"test": {"baseUrl": "https://staging.data.norge.no", "apiKey": "…"}}`.
+pub fn parse_profiles(json: &str) -> Result<HashMap<String, ProfileConfig>, Error> {
+    serde_json::from_str(json).map_err(|e| format!("invalid reference data profiles: {}", e).into())
+}
+
+/// Cache namespaces name files of the disk cache, so are restricted to
+/// letters, digits, `-` and `_`.
+fn validate_cache_namespace(namespace: &str) -> Result<(), Error> {
+    if !namespace.is_empty()
+        && namespace
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        Ok(())
+    } else {
+        Err(format!(
+            "invalid reference data cache namespace '{}', expected letters, digits, '-' and '_'",
+            namespace
+        )
+        .into())
+    }
+}
+
+/// Reference data service of a profile. Its collections are kept apart from
+/// those of other profiles: in memory by the provider, and on disk, by the
+/// circuit breakers and in metrics by names prefixed with its cache
+/// namespace.
+pub struct ReferenceDataProvider {
+    base_url: String,
+    client: Client,
+    /// Prefix of the names of the collections, empty for the main profile.
+    prefix: String,
+    media_types: CachedCollection<HashMap<String, MediaType>>,
+    file_types: CachedCollection<HashMap<String, FileType>>,
+    open_licenses: CachedCollection<HashMap<String, OpenLicense>>,
+    locations: CachedCollection<HashMap<String, Location>>,
+}
+
+impl ReferenceDataProvider {
+    /// Provider of the service at a base url, sending requests with the
+    /// client, and caching collections in a namespace, none for the main
+    /// profile.
+    pub fn new(base_url: &str, client: Client, cache_namespace: Option<&str>) -> Self {
+        ReferenceDataProvider {
+            base_url: base_url.to_string(),
+            client,
+            prefix: cache_namespace
+                .map(|namespace| format!("{}.", namespace))
+                .unwrap_or_default(),
+            media_types: CachedCollection::new(),
+            file_types: CachedCollection::new(),
+            open_licenses: CachedCollection::new(),
+            locations: CachedCollection::new(),
+        }
+    }
+
+    /// Name of a collection of the profile.
+    fn name(&self, collection: &str) -> String {
+        format!("{}{}", self.prefix, collection)
+    }
+
+    /// Obtains the locations if spatial coverage is to be checked, and the
+    /// media types, file types and open licenses if distributions are.
+    pub async fn fetch(&self, spatial: bool, distributions: bool) -> ReferenceData {
+        let locations = async {
+            if spatial {
+                self.locations().await
+            } else {
                 None
             }
-        },
-        Err(e) => {
-            tracing::warn!("Cannot get remote file-types {}", e);
-            None
+        };
+        let distribution_data = async {
+            if distributions {
+                futures::join!(self.media_types(), self.file_types(), self.open_licenses())
+            } else {
+                (None, None, None)
+            }
+        };
+        let (locations, (media_types, file_types, open_licenses)) =
+            futures::join!(locations, distribution_data);
+        ReferenceData {
+            media_types,
+            file_types,
+            open_licenses,
+            locations,
         }
     }
+
+    pub async fn media_types(&self) -> Option<HashMap<String, MediaType>> {
+        self.media_types
+            .get(async {
+                let fetched = fetch_with_embedded_fallback::<MediaTypeCollection>(
+                    &self.client,
+                    &BREAKER,
+                    format!("{}/reference-data/iana/media-types", self.base_url).as_str(),
+                    &self.name("media-types"),
+                    DISK_CACHE.as_ref(),
+                    EMBEDDED_FALLBACK.then_some(EMBEDDED_MEDIA_TYPES),
+                )
+                .await;
+                keyed(fetched, |json| json.media_types, |mt| mt.uri.as_str())
+            })
+            .await
+    }
+
+    pub async fn file_types(&self) -> Option<HashMap<String, FileType>> {
+        self.file_types
+            .get(async {
+                let fetched = fetch_with_embedded_fallback::<FileTypeCollection>(
+                    &self.client,
+                    &BREAKER,
+                    format!("{}/reference-data/eu/file-types", self.base_url).as_str(),
+                    &self.name("file-types"),
+                    DISK_CACHE.as_ref(),
+                    EMBEDDED_FALLBACK.then_some(EMBEDDED_FILE_TYPES),
+                )
+                .await;
+                keyed(fetched, |json| json.file_types, |ft| ft.uri.as_str())
+            })
+            .await
+    }
+
+    pub async fn open_licenses(&self) -> Option<HashMap<String, OpenLicense>> {
+        self.open_licenses
+            .get(async {
+                let fetched = fetch_with_embedded_fallback::<OpenLicenseCollection>(
+                    &self.client,
+                    &BREAKER,
+                    format!("{}/reference-data/open-licenses", self.base_url).as_str(),
+                    &self.name("open-licenses"),
+                    DISK_CACHE.as_ref(),
+                    EMBEDDED_FALLBACK.then_some(EMBEDDED_OPEN_LICENSES),
+                )
+                .await;
+                keyed(
+                    fetched,
+                    |json| json.open_licenses,
+                    |license| license.uri.as_str(),
+                )
+            })
+            .await
+    }
+
+    pub async fn locations(&self) -> Option<HashMap<String, Location>> {
+        self.locations
+            .get(fetch_locations(
+                &self.client,
+                &BREAKER,
+                &self.base_url,
+                &self.prefix,
+                DISK_CACHE.as_ref(),
+            ))
+            .await
+    }
 }
 
-#[cached(time = 86400)]
-pub async fn get_remote_open_licenses() -> Option<HashMap<String, OpenLicense>> {
-    let response = reqwest::Client::new()
-        .get(format!("{}/reference-data/open-licenses", REFERENCE_DATA_BASE_URL.to_string()).as_str())
-        .headers(construct_headers())
-        .send()
+/// Providers of the main profile and of the profiles selected per message by
+/// the `reference-data-profile` header, like of a test catalog whose
+/// reference data differs.
+pub struct ReferenceDataProfiles {
+    main: ReferenceDataProvider,
+    profiles: HashMap<String, ReferenceDataProvider>,
+}
+
+impl ReferenceDataProfiles {
+    /// Providers of the main profile and of each configured profile, which
+    /// must neither be named after the main profile nor share a cache
+    /// namespace.
+    pub fn new(
+        main: ReferenceDataProvider,
+        configs: HashMap<String, ProfileConfig>,
+    ) -> Result<Self, Error> {
+        let mut namespaces = HashSet::new();
+        let mut profiles = HashMap::new();
+        for (name, config) in configs {
+            if name == MAIN_PROFILE {
+                return Err(format!(
+                    "reference data profile '{}' is configured by REFERENCE_DATA_BASE_URL",
+                    MAIN_PROFILE
+                )
+                .into());
+            }
+            let namespace = config
+                .cache_namespace
+                .clone()
+                .unwrap_or_else(|| name.clone());
+            validate_cache_namespace(&namespace)?;
+            if !namespaces.insert(namespace.clone()) {
+                return Err(format!(
+                    "reference data cache namespace '{}' is used by several profiles",
+                    namespace
+                )
+                .into());
+            }
+            let client = build_client(
+                Duration::from_secs(10),
+                Duration::from_secs(5),
+                PROXY.as_ref(),
+                &config.api_key,
+            )?;
+            let provider = ReferenceDataProvider::new(&config.base_url, client, Some(&namespace));
+            profiles.insert(name, provider);
+        }
+        Ok(ReferenceDataProfiles { main, profiles })
+    }
+
+    fn from_env() -> Result<Self, Error> {
+        let main = ReferenceDataProvider::new(&REFERENCE_DATA_BASE_URL, CLIENT.clone(), None);
+        let configs = match env::var("REFERENCE_DATA_PROFILES") {
+            Ok(path) if !path.trim().is_empty() => {
+                let json = fs::read_to_string(&path).map_err(|e| {
+                    format!("cannot read reference data profiles '{}': {}", path, e)
+                })?;
+                parse_profiles(&json)?
+            }
+            _ => HashMap::new(),
+        };
+        ReferenceDataProfiles::new(main, configs)
+    }
+
+    /// Provider of a profile, or of the main profile when none is given or
+    /// the profile is unknown.
+    pub fn get(&self, profile: Option<&str>) -> &ReferenceDataProvider {
+        match profile {
+            None | Some(MAIN_PROFILE) => &self.main,
+            Some(profile) => self.profiles.get(profile).unwrap_or_else(|| {
+                tracing::warn!(
+                    profile,
+                    "unknown reference data profile, using the main profile"
+                );
+                &self.main
+            }),
+        }
+    }
+}
+
+/// Fetches all spatial authorities, named with the prefix of a profile,
+/// returning None only if none of them could be fetched, and whether all of
+/// them were served live.
+async fn fetch_locations(
+    client: &Client,
+    breaker: &Breaker,
+    base_url: &str,
+    prefix: &str,
+    disk_cache: Option<&DiskCache>,
+) -> (Option<HashMap<String, Location>>, bool) {
+    let mut locations: Option<HashMap<String, Location>> = None;
+    let mut live = true;
+
+    for path in LOCATION_PATHS {
+        let fetched = fetch_reference_data::<HashMap<String, Vec<Location>>>(
+            client,
+            breaker,
+            format!("{}{}", base_url, path).as_str(),
+            &format!(
+                "{}{}",
+                prefix,
+                path.trim_start_matches("/reference-data/")
+                    .replace('/', "-")
+            ),
+            disk_cache,
+        )
+        .await;
+        live &= matches!(fetched, Some((_, Tier::Live)));
+        if let Some((json, _)) = fetched {
+            locations.get_or_insert_with(HashMap::new).extend(
+                json.into_values()
+                    .flatten()
+                    .map(|location| (strip_http_scheme(location.uri.clone()), location)),
+            );
+        }
+    }
+
+    (locations, live)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::MockServer;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Instant;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn reference_map(uris: &[&str]) -> HashMap<String, String> {
+        uris.iter()
+            .map(|uri| (strip_http_scheme(uri.to_string()), uri.to_string()))
+            .collect()
+    }
+
+    /// Breaker of a single test, as breakers of shared endpoints would be
+    /// opened by the failures of other tests.
+    fn breaker() -> Breaker {
+        Breaker::new(5, Duration::from_secs(60))
+    }
+
+    #[test]
+    fn test_lookup_fragment_and_query() {
+        let map = reference_map(&["https://creativecommons.org/licenses/by/4.0/"]);
+        for uri in [
+            "https://creativecommons.org/licenses/by/4.0/",
+            "http://creativecommons.org/licenses/by/4.0/",
+            "https://creativecommons.org/licenses/by/4.0/#deed.no",
+            "https://creativecommons.org/licenses/by/4.0/?lang=no",
+            "https://creativecommons.org/licenses/by/4.0/?lang=no#deed.no",
+        ] {
+            assert!(lookup(&map, uri, true).is_some(), "{} should match", uri);
+        }
+
+        assert!(lookup(
+            &map,
+            "https://creativecommons.org/licenses/by/4.0/#deed.no",
+            false
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_lookup_trailing_slash() {
+        let map = reference_map(&[
+            "https://creativecommons.org/licenses/by/4.0/",
+            "http://data.norge.no/nlod/no/2.0",
+        ]);
+        for uri in [
+            "https://creativecommons.org/licenses/by/4.0",
+            "https://creativecommons.org/licenses/by/4.0#deed.no",
+            "http://data.norge.no/nlod/no/2.0/",
+            "http://data.norge.no/nlod/no/2.0/?lang=en",
+        ] {
+            assert!(lookup(&map, uri, true).is_some(), "{} should match", uri);
+        }
+    }
+
+    #[test]
+    fn test_lookup_distinct_entries() {
+        let map = reference_map(&[
+            "https://creativecommons.org/licenses/by/4.0",
+            "https://creativecommons.org/licenses/by/4.0/",
+            "https://creativecommons.org/licenses/by-sa/4.0/",
+            "https://data.norge.no/license#foo",
+        ]);
+
+        assert_eq!(
+            Some(&"https://creativecommons.org/licenses/by/4.0".to_string()),
+            lookup(&map, "https://creativecommons.org/licenses/by/4.0", true)
+        );
+        assert_eq!(
+            Some(&"https://creativecommons.org/licenses/by/4.0/".to_string()),
+            lookup(&map, "https://creativecommons.org/licenses/by/4.0/", true)
+        );
+        assert_eq!(
+            Some(&"https://creativecommons.org/licenses/by/4.0".to_string()),
+            lookup(
+                &map,
+                "https://creativecommons.org/licenses/by/4.0#deed.no",
+                true
+            )
+        );
+        assert_eq!(
+            Some(&"https://data.norge.no/license#foo".to_string()),
+            lookup(&map, "https://data.norge.no/license#foo", true)
+        );
+        assert!(lookup(
+            &map,
+            "https://creativecommons.org/licenses/by-nc/4.0/",
+            true
+        )
+        .is_none());
+        assert!(lookup(&map, "https://data.norge.no/license#bar", true).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_times_out() {
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses");
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(r#"{"openLicenses":[]}"#)
+                    .delay(Duration::from_secs(5));
+            })
+            .await;
+
+        let client = build_client(
+            Duration::from_millis(200),
+            Duration::from_millis(200),
+            None,
+            "",
+        )
+        .unwrap();
+        let start_time = Instant::now();
+        let result = fetch_reference_data::<OpenLicenseCollection>(
+            &client,
+            &breaker(),
+            &server.url("/reference-data/open-licenses"),
+            "open-licenses",
+            None,
+        )
         .await;
 
-    match response {
-        Ok(resp) => match resp.json::<OpenLicenseCollection>().await {
-            Ok(json) => Some(
-                json.open_licenses
-                    .into_iter()
-                    .map(|ft| (strip_http_scheme(ft.uri.clone()), ft))
-                    .collect::<HashMap<String, OpenLicense>>(),
+        assert!(result.is_none());
+        assert!(start_time.elapsed() < Duration::from_secs(2));
+    }
+
+    /// Serves the open licenses on keep-alive connections, counting the
+    /// connections accepted.
+    async fn serve_licenses_counting_connections() -> (String, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!(
+            "http://{}/reference-data/open-licenses",
+            listener.local_addr().unwrap()
+        );
+        let connections = Arc::new(AtomicUsize::new(0));
+        let accepted = connections.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                accepted.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                        NLOD.len(),
+                        NLOD
+                    );
+                    let mut request = [0; 4096];
+                    // The GET requests have no body, so each read is a request.
+                    while matches!(stream.read(&mut request).await, Ok(n) if n > 0) {
+                        if stream.write_all(response.as_bytes()).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        (url, connections)
+    }
+
+    #[tokio::test]
+    async fn test_fetch_with_shared_client() {
+        let (url, connections) = serve_licenses_counting_connections().await;
+
+        for _ in 0..3 {
+            let licenses = fetch_reference_data::<OpenLicenseCollection>(
+                &CLIENT,
+                &breaker(),
+                &url,
+                "shared-client-open-licenses",
+                None,
+            )
+            .await
+            .unwrap()
+            .0;
+            assert_eq!(1, licenses.open_licenses.len());
+        }
+
+        // The requests reuse the pooled connection of the shared client.
+        assert_eq!(1, connections.load(Ordering::SeqCst));
+    }
+
+    const NLOD: &str =
+        r#"{"openLicenses":[{"uri":"http://data.norge.no/nlod/no/2.0","code":"NLOD20"}]}"#;
+    const CC_BY: &str = r#"{"openLicenses":[{"uri":"http://creativecommons.org/licenses/by/4.0/","code":"CC BY 4.0"}]}"#;
+
+    async fn fetch_license_codes(server: &MockServer) -> Vec<String> {
+        fetch_reference_data::<OpenLicenseCollection>(
+            &CLIENT,
+            &breaker(),
+            &server.url("/reference-data/open-licenses"),
+            "open-licenses",
+            None,
+        )
+        .await
+        .unwrap()
+        .0
+        .open_licenses
+        .into_iter()
+        .map(|license| license.code)
+        .collect()
+    }
+
+    #[tokio::test]
+    async fn test_fetch_not_modified() {
+        let server = MockServer::start_async().await;
+        let modified = server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses")
+                    .header_missing("if-none-match");
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .header("etag", r#""v1""#)
+                    .header("last-modified", "Wed, 01 Jan 2025 00:00:00 GMT")
+                    .body(NLOD);
+            })
+            .await;
+        let not_modified = server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses")
+                    .header("if-none-match", r#""v1""#)
+                    .header("if-modified-since", "Wed, 01 Jan 2025 00:00:00 GMT");
+                then.status(304);
+            })
+            .await;
+
+        assert_eq!(vec!["NLOD20"], fetch_license_codes(&server).await);
+        assert_eq!(vec!["NLOD20"], fetch_license_codes(&server).await);
+        assert_eq!(vec!["NLOD20"], fetch_license_codes(&server).await);
+
+        modified.assert_hits_async(1).await;
+        not_modified.assert_hits_async(2).await;
+    }
+
+    #[tokio::test]
+    async fn test_fetch_changed_etag_replaces_data() {
+        let server = MockServer::start_async().await;
+        let first = server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses")
+                    .header_missing("if-none-match");
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .header("etag", r#""v1""#)
+                    .body(NLOD);
+            })
+            .await;
+        let changed = server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses")
+                    .header("if-none-match", r#""v1""#);
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .header("etag", r#""v2""#)
+                    .body(CC_BY);
+            })
+            .await;
+        let not_modified = server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses")
+                    .header("if-none-match", r#""v2""#);
+                then.status(304);
+            })
+            .await;
+
+        assert_eq!(vec!["NLOD20"], fetch_license_codes(&server).await);
+        assert_eq!(vec!["CC BY 4.0"], fetch_license_codes(&server).await);
+        assert_eq!(vec!["CC BY 4.0"], fetch_license_codes(&server).await);
+
+        first.assert_hits_async(1).await;
+        changed.assert_hits_async(1).await;
+        not_modified.assert_hits_async(1).await;
+    }
+
+    #[tokio::test]
+    async fn test_fetch_locations() {
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.path("/reference-data/geonorge/administrative-enheter/kommuner");
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(
+                        r#"{"municipalities":[{"uri":"https://data.geonorge.no/administrativeEnheter/kommune/id/172833"}]}"#,
+                    );
+            })
+            .await;
+        server
+            .mock_async(|when, then| {
+                when.path("/reference-data/eu/countries");
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(
+                        r#"{"countries":[{"uri":"http://publications.europa.eu/resource/authority/country/NOR"}]}"#,
+                    );
+            })
+            .await;
+
+        let (locations, live) =
+            fetch_locations(&CLIENT, &breaker(), &server.base_url(), "", None).await;
+        let locations = locations.unwrap();
+        assert!(!live);
+
+        assert!(locations.contains_key("data.geonorge.no/administrativeEnheter/kommune/id/172833"));
+        assert!(locations.contains_key("publications.europa.eu/resource/authority/country/NOR"));
+        assert!(!locations.contains_key("example.com/places/nowhere"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_locations_unavailable() {
+        let server = MockServer::start_async().await;
+
+        assert!(
+            fetch_locations(&CLIENT, &breaker(), &server.base_url(), "", None)
+                .await
+                .0
+                .is_none()
+        );
+    }
+
+    fn disk_cache(max_age: Duration) -> DiskCache {
+        DiskCache {
+            dir: env::temp_dir().join(format!("reference-data-{}", Uuid::new_v4())),
+            max_age,
+        }
+    }
+
+    async fn fetch_licenses(
+        server: &MockServer,
+        cache: &DiskCache,
+    ) -> Option<OpenLicenseCollection> {
+        fetch_reference_data::<OpenLicenseCollection>(
+            &CLIENT,
+            &breaker(),
+            &server.url("/reference-data/open-licenses"),
+            "open-licenses",
+            Some(cache),
+        )
+        .await
+        .map(|(licenses, _)| licenses)
+    }
+
+    #[tokio::test]
+    async fn test_fetch_writes_disk_cache() {
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses");
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(
+                        r#"{"openLicenses":[{"uri":"http://data.norge.no/nlod/no/2.0","code":"NLOD20"}]}"#,
+                    );
+            })
+            .await;
+        let cache = disk_cache(Duration::from_secs(60));
+
+        let licenses = fetch_licenses(&server, &cache).await.unwrap();
+        assert_eq!(1, licenses.open_licenses.len());
+
+        let cached = cache
+            .read::<OpenLicenseCollection>("open-licenses")
+            .unwrap();
+        assert_eq!("NLOD20", cached.open_licenses[0].code);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_falls_back_to_disk_cache() {
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses");
+                then.status(503);
+            })
+            .await;
+        let cache = disk_cache(Duration::from_secs(60));
+        cache
+            .write(
+                "open-licenses",
+                r#"{"openLicenses":[{"uri":"http://data.norge.no/nlod/no/2.0","code":"NLOD20"}]}"#,
+            )
+            .unwrap();
+
+        let licenses = fetch_licenses(&server, &cache).await.unwrap();
+        assert_eq!("NLOD20", licenses.open_licenses[0].code);
+    }
+
+    #[tokio::test]
+    async fn test_expired_disk_cache_rejected() {
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses");
+                then.status(503);
+            })
+            .await;
+        let cache = disk_cache(Duration::from_secs(60));
+        fs::create_dir_all(&cache.dir).unwrap();
+        fs::write(
+            cache.path("open-licenses"),
+            r#"{"timestamp":0,"data":{"openLicenses":[]}}"#,
+        )
+        .unwrap();
+
+        assert!(fetch_licenses(&server, &cache).await.is_none());
+    }
+
+    #[test]
+    fn test_embedded_snapshots() {
+        let media_types =
+            serde_json::from_str::<MediaTypeCollection>(EMBEDDED_MEDIA_TYPES).unwrap();
+        assert!(media_types
+            .media_types
+            .iter()
+            .any(|media_type| media_type.name == "csv"));
+
+        let file_types = serde_json::from_str::<FileTypeCollection>(EMBEDDED_FILE_TYPES).unwrap();
+        assert!(file_types
+            .file_types
+            .iter()
+            .any(|file_type| file_type.code == "CSV"));
+
+        let licenses =
+            serde_json::from_str::<OpenLicenseCollection>(EMBEDDED_OPEN_LICENSES).unwrap();
+        assert!(licenses
+            .open_licenses
+            .iter()
+            .any(|license| license.code == "NLOD20"));
+    }
+
+    fn tier_gauge(name: &str, tier: Tier) -> i64 {
+        REFERENCE_DATA_TIER
+            .with_label_values(&[name, tier.as_str()])
+            .get()
+    }
+
+    #[tokio::test]
+    async fn test_fetch_falls_back_to_embedded() {
+        // Gauges are process wide, so the collection is named after the test.
+        let name = "embedded-fallback-open-licenses";
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses");
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(CC_BY);
+            })
+            .await;
+        let url = server.url("/reference-data/open-licenses");
+
+        let (licenses, tier) = fetch_with_embedded_fallback::<OpenLicenseCollection>(
+            &CLIENT,
+            &breaker(),
+            &url,
+            name,
+            None,
+            Some(EMBEDDED_OPEN_LICENSES),
+        )
+        .await
+        .unwrap();
+        assert_eq!(1, licenses.open_licenses.len());
+        assert_eq!(Tier::Live, tier);
+        assert_eq!(1, tier_gauge(name, Tier::Live));
+        assert_eq!(0, tier_gauge(name, Tier::Embedded));
+
+        mock.delete_async().await;
+
+        let (licenses, tier) = fetch_with_embedded_fallback::<OpenLicenseCollection>(
+            &CLIENT,
+            &breaker(),
+            &url,
+            name,
+            None,
+            Some(EMBEDDED_OPEN_LICENSES),
+        )
+        .await
+        .unwrap();
+        assert_eq!(Tier::Embedded, tier);
+        assert!(licenses
+            .open_licenses
+            .iter()
+            .any(|license| license.uri == "http://data.norge.no/nlod/no/2.0"));
+        assert_eq!(0, tier_gauge(name, Tier::Live));
+        assert_eq!(0, tier_gauge(name, Tier::DiskCache));
+        assert_eq!(1, tier_gauge(name, Tier::Embedded));
+    }
+
+    #[tokio::test]
+    async fn test_embedded_fallback_disabled() {
+        let name = "embedded-fallback-disabled-open-licenses";
+        let server = MockServer::start_async().await;
+
+        let licenses = fetch_with_embedded_fallback::<OpenLicenseCollection>(
+            &CLIENT,
+            &breaker(),
+            &server.url("/reference-data/open-licenses"),
+            name,
+            None,
+            None,
+        )
+        .await;
+
+        assert!(licenses.is_none());
+        assert_eq!(0, tier_gauge(name, Tier::Embedded));
+    }
+
+    fn invalid_responses(name: &str) -> u64 {
+        REFERENCE_DATA_INVALID_RESPONSES
+            .with_label_values(&[name])
+            .get()
+    }
+
+    async fn fetch_named_licenses(
+        server: &MockServer,
+        name: &str,
+        cache: &DiskCache,
+    ) -> Option<(OpenLicenseCollection, Tier)> {
+        fetch_reference_data::<OpenLicenseCollection>(
+            &CLIENT,
+            &breaker(),
+            &server.url("/reference-data/open-licenses"),
+            name,
+            Some(cache),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_html_response_rejected() {
+        // Counters are process wide, so the collection is named after the test.
+        let name = "html-response-open-licenses";
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses");
+                then.status(200)
+                    .header("content-type", "text/html; charset=utf-8")
+                    .body("<html><body>Bad Gateway</body></html>");
+            })
+            .await;
+        let cache = disk_cache(Duration::from_secs(60));
+
+        assert!(fetch_named_licenses(&server, name, &cache).await.is_none());
+        assert_eq!(1, invalid_responses(name));
+
+        cache.write(name, NLOD).unwrap();
+        let (licenses, tier) = fetch_named_licenses(&server, name, &cache).await.unwrap();
+        assert_eq!("NLOD20", licenses.open_licenses[0].code);
+        assert_eq!(Tier::DiskCache, tier);
+        assert_eq!(2, invalid_responses(name));
+    }
+
+    #[tokio::test]
+    async fn test_empty_collection_rejected() {
+        let name = "empty-collection-open-licenses";
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses");
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .header("etag", r#""empty""#)
+                    .body(r#"{"openLicenses":[]}"#);
+            })
+            .await;
+        let cache = disk_cache(Duration::from_secs(60));
+
+        assert!(fetch_named_licenses(&server, name, &cache).await.is_none());
+        assert_eq!(1, invalid_responses(name));
+        // Neither written to the disk cache nor revalidated later.
+        assert!(cache.read::<OpenLicenseCollection>(name).is_none());
+        let url = server.url("/reference-data/open-licenses");
+        assert!(validated_response(&url).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_valid_response_accepted() {
+        let name = "valid-response-open-licenses";
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(NLOD);
+            })
+            .await;
+        let cache = disk_cache(Duration::from_secs(60));
+
+        let (licenses, tier) = fetch_named_licenses(&server, name, &cache).await.unwrap();
+        assert_eq!("NLOD20", licenses.open_licenses[0].code);
+        assert_eq!(Tier::Live, tier);
+        assert_eq!(0, invalid_responses(name));
+    }
+
+    #[test]
+    fn test_is_json() {
+        let headers = |content_type: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+            headers
+        };
+
+        assert!(is_json(&headers("application/json")));
+        assert!(is_json(&headers("Application/JSON; charset=utf-8")));
+        assert!(!is_json(&headers("text/html")));
+        assert!(!is_json(&headers("application/ld+json")));
+        assert!(!is_json(&HeaderMap::new()));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_fallback_collections_kept_shortly() {
+        let cache = CachedCollection::new();
+        let fetches = AtomicUsize::new(0);
+        let fetch = |live| {
+            let fetches = &fetches;
+            async move {
+                fetches.fetch_add(1, Ordering::SeqCst);
+                (Some(vec!["NLOD20"]), live)
+            }
+        };
+
+        cache.get(fetch(false)).await;
+        cache.get(fetch(false)).await;
+        assert_eq!(1, fetches.load(Ordering::SeqCst));
+
+        tokio::time::advance(FALLBACK_TIME_TO_LIVE).await;
+        cache.get(fetch(true)).await;
+        assert_eq!(2, fetches.load(Ordering::SeqCst));
+
+        tokio::time::advance(FALLBACK_TIME_TO_LIVE).await;
+        assert_eq!(Some(vec!["NLOD20"]), cache.get(fetch(true)).await);
+        assert_eq!(2, fetches.load(Ordering::SeqCst));
+
+        tokio::time::advance(LIVE_TIME_TO_LIVE).await;
+        cache.get(fetch(true)).await;
+        assert_eq!(3, fetches.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_through_proxy() {
+        let proxy_server = MockServer::start_async().await;
+        let proxied = proxy_server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses");
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(NLOD);
+            })
+            .await;
+        let proxy = ProxyConfig::new(&proxy_server.base_url(), "").unwrap();
+        let client = build_client(
+            Duration::from_secs(5),
+            Duration::from_secs(5),
+            Some(&proxy),
+            "",
+        )
+        .unwrap();
+
+        // The host does not resolve, so the request is answered by the proxy.
+        let (licenses, _) = fetch_reference_data::<OpenLicenseCollection>(
+            &client,
+            &breaker(),
+            "http://reference-data.invalid/reference-data/open-licenses",
+            "proxied-open-licenses",
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!("NLOD20", licenses.open_licenses[0].code);
+        proxied.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_fetch_bypassing_proxy() {
+        let proxy_server = MockServer::start_async().await;
+        let proxied = proxy_server
+            .mock_async(|when, then| {
+                when.any_request();
+                then.status(502);
+            })
+            .await;
+        let server = MockServer::start_async().await;
+        let direct = server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses");
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(NLOD);
+            })
+            .await;
+        let proxy = ProxyConfig::new(&proxy_server.base_url(), "127.0.0.0/8,localhost").unwrap();
+        let client = build_client(
+            Duration::from_secs(5),
+            Duration::from_secs(5),
+            Some(&proxy),
+            "",
+        )
+        .unwrap();
+
+        let fetched = fetch_reference_data::<OpenLicenseCollection>(
+            &client,
+            &breaker(),
+            &server.url("/reference-data/open-licenses"),
+            "unproxied-open-licenses",
+            None,
+        )
+        .await;
+        assert!(fetched.is_some());
+        direct.assert_async().await;
+        proxied.assert_hits_async(0).await;
+    }
+
+    fn breaker_gauge(endpoint: &str, state: BreakerState) -> i64 {
+        REFERENCE_DATA_BREAKER_STATE
+            .with_label_values(&[endpoint, state.as_str()])
+            .get()
+    }
+
+    #[test]
+    fn test_breaker_opens_after_consecutive_failures() {
+        // Gauges are process wide, so endpoints are named after the test.
+        let endpoint = "breaker-opens-open-licenses";
+        let breaker = Breaker::new(3, Duration::from_secs(60));
+        let start = tokio::time::Instant::now();
+
+        assert!(breaker.allow(endpoint, start));
+        breaker.record(endpoint, false, start);
+        breaker.record(endpoint, false, start);
+        // A success resets the count of failures.
+        breaker.record(endpoint, true, start);
+        breaker.record(endpoint, false, start);
+        breaker.record(endpoint, false, start);
+        assert_eq!(1, breaker_gauge(endpoint, BreakerState::Closed));
+        assert!(breaker.allow(endpoint, start));
+
+        breaker.record(endpoint, false, start);
+        assert_eq!(1, breaker_gauge(endpoint, BreakerState::Open));
+        assert_eq!(0, breaker_gauge(endpoint, BreakerState::Closed));
+        let later = start + Duration::from_secs(59);
+        assert!(!breaker.allow(endpoint, later));
+        assert_eq!(1, breaker_gauge(endpoint, BreakerState::Open));
+
+        // Other endpoints have their own breakers.
+        assert!(breaker.allow("breaker-opens-media-types", later));
+    }
+
+    #[test]
+    fn test_breaker_half_open_probe() {
+        let endpoint = "breaker-probe-open-licenses";
+        let breaker = Breaker::new(1, Duration::from_secs(60));
+        let start = tokio::time::Instant::now();
+        breaker.record(endpoint, false, start);
+
+        let cooled_down = start + Duration::from_secs(60);
+        assert!(breaker.allow(endpoint, cooled_down));
+        assert_eq!(1, breaker_gauge(endpoint, BreakerState::HalfOpen));
+        // A single probe is sent at a time.
+        assert!(!breaker.allow(endpoint, cooled_down + Duration::from_secs(1)));
+
+        // A failed probe opens the breaker for another cooldown.
+        let probed = cooled_down + Duration::from_secs(2);
+        breaker.record(endpoint, false, probed);
+        assert_eq!(1, breaker_gauge(endpoint, BreakerState::Open));
+        assert!(!breaker.allow(endpoint, probed + Duration::from_secs(59)));
+        assert!(breaker.allow(endpoint, probed + Duration::from_secs(60)));
+
+        // A probe never reporting is replaced after a cooldown.
+        assert!(!breaker.allow(endpoint, probed + Duration::from_secs(119)));
+        assert!(breaker.allow(endpoint, probed + Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn test_breaker_closes_after_successful_probe() {
+        let endpoint = "breaker-closes-open-licenses";
+        let breaker = Breaker::new(2, Duration::from_secs(60));
+        let start = tokio::time::Instant::now();
+        breaker.record(endpoint, false, start);
+        breaker.record(endpoint, false, start);
+
+        let cooled_down = start + Duration::from_secs(60);
+        assert!(breaker.allow(endpoint, cooled_down));
+        breaker.record(endpoint, true, cooled_down);
+        assert_eq!(1, breaker_gauge(endpoint, BreakerState::Closed));
+        assert_eq!(0, breaker_gauge(endpoint, BreakerState::HalfOpen));
+
+        // Failures are counted from zero again.
+        breaker.record(endpoint, false, cooled_down);
+        assert!(breaker.allow(endpoint, cooled_down));
+    }
+
+    #[tokio::test]
+    async fn test_open_breaker_falls_back() {
+        let name = "open-breaker-open-licenses";
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses");
+                then.status(503);
+            })
+            .await;
+        let breaker = Breaker::new(1, Duration::from_secs(60));
+        let url = server.url("/reference-data/open-licenses");
+
+        for _ in 0..2 {
+            let (licenses, tier) = fetch_with_embedded_fallback::<OpenLicenseCollection>(
+                &CLIENT,
+                &breaker,
+                &url,
+                name,
+                None,
+                Some(EMBEDDED_OPEN_LICENSES),
+            )
+            .await
+            .unwrap();
+            assert!(!licenses.open_licenses.is_empty());
+            assert_eq!(Tier::Embedded, tier);
+        }
+        // The throttled request is retried once, and the service is then not
+        // requested while the breaker is open.
+        mock.assert_hits_async(2).await;
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let now = UNIX_EPOCH + Duration::from_secs(784111777);
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(RETRY_AFTER, HeaderValue::from_str(value).unwrap());
+            headers
+        };
+
+        assert_eq!(
+            Some(Duration::from_secs(120)),
+            retry_after(&headers("120"), now)
+        );
+        assert_eq!(
+            Some(Duration::from_secs(60)),
+            retry_after(&headers("Sun, 06 Nov 1994 08:50:37 GMT"), now)
+        );
+        assert_eq!(
+            Some(Duration::ZERO),
+            retry_after(&headers("Sun, 06 Nov 1994 08:48:37 GMT"), now)
+        );
+        assert_eq!(None, retry_after(&HeaderMap::new(), now));
+        for value in [
+            "-1",
+            "soon",
+            "Sunday, 06-Nov-94 08:49:37 GMT",
+            "Sun Nov  6 08:49:37 1994",
+            "Sun, 06 Nov 1994 08:49:37 CET",
+            "Sun, 31 Nov 1994 08:49:37 GMT",
+            "Sun, 06 Nov 1994 24:49:37 GMT",
+        ] {
+            assert_eq!(None, retry_after(&headers(value), now), "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_parse_http_date() {
+        assert_eq!(
+            Some(UNIX_EPOCH + Duration::from_secs(784111777)),
+            parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT")
+        );
+        assert_eq!(
+            Some(UNIX_EPOCH + Duration::from_secs(1709209815)),
+            parse_http_date("Thu, 29 Feb 2024 12:30:15 GMT")
+        );
+    }
+
+    /// HTTP date of a time, the inverse of [`parse_http_date`], with a day
+    /// name that is not checked.
+    fn http_date(time: SystemTime) -> String {
+        let seconds = time.duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+        let (days, seconds) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+        // Civil date of the days since 1970-01-01, after Howard Hinnant.
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let day_of_era = z - era * 146097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        format!(
+            "Mon, {:02} {} {} {:02}:{:02}:{:02} GMT",
+            day,
+            MONTHS[month as usize - 1],
+            year,
+            seconds / 3600,
+            seconds % 3600 / 60,
+            seconds % 60
+        )
+    }
+
+    fn throttled_gauge(name: &str) -> i64 {
+        REFERENCE_DATA_THROTTLED.with_label_values(&[name]).get()
+    }
+
+    /// Replaces a mock of a throttled response, once requested, with one of
+    /// the open licenses, while the fetch waits to retry.
+    async fn unthrottle(server: &MockServer, throttled: httpmock::Mock<'_>) {
+        while throttled.hits_async().await == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        throttled.delete_async().await;
+        server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses");
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(NLOD);
+            })
+            .await;
+    }
+
+    async fn fetch_throttled_licenses(
+        server: &MockServer,
+        name: &str,
+        breaker: &Breaker,
+    ) -> Option<(OpenLicenseCollection, Tier)> {
+        fetch_reference_data::<OpenLicenseCollection>(
+            &CLIENT,
+            breaker,
+            &server.url("/reference-data/open-licenses"),
+            name,
+            None,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_fetch_retried_after_seconds() {
+        let name = "retry-after-seconds-open-licenses";
+        let server = MockServer::start_async().await;
+        let throttled = server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses");
+                then.status(429).header("retry-after", "1");
+            })
+            .await;
+        let breaker = breaker();
+
+        let start_time = Instant::now();
+        let (fetched, _) = tokio::join!(
+            fetch_throttled_licenses(&server, name, &breaker),
+            unthrottle(&server, throttled)
+        );
+        let (licenses, tier) = fetched.unwrap();
+        assert_eq!("NLOD20", licenses.open_licenses[0].code);
+        assert_eq!(Tier::Live, tier);
+        assert!(start_time.elapsed() >= Duration::from_secs(1));
+        assert_eq!(0, throttled_gauge(name));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_retried_after_date() {
+        let name = "retry-after-date-open-licenses";
+        let server = MockServer::start_async().await;
+        let retry_at = http_date(SystemTime::now() + Duration::from_secs(2));
+        let throttled = server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses");
+                then.status(503).header("retry-after", retry_at.as_str());
+            })
+            .await;
+        let breaker = breaker();
+
+        let start_time = Instant::now();
+        let (fetched, _) = tokio::join!(
+            fetch_throttled_licenses(&server, name, &breaker),
+            unthrottle(&server, throttled)
+        );
+        assert_eq!(Tier::Live, fetched.unwrap().1);
+        // The date is truncated to seconds.
+        assert!(start_time.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_permit_released_while_waiting_to_retry() {
+        let server = MockServer::start_async().await;
+        let throttled = server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses");
+                then.status(429).header("retry-after", "1");
+            })
+            .await;
+        let url = server.url("/reference-data/open-licenses");
+        let permits = Semaphore::new(1);
+
+        let (sent, _) = tokio::join!(
+            send_retrying_throttled(
+                || CLIENT.get(&url),
+                "permit-released-open-licenses",
+                Duration::from_secs(30),
+                &permits
             ),
-            Err(e) => {
-                tracing::warn!("Cannot get remote open-licenses {}", e);
-                None
+            async {
+                while throttled.hits_async().await == 0 {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+                // Another request gets the permit well before the retry.
+                let permit =
+                    tokio::time::timeout(Duration::from_millis(500), permits.acquire()).await;
+                assert!(matches!(permit, Ok(Ok(_))));
+                drop(permit);
+                unthrottle(&server, throttled).await;
             }
-        },
-        Err(e) => {
-            tracing::warn!("Cannot get remote open-licenses {}", e);
-            None
+        );
+        let (response, permit) = sent.unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(permit.is_some());
+        assert_eq!(0, permits.available_permits());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_throttled_without_retry_after() {
+        let name = "throttled-without-retry-after-open-licenses";
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses");
+                then.status(429);
+            })
+            .await;
+        let breaker = breaker();
+
+        let start_time = Instant::now();
+        assert!(fetch_throttled_licenses(&server, name, &breaker)
+            .await
+            .is_none());
+        assert!(start_time.elapsed() >= THROTTLED_BACKOFF);
+        mock.assert_hits_async(1 + THROTTLED_RETRIES as usize).await;
+        assert_eq!(1, throttled_gauge(name));
+        // Still throttled after retrying, the endpoint is not requested again
+        // until the breaker cools down.
+        assert!(!breaker.allow(name, tokio::time::Instant::now()));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_throttled_beyond_cap() {
+        let name = "throttled-beyond-cap-open-licenses";
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses");
+                then.status(429).header("retry-after", "3600");
+            })
+            .await;
+        let breaker = breaker();
+
+        let start_time = Instant::now();
+        assert!(fetch_throttled_licenses(&server, name, &breaker)
+            .await
+            .is_none());
+        // Given up at once rather than waiting an hour.
+        assert!(start_time.elapsed() < Duration::from_secs(1));
+        mock.assert_hits_async(1).await;
+        assert_eq!(1, throttled_gauge(name));
+        assert!(!breaker.allow(name, tokio::time::Instant::now()));
+    }
+
+    #[test]
+    fn test_parse_positive() {
+        assert_eq!(
+            4,
+            parse_positive("REFERENCE_DATA_MAX_CONCURRENT_REQUESTS", "4").unwrap()
+        );
+        assert!(parse_positive("REFERENCE_DATA_BREAKER_FAILURES", "0").is_err());
+        assert!(parse_positive("REFERENCE_DATA_BREAKER_COOLDOWN", "-1").is_err());
+        assert!(parse_positive("REFERENCE_DATA_BREAKER_COOLDOWN", "1m").is_err());
+    }
+
+    /// Main and test profiles, served by their own servers. Namespaced after
+    /// the test, as breakers and gauges are process wide.
+    fn profiles(main: &MockServer, test: &MockServer) -> ReferenceDataProfiles {
+        let configs = parse_profiles(&format!(
+            r#"{{"test": {{"baseUrl": "{}", "apiKey": "test-key", "cacheNamespace": "profiles-test"}}}}"#,
+            test.base_url()
+        ))
+        .unwrap();
+        let main =
+            ReferenceDataProvider::new(&main.base_url(), CLIENT.clone(), Some("profiles-main"));
+        ReferenceDataProfiles::new(main, configs).unwrap()
+    }
+
+    async fn serve_licenses<'a>(server: &'a MockServer, licenses: &str) -> httpmock::Mock<'a> {
+        server
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses");
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(licenses);
+            })
+            .await
+    }
+
+    async fn profile_license_codes(provider: &ReferenceDataProvider) -> Vec<String> {
+        provider
+            .open_licenses()
+            .await
+            .unwrap()
+            .into_values()
+            .map(|license| license.code)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_profile_selection() {
+        let main = MockServer::start_async().await;
+        serve_licenses(&main, NLOD).await;
+        let test = MockServer::start_async().await;
+        let with_key = test
+            .mock_async(|when, then| {
+                when.path("/reference-data/open-licenses")
+                    .header("x-api-key", "test-key");
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(CC_BY);
+            })
+            .await;
+        let profiles = profiles(&main, &test);
+
+        assert_eq!(
+            vec!["NLOD20"],
+            profile_license_codes(profiles.get(None)).await
+        );
+        assert_eq!(
+            vec!["NLOD20"],
+            profile_license_codes(profiles.get(Some(MAIN_PROFILE))).await
+        );
+        assert_eq!(
+            vec!["CC BY 4.0"],
+            profile_license_codes(profiles.get(Some("test"))).await
+        );
+        // Requests of a profile are sent with its API key.
+        with_key.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_unknown_profile_falls_back_to_main() {
+        let main = MockServer::start_async().await;
+        let test = MockServer::start_async().await;
+        let profiles = profiles(&main, &test);
+
+        assert!(std::ptr::eq(
+            profiles.get(None),
+            profiles.get(Some("staging"))
+        ));
+        assert!(!std::ptr::eq(
+            profiles.get(None),
+            profiles.get(Some("test"))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_profile_caches_isolated() {
+        let main = MockServer::start_async().await;
+        let main_licenses = serve_licenses(&main, NLOD).await;
+        let test = MockServer::start_async().await;
+        let test_licenses = serve_licenses(&test, CC_BY).await;
+        let profiles = profiles(&main, &test);
+
+        for _ in 0..2 {
+            assert_eq!(
+                vec!["NLOD20"],
+                profile_license_codes(profiles.get(None)).await
+            );
+            assert_eq!(
+                vec!["CC BY 4.0"],
+                profile_license_codes(profiles.get(Some("test"))).await
+            );
         }
+        // Each profile keeps its own collection after fetching it once.
+        main_licenses.assert_hits_async(1).await;
+        test_licenses.assert_hits_async(1).await;
+
+        // Collections on disk, breakers and gauges are named per profile.
+        assert_eq!(
+            "profiles-test.open-licenses",
+            profiles.get(Some("test")).name("open-licenses")
+        );
+        assert_eq!(1, tier_gauge("profiles-main.open-licenses", Tier::Live));
+        assert_eq!(1, tier_gauge("profiles-test.open-licenses", Tier::Live));
+    }
+
+    #[test]
+    fn test_parse_profiles() {
+        let configs = parse_profiles(
+            r#"{"test": {"baseUrl": "https://staging.data.norge.no", "apiKey": "abc"}}"#,
+        )
+        .unwrap();
+        assert_eq!("https://staging.data.norge.no", configs["test"].base_url);
+        assert_eq!("abc", configs["test"].api_key);
+        assert_eq!(None, configs["test"].cache_namespace);
+
+        assert!(parse_profiles(
+            r#"{"test": {"baseUrl": "https://staging.data.norge.no", "url": "x"}}"#
+        )
+        .is_err());
+        assert!(parse_profiles(r#"{"test": {"apiKey": "abc"}}"#).is_err());
+    }
+
+    #[test]
+    fn test_invalid_profiles() {
+        let new = |json: &str| {
+            let main = ReferenceDataProvider::new("https://data.norge.no", CLIENT.clone(), None);
+            ReferenceDataProfiles::new(main, parse_profiles(json).unwrap())
+        };
+
+        assert!(new(r#"{"test": {"baseUrl": "https://staging.data.norge.no"}}"#).is_ok());
+        assert!(new(r#"{"main": {"baseUrl": "https://staging.data.norge.no"}}"#).is_err());
+        assert!(new(r#"{"../test": {"baseUrl": "https://staging.data.norge.no"}}"#).is_err());
+        assert!(new(r#"{
+                "test": {"baseUrl": "https://staging.data.norge.no"},
+                "demo": {"baseUrl": "https://demo.data.norge.no", "cacheNamespace": "test"}
+            }"#)
+        .is_err());
     }
 }
@@ -1,7 +1,14 @@
+use apache_avro::{schema_compatibility::SchemaCompatibility, Schema};
 use schema_registry_converter::{
-    async_impl::schema_registry::{post_schema, SrSettings},
-    schema_registry_common::{SchemaType, SuppliedSchema},
+    async_impl::schema_registry::{get_schema_by_subject, post_schema, SrSettings},
+    schema_registry_common::{SchemaType, SubjectNameStrategy, SuppliedSchema},
 };
+use std::{
+    collections::BTreeMap,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
 use serde_derive::{Deserialize, Serialize};
 
 use crate::error::Error;
@@ -11,14 +18,43 @@ pub enum InputEvent {
     Unknown { namespace: String, name: String },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DatasetEventType {
     #[serde(rename = "DATASET_HARVESTED")]
     DatasetHarvested,
+    /// Harvested dataset enriched by reasoning, with the fullest graph.
+    #[serde(rename = "DATASET_REASONED")]
+    DatasetReasoned,
     #[serde(other)]
     Unknown,
 }
 
+impl DatasetEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DatasetEventType::DatasetHarvested => "DATASET_HARVESTED",
+            DatasetEventType::DatasetReasoned => "DATASET_REASONED",
+            DatasetEventType::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+impl FromStr for DatasetEventType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "DATASET_HARVESTED" => Ok(DatasetEventType::DatasetHarvested),
+            "DATASET_REASONED" => Ok(DatasetEventType::DatasetReasoned),
+            _ => Err(format!(
+                "invalid dataset event type '{}', expected 'DATASET_HARVESTED' or 'DATASET_REASONED'",
+                s
+            )
+            .into()),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DatasetEvent {
     #[serde(rename = "type")]
@@ -26,10 +62,27 @@ pub struct DatasetEvent {
     #[serde(rename = "fdkId")]
     pub fdk_id: String,
     pub graph: String,
+    #[serde(default = "current_timestamp")]
     pub timestamp: i64,
+    /// MQA graph of the previous assessment of the dataset, when provided
+    /// upstream, to find the assessments that are no longer made.
+    #[serde(rename = "previousGraph", default)]
+    pub previous_graph: Option<String>,
+    /// IRI relative IRIs of the graph are resolved against, when the graph
+    /// has any and they are not resolved upstream.
+    #[serde(rename = "baseIri", default)]
+    pub base_iri: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Milliseconds since the unix epoch.
+pub fn current_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MqaEvent {
     #[serde(rename = "type")]
     pub event_type: MQAEventType,
@@ -37,55 +90,223 @@ pub struct MqaEvent {
     pub fdk_id: String,
     pub graph: String,
     pub timestamp: i64,
+    /// Timestamp of the dataset event assessed.
+    #[serde(rename = "sourceTimestamp", default)]
+    pub source_timestamp: Option<i64>,
+    /// Canonical hash of the graph as assessed, after duplicate dataset
+    /// nodes are merged.
+    #[serde(rename = "graphHash", default)]
+    pub graph_hash: Option<String>,
+    #[serde(rename = "metricsRevision", default)]
+    pub metrics_revision: Option<u32>,
+    /// Encoding of a graph too large to be sent as is, `gzip+base64`, or none
+    /// for plain RDF.
+    #[serde(rename = "graphEncoding", default)]
+    pub graph_encoding: Option<String>,
+    /// IRIs of assessments of the previous graph that are not in this one,
+    /// or none when the previous graph is not known.
+    #[serde(rename = "removedAssessments", default)]
+    pub removed_assessments: Option<Vec<String>>,
+    /// Number of quads of the graph assessed.
+    #[serde(rename = "inputQuads", default)]
+    pub input_quads: Option<i32>,
+    /// Number of quads of the MQA graph.
+    #[serde(rename = "outputQuads", default)]
+    pub output_quads: Option<i32>,
 }
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MQAEventType {
     #[serde(rename = "PROPERTIES_CHECKED")]
     PropertiesChecked,
 }
 
-pub async fn setup_schemas(sr_settings: &SrSettings) -> Result<(), Error> {
-    register_schema(
-        sr_settings,
-        "no.fdk.mqa.MQAEvent",
-        r#"{
-            "name": "MQAEvent",
-            "namespace": "no.fdk.mqa",
-            "type": "record",
-            "fields": [
-                {
-                    "name": "type", 
-                    "type": {
-                        "type": "enum",
-                        "name": "MQAEventType",
-                        "symbols": [
-                            "URLS_CHECKED", 
-                            "PROPERTIES_CHECKED", 
-                            "DCAT_COMPLIANCE_CHECKED", 
-                            "SCORE_CALCULATED"
-                        ]
-                    }
-                },
-                {"name": "fdkId", "type": "string"},
-                {"name": "graph", "type": "string"},
-                {"name": "timestamp", "type": "long", "logicalType": "timestamp-millis"}
-            ]
-        }"#,
-    )
-    .await?;
+pub const MQA_EVENT_SCHEMA: &str = r#"{
+    "name": "MQAEvent",
+    "namespace": "no.fdk.mqa",
+    "type": "record",
+    "fields": [
+        {
+            "name": "type", 
+            "type": {
+                "type": "enum",
+                "name": "MQAEventType",
+                "symbols": [
+                    "URLS_CHECKED", 
+                    "PROPERTIES_CHECKED", 
+                    "DCAT_COMPLIANCE_CHECKED", 
+                    "SCORE_CALCULATED"
+                ]
+            }
+        },
+        {"name": "fdkId", "type": "string"},
+        {"name": "graph", "type": "string"},
+        {"name": "timestamp", "type": "long", "logicalType": "timestamp-millis"},
+        {"name": "graphHash", "type": ["null", "string"], "default": null},
+        {"name": "metricsRevision", "type": ["null", "int"], "default": null},
+        {"name": "graphEncoding", "type": ["null", "string"], "default": null},
+        {"name": "sourceTimestamp", "type": ["null", "long"], "default": null},
+        {
+            "name": "removedAssessments",
+            "type": ["null", {"type": "array", "items": "string"}],
+            "default": null
+        },
+        {"name": "inputQuads", "type": ["null", "int"], "default": null},
+        {"name": "outputQuads", "type": ["null", "int"], "default": null}
+    ]
+}"#;
+
+/// Full name of the MQA event record.
+pub const MQA_EVENT_RECORD_NAME: &str = "no.fdk.mqa.MQAEvent";
+
+/// How the subject the MQA event schema is registered under is named, like
+/// the subject name strategies of the Confluent serializers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SubjectNaming {
+    /// `<topic>-value`
+    TopicName,
+    /// `no.fdk.mqa.MQAEvent`
+    #[default]
+    RecordName,
+    /// `<topic>-no.fdk.mqa.MQAEvent`
+    TopicRecordName,
+}
+
+impl FromStr for SubjectNaming {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "topic-name" => Ok(SubjectNaming::TopicName),
+            "record-name" => Ok(SubjectNaming::RecordName),
+            "topic-record-name" => Ok(SubjectNaming::TopicRecordName),
+            _ => Err(format!(
+                "invalid subject name strategy '{}', expected 'topic-name', 'record-name' or 'topic-record-name'",
+                s
+            )
+            .into()),
+        }
+    }
+}
+
+impl SubjectNaming {
+    /// Strategy of the encoder of MQA events produced to a topic.
+    pub fn strategy(&self, topic: &str) -> SubjectNameStrategy {
+        match self {
+            SubjectNaming::TopicName => {
+                SubjectNameStrategy::TopicNameStrategy(topic.to_string(), false)
+            }
+            SubjectNaming::RecordName => {
+                SubjectNameStrategy::RecordNameStrategy(MQA_EVENT_RECORD_NAME.to_string())
+            }
+            SubjectNaming::TopicRecordName => SubjectNameStrategy::TopicRecordNameStrategy(
+                topic.to_string(),
+                MQA_EVENT_RECORD_NAME.to_string(),
+            ),
+        }
+    }
+
+    /// Subject of the MQA event schema of a topic.
+    pub fn subject(&self, topic: &str) -> String {
+        match self {
+            SubjectNaming::TopicName => format!("{}-value", topic),
+            SubjectNaming::RecordName => MQA_EVENT_RECORD_NAME.to_string(),
+            SubjectNaming::TopicRecordName => format!("{}-{}", topic, MQA_EVENT_RECORD_NAME),
+        }
+    }
+}
+
+/// Registers the MQA event schema under the subject of each of the topics
+/// events are produced to, when `register` is set, and checks that the
+/// encoder resolves them. A registration failing while the subject already
+/// has the schema, like when the registry is read-only, is not an error.
+/// When not registering, the schema of each subject must instead be
+/// compatible with the MQA event schema, reading the events it writes.
+pub async fn setup_schemas(
+    sr_settings: &SrSettings,
+    naming: SubjectNaming,
+    topics: &[&str],
+    register: bool,
+) -> Result<(), Error> {
+    if register {
+        let mut subjects = BTreeMap::new();
+        for topic in topics {
+            subjects.entry(naming.subject(topic)).or_insert(*topic);
+        }
+        for (subject, topic) in subjects {
+            let Err(e) = register_schema(
+                sr_settings,
+                &subject,
+                MQA_EVENT_RECORD_NAME,
+                MQA_EVENT_SCHEMA,
+            )
+            .await
+            else {
+                continue;
+            };
+            match get_schema_by_subject(sr_settings, &naming.strategy(topic)).await {
+                Ok(registered) if is_mqa_event_schema(&registered.schema) => {
+                    tracing::info!(subject, "schema already registered");
+                }
+                _ => return Err(e),
+            }
+        }
+    }
+
+    for topic in topics {
+        let subject = naming.subject(topic);
+        let registered = get_schema_by_subject(sr_settings, &naming.strategy(topic))
+            .await
+            .map_err(|e| {
+                format!(
+                    "subject '{}' of topic '{}' does not resolve: {}",
+                    subject, topic, e
+                )
+            })?;
+        if !register {
+            check_compatibility(&subject, &registered.schema)?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether a schema is the MQA event schema, in parsing canonical form.
+fn is_mqa_event_schema(schema: &str) -> bool {
+    match (
+        Schema::parse_str(schema),
+        Schema::parse_str(MQA_EVENT_SCHEMA),
+    ) {
+        (Ok(schema), Ok(mqa_event_schema)) => schema == mqa_event_schema,
+        _ => false,
+    }
+}
+
+/// Checks that the schema registered under a subject reads MQA events written
+/// with the MQA event schema.
+fn check_compatibility(subject: &str, registered: &str) -> Result<(), Error> {
+    let registered = Schema::parse_str(registered)
+        .map_err(|e| format!("invalid schema of subject '{}': {}", subject, e))?;
+    SchemaCompatibility::can_read(&Schema::parse_str(MQA_EVENT_SCHEMA)?, &registered).map_err(
+        |e| {
+            format!(
+                "schema of subject '{}' is not compatible with the MQA event schema: {}",
+                subject, e
+            )
+        },
+    )?;
     Ok(())
 }
 
 pub async fn register_schema(
     sr_settings: &SrSettings,
+    subject: &str,
     name: &str,
     schema_str: &str,
 ) -> Result<(), Error> {
-    tracing::info!(name, "registering schema");
+    tracing::info!(subject, name, "registering schema");
 
     let schema = post_schema(
         sr_settings,
-        name.to_string(),
+        subject.to_string(),
         SuppliedSchema {
             name: Some(name.to_string()),
             schema_type: SchemaType::Avro,
@@ -95,6 +316,526 @@ pub async fn register_schema(
     )
     .await?;
 
-    tracing::info!(id = schema.id, name, "schema succesfully registered");
+    tracing::info!(
+        id = schema.id,
+        subject,
+        name,
+        "schema succesfully registered"
+    );
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use apache_avro::{from_avro_datum, from_value, to_avro_datum, to_value, types::Value, Schema};
+    use httpmock::{
+        Method::{GET, POST},
+        MockServer,
+    };
+
+    fn dataset_event_schema() -> Schema {
+        Schema::parse_str(include_str!(
+            "../kafka/schemas/no.fdk.mqa.DatasetEvent.json"
+        ))
+        .unwrap()
+    }
+
+    fn mqa_event_schema() -> Schema {
+        Schema::parse_str(MQA_EVENT_SCHEMA).unwrap()
+    }
+
+    /// Serializes a struct the same way the schema registry encoder does and
+    /// reads it back as an avro value.
+    fn round_trip<T: serde::Serialize>(
+        schema: &Schema,
+        item: T,
+    ) -> Result<Value, apache_avro::Error> {
+        let value = to_value(item)?.resolve(schema)?;
+        let encoded = to_avro_datum(schema, value)?;
+        from_avro_datum(schema, &mut encoded.as_slice(), None)
+    }
+
+    #[test]
+    fn test_mqa_event_schema_file_in_sync() {
+        let file: serde_json::Value =
+            serde_json::from_str(include_str!("../kafka/schemas/no.fdk.mqa.MQAEvent.json"))
+                .unwrap();
+        let schema: serde_json::Value = serde_json::from_str(MQA_EVENT_SCHEMA).unwrap();
+        assert_eq!(file, schema);
+    }
+
+    #[test]
+    fn test_dataset_event_round_trip() {
+        let event = DatasetEvent {
+            event_type: DatasetEventType::DatasetHarvested,
+            fdk_id: "fdk-id".to_string(),
+            graph: "<a> <b> <c> .".to_string(),
+            timestamp: 1647698566000,
+            previous_graph: Some("<d> <e> <f> .".to_string()),
+            base_iri: Some("https://data.foo/".to_string()),
+        };
+
+        let value = round_trip(&dataset_event_schema(), event).unwrap();
+        let decoded = from_value::<DatasetEvent>(&value).unwrap();
+
+        assert!(matches!(
+            decoded.event_type,
+            DatasetEventType::DatasetHarvested
+        ));
+        assert_eq!(decoded.fdk_id, "fdk-id");
+        assert_eq!(decoded.graph, "<a> <b> <c> .");
+        assert_eq!(decoded.timestamp, 1647698566000);
+        assert_eq!(decoded.previous_graph, Some("<d> <e> <f> .".to_string()));
+        assert_eq!(decoded.base_iri, Some("https://data.foo/".to_string()));
+    }
+
+    #[test]
+    fn test_reasoned_dataset_event_round_trip() {
+        let event = DatasetEvent {
+            event_type: DatasetEventType::DatasetReasoned,
+            fdk_id: "fdk-id".to_string(),
+            graph: "<a> <b> <c> .".to_string(),
+            timestamp: 1647698566000,
+            previous_graph: None,
+            base_iri: None,
+        };
+
+        let value = round_trip(&dataset_event_schema(), event).unwrap();
+        let decoded = from_value::<DatasetEvent>(&value).unwrap();
+
+        assert_eq!(DatasetEventType::DatasetReasoned, decoded.event_type);
+    }
+
+    #[test]
+    fn test_dataset_event_type_names() {
+        let event = serde_json::from_str::<DatasetEvent>(
+            r#"{"type":"DATASET_REASONED","fdkId":"fdk-id","graph":"","timestamp":0}"#,
+        )
+        .unwrap();
+        assert_eq!(DatasetEventType::DatasetReasoned, event.event_type);
+
+        for event_type in [
+            DatasetEventType::DatasetHarvested,
+            DatasetEventType::DatasetReasoned,
+        ] {
+            assert_eq!(event_type, event_type.as_str().parse().unwrap());
+        }
+        assert!("UNKNOWN".parse::<DatasetEventType>().is_err());
+        assert!("dataset_reasoned".parse::<DatasetEventType>().is_err());
+    }
+
+    #[test]
+    fn test_unknown_dataset_event_type_is_not_serializable() {
+        let event = DatasetEvent {
+            event_type: DatasetEventType::Unknown,
+            fdk_id: "fdk-id".to_string(),
+            graph: "".to_string(),
+            timestamp: 0,
+            previous_graph: None,
+            base_iri: None,
+        };
+
+        assert!(round_trip(&dataset_event_schema(), event).is_err());
+    }
+
+    #[test]
+    fn test_unrecognized_dataset_event_symbol_decodes_as_unknown() {
+        let value = Value::Record(vec![
+            (
+                "type".to_string(),
+                Value::Enum(1, "DATASET_REMOVED".to_string()),
+            ),
+            ("fdkId".to_string(), Value::String("fdk-id".to_string())),
+            ("graph".to_string(), Value::String("".to_string())),
+            ("timestamp".to_string(), Value::Long(0)),
+        ]);
+
+        let decoded = from_value::<DatasetEvent>(&value).unwrap();
+        assert!(matches!(decoded.event_type, DatasetEventType::Unknown));
+        // Events of writers without the previous graph and base IRI.
+        assert_eq!(None, decoded.previous_graph);
+        assert_eq!(None, decoded.base_iri);
+    }
+
+    #[test]
+    fn test_mqa_event_round_trip() {
+        let event = MqaEvent {
+            event_type: MQAEventType::PropertiesChecked,
+            fdk_id: "fdk-id".to_string(),
+            graph: "<a> <b> <c> .".to_string(),
+            timestamp: 1647698566000,
+            source_timestamp: Some(1647698565000),
+            graph_hash: Some("abc".to_string()),
+            metrics_revision: Some(7),
+            graph_encoding: Some("gzip+base64".to_string()),
+            removed_assessments: Some(vec!["https://distribution.assessment.foo".to_string()]),
+            input_quads: Some(42),
+            output_quads: Some(137),
+        };
+
+        let value = round_trip(&mqa_event_schema(), event).unwrap();
+        match &value {
+            Value::Record(fields) => assert_eq!(
+                fields[0],
+                (
+                    "type".to_string(),
+                    Value::Enum(1, "PROPERTIES_CHECKED".to_string())
+                )
+            ),
+            _ => panic!("MQA event is not a record"),
+        }
+
+        let decoded = from_value::<MqaEvent>(&value).unwrap();
+        assert!(matches!(
+            decoded.event_type,
+            MQAEventType::PropertiesChecked
+        ));
+        assert_eq!(decoded.fdk_id, "fdk-id");
+        assert_eq!(decoded.graph, "<a> <b> <c> .");
+        assert_eq!(decoded.timestamp, 1647698566000);
+        assert_eq!(decoded.graph_hash, Some("abc".to_string()));
+        assert_eq!(decoded.metrics_revision, Some(7));
+        assert_eq!(decoded.graph_encoding, Some("gzip+base64".to_string()));
+        assert_eq!(decoded.source_timestamp, Some(1647698565000));
+        assert_eq!(
+            decoded.removed_assessments,
+            Some(vec!["https://distribution.assessment.foo".to_string()])
+        );
+        assert_eq!(decoded.input_quads, Some(42));
+        assert_eq!(decoded.output_quads, Some(137));
+    }
+
+    #[test]
+    fn test_mqa_event_without_graph_hash() {
+        let event = MqaEvent {
+            event_type: MQAEventType::PropertiesChecked,
+            fdk_id: "fdk-id".to_string(),
+            graph: "".to_string(),
+            timestamp: 1647698566000,
+            source_timestamp: None,
+            graph_hash: None,
+            metrics_revision: None,
+            graph_encoding: None,
+            removed_assessments: None,
+            input_quads: None,
+            output_quads: None,
+        };
+
+        let value = round_trip(&mqa_event_schema(), event).unwrap();
+        let decoded = from_value::<MqaEvent>(&value).unwrap();
+        assert_eq!(decoded.graph_hash, None);
+        assert_eq!(decoded.metrics_revision, None);
+        assert_eq!(decoded.graph_encoding, None);
+        assert_eq!(decoded.source_timestamp, None);
+        assert_eq!(decoded.removed_assessments, None);
+        assert_eq!(decoded.input_quads, None);
+        assert_eq!(decoded.output_quads, None);
+    }
+
+    #[test]
+    fn test_mqa_event_symbol_mismatch() {
+        let value = Value::Record(vec![
+            (
+                "type".to_string(),
+                Value::Enum(0, "URLS_CHECKED".to_string()),
+            ),
+            ("fdkId".to_string(), Value::String("fdk-id".to_string())),
+            ("graph".to_string(), Value::String("".to_string())),
+            ("timestamp".to_string(), Value::Long(0)),
+        ]);
+
+        assert!(from_value::<MqaEvent>(&value).is_err());
+    }
+
+    #[test]
+    fn test_dataset_event_json_round_trip() {
+        let event = DatasetEvent {
+            event_type: DatasetEventType::DatasetHarvested,
+            fdk_id: "fdk-id".to_string(),
+            graph: "<a> <b> <c> .".to_string(),
+            timestamp: 1647698566000,
+            previous_graph: None,
+            base_iri: None,
+        };
+
+        let json = serde_json::to_string(&event).unwrap();
+        let decoded = serde_json::from_str::<DatasetEvent>(&json).unwrap();
+
+        assert!(matches!(
+            decoded.event_type,
+            DatasetEventType::DatasetHarvested
+        ));
+        assert_eq!(decoded.fdk_id, "fdk-id");
+        assert_eq!(decoded.graph, "<a> <b> <c> .");
+        assert_eq!(decoded.timestamp, 1647698566000);
+    }
+
+    #[test]
+    fn test_dataset_event_json_defaults() {
+        let decoded = serde_json::from_str::<DatasetEvent>(
+            r#"{"type":"DATASET_HARVESTED","fdkId":"fdk-id","graph":"","harvestRunId":"foo"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(decoded.fdk_id, "fdk-id");
+        assert!(decoded.timestamp > 0);
+    }
+
+    #[test]
+    fn test_mqa_event_json_round_trip() {
+        let event = MqaEvent {
+            event_type: MQAEventType::PropertiesChecked,
+            fdk_id: "fdk-id".to_string(),
+            graph: "<a> <b> <c> .".to_string(),
+            timestamp: 1647698566000,
+            source_timestamp: Some(1647698565000),
+            graph_hash: None,
+            metrics_revision: Some(1),
+            graph_encoding: None,
+            removed_assessments: None,
+            input_quads: None,
+            output_quads: None,
+        };
+
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains(r#""type":"PROPERTIES_CHECKED""#));
+
+        let decoded = serde_json::from_str::<MqaEvent>(&json).unwrap();
+        assert!(matches!(
+            decoded.event_type,
+            MQAEventType::PropertiesChecked
+        ));
+        assert_eq!(decoded.fdk_id, "fdk-id");
+        assert_eq!(decoded.timestamp, 1647698566000);
+        assert_eq!(decoded.metrics_revision, Some(1));
+    }
+
+    #[test]
+    fn test_subject_naming() {
+        for (value, naming, subject) in [
+            ("topic-name", SubjectNaming::TopicName, "mqa-events-value"),
+            (
+                "record-name",
+                SubjectNaming::RecordName,
+                "no.fdk.mqa.MQAEvent",
+            ),
+            (
+                "topic-record-name",
+                SubjectNaming::TopicRecordName,
+                "mqa-events-no.fdk.mqa.MQAEvent",
+            ),
+        ] {
+            assert_eq!(naming, value.parse().unwrap());
+            assert_eq!(subject, naming.subject("mqa-events"));
+            assert_eq!(
+                subject,
+                naming.strategy("mqa-events").get_subject().unwrap()
+            );
+        }
+        assert!("TopicNameStrategy".parse::<SubjectNaming>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_setup_schemas_with_each_subject_naming() {
+        let topics = ["mqa-events", "mqa-events-nav"];
+        for (naming, subjects) in [
+            (
+                SubjectNaming::TopicName,
+                vec!["mqa-events-value", "mqa-events-nav-value"],
+            ),
+            (SubjectNaming::RecordName, vec!["no.fdk.mqa.MQAEvent"]),
+            (
+                SubjectNaming::TopicRecordName,
+                vec![
+                    "mqa-events-no.fdk.mqa.MQAEvent",
+                    "mqa-events-nav-no.fdk.mqa.MQAEvent",
+                ],
+            ),
+        ] {
+            let server = MockServer::start_async().await;
+            let mut registrations = Vec::new();
+            for subject in &subjects {
+                registrations.push(
+                    server
+                        .mock_async(|when, then| {
+                            when.method(POST)
+                                .path(format!("/subjects/{}/versions", subject));
+                            then.status(200)
+                                .header("content-type", "application/json")
+                                .body(r#"{"id":1}"#);
+                        })
+                        .await,
+                );
+                server
+                    .mock_async(|when, then| {
+                        when.method(GET)
+                            .path(format!("/subjects/{}/versions/latest", subject));
+                        then.status(200)
+                            .header("content-type", "application/json")
+                            .json_body(serde_json::json!({
+                                "subject": subject,
+                                "version": 1,
+                                "id": 1,
+                                "schema": MQA_EVENT_SCHEMA,
+                            }));
+                    })
+                    .await;
+            }
+
+            setup_schemas(&SrSettings::new(server.base_url()), naming, &topics, true)
+                .await
+                .unwrap();
+            for registration in registrations {
+                registration.assert_async().await;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_setup_schemas_fails_when_subject_does_not_resolve() {
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.method(POST);
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(r#"{"id":1}"#);
+            })
+            .await;
+        server
+            .mock_async(|when, then| {
+                when.method(GET);
+                then.status(404)
+                    .header("content-type", "application/json")
+                    .body(r#"{"error_code":40401,"message":"Subject not found."}"#);
+            })
+            .await;
+
+        let error = setup_schemas(
+            &SrSettings::new(server.base_url()),
+            SubjectNaming::TopicName,
+            &["mqa-events"],
+            true,
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("subject 'mqa-events-value'"), "{}", error);
+    }
+
+    /// Mocks a registry rejecting registrations with 409, with the given
+    /// schema registered under the subject of the `mqa-events` topic, if any,
+    /// returning the mock of the registrations.
+    async fn mock_read_only_registry<'a>(
+        server: &'a MockServer,
+        registered: Option<&str>,
+    ) -> httpmock::Mock<'a> {
+        match registered {
+            Some(schema) => {
+                server
+                    .mock_async(|when, then| {
+                        when.method(GET)
+                            .path("/subjects/mqa-events-value/versions/latest");
+                        then.status(200)
+                            .header("content-type", "application/json")
+                            .json_body(serde_json::json!({
+                                "subject": "mqa-events-value",
+                                "version": 1,
+                                "id": 1,
+                                "schema": schema,
+                            }));
+                    })
+                    .await;
+            }
+            None => {
+                server
+                    .mock_async(|when, then| {
+                        when.method(GET);
+                        then.status(404)
+                            .header("content-type", "application/json")
+                            .body(r#"{"error_code":40401,"message":"Subject not found."}"#);
+                    })
+                    .await;
+            }
+        }
+        server
+            .mock_async(|when, then| {
+                when.method(POST);
+                then.status(409)
+                    .header("content-type", "application/json")
+                    .body(r#"{"error_code":409,"message":"Conflict"}"#);
+            })
+            .await
+    }
+
+    async fn setup_mqa_events_schema(server: &MockServer, register: bool) -> Result<(), Error> {
+        setup_schemas(
+            &SrSettings::new(server.base_url()),
+            SubjectNaming::TopicName,
+            &["mqa-events"],
+            register,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_registration_conflict_with_schema_registered() {
+        let server = MockServer::start_async().await;
+        let registrations = mock_read_only_registry(&server, Some(MQA_EVENT_SCHEMA)).await;
+
+        setup_mqa_events_schema(&server, true).await.unwrap();
+        registrations.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_registration_conflict_without_subject() {
+        let server = MockServer::start_async().await;
+        let registrations = mock_read_only_registry(&server, None).await;
+
+        assert!(setup_mqa_events_schema(&server, true).await.is_err());
+        registrations.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_registered_schema_checked_without_registering() {
+        let server = MockServer::start_async().await;
+        let registrations = mock_read_only_registry(&server, Some(MQA_EVENT_SCHEMA)).await;
+
+        setup_mqa_events_schema(&server, false).await.unwrap();
+        registrations.assert_hits_async(0).await;
+
+        // A schema requiring a field MQA events do not have.
+        let server = MockServer::start_async().await;
+        mock_read_only_registry(
+            &server,
+            Some(
+                r#"{
+                    "name": "MQAEvent",
+                    "namespace": "no.fdk.mqa",
+                    "type": "record",
+                    "fields": [{"name": "assessor", "type": "string"}]
+                }"#,
+            ),
+        )
+        .await;
+        let error = setup_mqa_events_schema(&server, false)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("not compatible"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_missing_subject_without_registering() {
+        let server = MockServer::start_async().await;
+        let registrations = mock_read_only_registry(&server, None).await;
+
+        let error = setup_mqa_events_schema(&server, false)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("subject 'mqa-events-value'"), "{}", error);
+        registrations.assert_hits_async(0).await;
+    }
+}
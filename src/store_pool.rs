@@ -0,0 +1,167 @@
+use std::env;
+
+use lazy_static::lazy_static;
+use oxigraph::store::Store;
+
+use crate::{
+    error::Error,
+    prometheus_metrics::{LAST_GRAPH_QUADS, STORE_RECYCLES},
+};
+
+lazy_static! {
+    pub static ref STORE_RECYCLE_INTERVAL: Option<usize> = env::var("STORE_RECYCLE_INTERVAL")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|interval| *interval > 0);
+    pub static ref GRAPH_QUADS_WARNING_THRESHOLD: usize = env::var("GRAPH_QUADS_WARNING_THRESHOLD")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(100000);
+}
+
+/// Rough memory use of a quad in an in-memory store, including its indexes.
+const APPROX_BYTES_PER_QUAD: usize = 256;
+
+/// Input and output stores of a worker, reused for every message. The stores
+/// are emptied after each message, and recreated after every
+/// `recycle_interval` messages to release memory held by their allocations.
+pub struct StorePool {
+    worker_id: usize,
+    recycle_interval: Option<usize>,
+    quads_warning_threshold: usize,
+    input: Store,
+    output: Store,
+    messages: usize,
+}
+
+impl StorePool {
+    pub fn new(
+        worker_id: usize,
+        recycle_interval: Option<usize>,
+        quads_warning_threshold: usize,
+    ) -> Result<Self, Error> {
+        Ok(StorePool {
+            worker_id,
+            recycle_interval,
+            quads_warning_threshold,
+            input: Store::new()?,
+            output: Store::new()?,
+            messages: 0,
+        })
+    }
+
+    pub fn input(&self) -> &Store {
+        &self.input
+    }
+
+    pub fn output(&self) -> &Store {
+        &self.output
+    }
+
+    /// Records the size of the graph of the last message, then empties the
+    /// stores, recreating them if due.
+    pub fn release(&mut self) -> Result<(), Error> {
+        let worker_id = self.worker_id;
+        let quads = self.input.len()?;
+        let approx_bytes = (quads + self.output.len()?) * APPROX_BYTES_PER_QUAD;
+        LAST_GRAPH_QUADS
+            .with_label_values(&[&worker_id.to_string()])
+            .set(quads as i64);
+        if quads > self.quads_warning_threshold {
+            tracing::warn!(
+                worker_id,
+                quads,
+                approx_bytes,
+                threshold = self.quads_warning_threshold,
+                "graph exceeds quad threshold"
+            );
+        } else {
+            tracing::debug!(worker_id, quads, approx_bytes, "graph assessed");
+        }
+
+        self.input.clear()?;
+        self.output.clear()?;
+        self.messages += 1;
+
+        if self
+            .recycle_interval
+            .is_some_and(|interval| self.messages % interval == 0)
+        {
+            self.input = Store::new()?;
+            self.output = Store::new()?;
+            STORE_RECYCLES
+                .with_label_values(&[&worker_id.to_string()])
+                .inc();
+            tracing::info!(worker_id, messages = self.messages, "stores recycled");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        metrics::{parse_rdf_graph_and_calculate_metrics, AssessmentOptions},
+        post_processing::SourceContext,
+    };
+
+    fn graph(i: usize) -> String {
+        format!(
+            r#"
+            @prefix dcat: <http://www.w3.org/ns/dcat#> .
+            @prefix dct: <http://purl.org/dc/terms/> .
+            @prefix dcatnomqa: <https://data.norge.no/vocabulary/dcatno-mqa#> .
+
+            <https://dataset.foo/{i}> a dcat:Dataset ;
+                dcatnomqa:hasAssessment <https://dataset.assessment.foo/{i}> ;
+                dct:title "Dataset {i}" ;
+                dct:issued "2024-01-01" .
+            "#
+        )
+    }
+
+    #[tokio::test]
+    async fn test_stores_empty_between_messages() {
+        let mut pool = StorePool::new(200, Some(100), 100000).unwrap();
+        let recycles = || STORE_RECYCLES.with_label_values(&["200"]).get();
+        let recycles_before = recycles();
+
+        for i in 0..1000 {
+            parse_rdf_graph_and_calculate_metrics(
+                pool.input(),
+                pool.output(),
+                graph(i),
+                None,
+                &SourceContext::default(),
+                &AssessmentOptions::default(),
+            )
+            .await
+            .unwrap();
+            assert!(pool.input().len().unwrap() > 0);
+            assert!(pool.output().len().unwrap() > 0);
+
+            pool.release().unwrap();
+            assert_eq!(0, pool.input().len().unwrap());
+            assert_eq!(0, pool.output().len().unwrap());
+        }
+
+        assert_eq!(recycles_before + 10, recycles());
+        assert_eq!(
+            4,
+            LAST_GRAPH_QUADS.with_label_values(&["200"]).get(),
+            "quads of the last graph"
+        );
+    }
+
+    #[test]
+    fn test_stores_not_recycled_without_interval() {
+        let mut pool = StorePool::new(201, None, 100000).unwrap();
+
+        for _ in 0..10 {
+            pool.release().unwrap();
+        }
+
+        assert_eq!(0, STORE_RECYCLES.with_label_values(&["201"]).get());
+    }
+}
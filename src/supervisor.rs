@@ -0,0 +1,198 @@
+use std::{
+    collections::VecDeque,
+    env,
+    future::Future,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+
+use crate::{error::Error, prometheus_metrics::WORKER_RESTARTS};
+
+lazy_static! {
+    pub static ref RESTART_POLICY: RestartPolicy = RestartPolicy {
+        on_failure: env::var("ON_WORKER_FAILURE")
+            .map(|v| {
+                v.parse().unwrap_or_else(|e: Error| {
+                    tracing::error!(error = e.to_string(), "invalid worker failure policy");
+                    std::process::exit(1);
+                })
+            })
+            .unwrap_or(OnWorkerFailure::Restart),
+        backoff: Duration::from_millis(
+            env::var("WORKER_RESTART_BACKOFF_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1000),
+        ),
+        max_restarts: env::var("WORKER_MAX_RESTARTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(5),
+        window: Duration::from_secs(60),
+    };
+}
+
+/// What to do when a worker fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnWorkerFailure {
+    Exit,
+    Restart,
+}
+
+impl FromStr for OnWorkerFailure {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "exit" => Ok(OnWorkerFailure::Exit),
+            "restart" => Ok(OnWorkerFailure::Restart),
+            _ => Err(format!("unknown worker failure policy '{}'", s).into()),
+        }
+    }
+}
+
+/// How failed workers are restarted. At most `max_restarts` restarts are
+/// allowed within `window`, each after waiting `backoff`.
+#[derive(Debug, Clone)]
+pub struct RestartPolicy {
+    pub on_failure: OnWorkerFailure,
+    pub backoff: Duration,
+    pub max_restarts: usize,
+    pub window: Duration,
+}
+
+/// Runs a worker as a task, restarting it according to the policy when it
+/// fails or panics. Returns an error once the worker is not to be restarted.
+pub async fn supervise<F, Fut>(
+    worker_id: usize,
+    policy: &RestartPolicy,
+    worker: F,
+) -> Result<(), Error>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<(), Error>> + Send + 'static,
+{
+    let mut restarts: VecDeque<Instant> = VecDeque::new();
+    loop {
+        let error = match tokio::spawn(worker()).await {
+            Ok(Ok(())) => {
+                tracing::info!(worker_id, "worker stopped");
+                return Ok(());
+            }
+            Ok(Err(e)) => e.to_string(),
+            Err(e) => e.to_string(),
+        };
+        tracing::error!(worker_id, error, "worker failed");
+
+        if policy.on_failure == OnWorkerFailure::Exit {
+            return Err(format!("worker {} failed: {}", worker_id, error).into());
+        }
+
+        while restarts
+            .front()
+            .is_some_and(|restart| restart.elapsed() > policy.window)
+        {
+            restarts.pop_front();
+        }
+        if restarts.len() >= policy.max_restarts {
+            return Err(format!(
+                "worker {} failed {} times within {:?}: {}",
+                worker_id,
+                restarts.len() + 1,
+                policy.window,
+                error
+            )
+            .into());
+        }
+
+        tokio::time::sleep(policy.backoff).await;
+        restarts.push_back(Instant::now());
+        WORKER_RESTARTS
+            .with_label_values(&[&worker_id.to_string()])
+            .inc();
+        tracing::warn!(worker_id, restarts = restarts.len(), "restarting worker");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    fn policy(on_failure: OnWorkerFailure) -> RestartPolicy {
+        RestartPolicy {
+            on_failure,
+            backoff: Duration::from_millis(1),
+            max_restarts: 3,
+            window: Duration::from_secs(60),
+        }
+    }
+
+    #[test]
+    fn test_parse_on_worker_failure() {
+        assert_eq!(OnWorkerFailure::Exit, "exit".parse().unwrap());
+        assert_eq!(OnWorkerFailure::Restart, "restart".parse().unwrap());
+        assert!("ignore".parse::<OnWorkerFailure>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failing_worker_restarted() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let restarts = || WORKER_RESTARTS.with_label_values(&["100"]).get();
+        let restarts_before = restarts();
+
+        let result = supervise(100, &policy(OnWorkerFailure::Restart), || {
+            let runs = runs.clone();
+            async move {
+                runs.fetch_add(1, Ordering::SeqCst);
+                Err(Error::from("broker transport failure"))
+            }
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(4, runs.load(Ordering::SeqCst));
+        assert_eq!(restarts_before + 3, restarts());
+    }
+
+    #[tokio::test]
+    async fn test_panicking_worker_restarted() {
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let result = supervise(101, &policy(OnWorkerFailure::Restart), || {
+            let runs = runs.clone();
+            async move {
+                if runs.fetch_add(1, Ordering::SeqCst) < 2 {
+                    panic!("worker panicked");
+                }
+                Ok(())
+            }
+        })
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(3, runs.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_failing_worker_exits() {
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let result = supervise(102, &policy(OnWorkerFailure::Exit), || {
+            let runs = runs.clone();
+            async move {
+                runs.fetch_add(1, Ordering::SeqCst);
+                Err(Error::from("broker transport failure"))
+            }
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(1, runs.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,150 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::reference_data::{strip_http_scheme, Location, ReferenceData};
+
+/// Location of the generated datasets, known in [`reference_data`].
+const SPATIAL: &str = "https://data.geonorge.no/administrativeEnheter/fylke/id/173142";
+
+/// Formats and media types of the generated distributions, taken in turn,
+/// all known in the embedded reference data.
+const FORMATS: [(&str, &str); 3] = [
+    (
+        "http://publications.europa.eu/resource/authority/file-type/CSV",
+        "https://www.iana.org/assignments/media-types/text/csv",
+    ),
+    (
+        "http://publications.europa.eu/resource/authority/file-type/JSON",
+        "https://www.iana.org/assignments/media-types/application/json",
+    ),
+    (
+        "https://www.iana.org/assignments/media-types/application/geo+json",
+        "https://www.iana.org/assignments/media-types/application/geo+json",
+    ),
+];
+
+/// Licenses of the generated distributions, taken in turn. The last one is
+/// not an open license.
+const LICENSES: [&str; 3] = [
+    "http://data.norge.no/nlod/no/2.0",
+    "http://creativecommons.org/licenses/by/4.0/",
+    "https://licenses.foo/proprietary",
+];
+
+/// Turtle of a dataset with the given number of distributions, having the
+/// properties the assessment measures, so that it exercises every metric.
+pub fn dataset_graph(distributions: usize) -> String {
+    let mut graph = format!(
+        r#"
+        @prefix dcat: <http://www.w3.org/ns/dcat#> .
+        @prefix dct: <http://purl.org/dc/terms/> .
+        @prefix dcatnomqa: <https://data.norge.no/vocabulary/dcatno-mqa#> .
+        @prefix vcard: <http://www.w3.org/2006/vcard/ns#> .
+        @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+
+        <https://datasets.foo/generated> a dcat:Dataset ;
+            dcatnomqa:hasAssessment <https://assessments.foo/datasets/generated> ;
+            dct:title "Generert datasett"@nb , "Generated dataset"@en ;
+            dct:description "Datasett med {distributions} distribusjoner"@nb ;
+            dct:publisher <https://organization-catalog.fellesdatakatalog.digdir.no/organizations/971277882> ;
+            dct:accessRights <http://publications.europa.eu/resource/authority/access-right/PUBLIC> ;
+            dct:spatial <{SPATIAL}> ;
+            dct:temporal [
+                a dct:PeriodOfTime ;
+                dcat:startDate "2020-01-01"^^xsd:date ;
+                dcat:endDate "2024-12-31"^^xsd:date
+            ] ;
+            dct:issued "2020-01-01"^^xsd:date ;
+            dct:modified "2024-12-31"^^xsd:date ;
+            dct:accrualPeriodicity <http://publications.europa.eu/resource/authority/frequency/ANNUAL> ;
+            dcat:keyword "generert"@nb , "generated"@en ;
+            dcat:theme <http://publications.europa.eu/resource/authority/data-theme/GOVE> ;
+            dcat:contactPoint [ a vcard:Organization ; vcard:hasEmail <mailto:data@datasets.foo> ] .
+        "#
+    );
+
+    for i in 0..distributions {
+        let (format, media_type) = FORMATS[i % FORMATS.len()];
+        let license = LICENSES[i % LICENSES.len()];
+        graph.push_str(&format!(
+            r#"
+            <https://datasets.foo/generated> dcat:distribution <https://distributions.foo/{i}> .
+            <https://distributions.foo/{i}> a dcat:Distribution ;
+                dcatnomqa:hasAssessment <https://assessments.foo/distributions/{i}> ;
+                dct:title "Distribusjon {i}"@nb ;
+                dct:description "Distribusjon {i} av det genererte datasettet"@nb ;
+                dct:format <{format}> ;
+                dcat:mediaType <{media_type}> ;
+                dct:license <{license}> ;
+                dct:conformsTo <https://standards.foo/{i}> ;
+                dcat:accessURL <https://data.foo/{i}> ;
+                dcat:downloadURL <https://data.foo/{i}/download> .
+            "#
+        ));
+    }
+    graph
+}
+
+/// Reference data of the snapshots bundled in the binary together with the
+/// location of the generated datasets, assessed without the reference data
+/// service.
+pub fn reference_data() -> Arc<ReferenceData> {
+    let locations = HashMap::from([(
+        strip_http_scheme(SPATIAL.to_string()),
+        Location {
+            uri: SPATIAL.to_string(),
+        },
+    )]);
+    Arc::new(ReferenceData {
+        locations: Some(locations),
+        ..ReferenceData::embedded()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        metrics::{assess_with_options, AssessmentOptions},
+        vocab::dcat_mqa,
+    };
+
+    #[tokio::test]
+    async fn test_generated_dataset_assessed() {
+        let options = AssessmentOptions {
+            reference_data: Some(reference_data()),
+            ..Default::default()
+        };
+        let (_, summary) = assess_with_options(&dataset_graph(20), &options)
+            .await
+            .unwrap();
+
+        let measured = |metric: oxigraph::model::NamedNodeRef, computed_on: &str| {
+            summary
+                .measurements()
+                .find(|m| m.metric == metric.as_str() && m.computed_on == computed_on)
+                .map(|m| m.value)
+        };
+        assert_eq!(
+            Some(true),
+            measured(
+                dcat_mqa::SPATIAL_VOCABULARY_ALIGNMENT,
+                "https://datasets.foo/generated"
+            )
+        );
+        assert_eq!(
+            Some(true),
+            measured(dcat_mqa::KNOWN_LICENSE, "https://distributions.foo/0")
+        );
+        assert_eq!(
+            Some(false),
+            measured(dcat_mqa::KNOWN_LICENSE, "https://distributions.foo/2")
+        );
+        assert_eq!(
+            Some(true),
+            measured(
+                dcat_mqa::FORMAT_MEDIA_TYPE_VOCABULARY_ALIGNMENT,
+                "https://distributions.foo/19"
+            )
+        );
+    }
+}
@@ -20,19 +20,36 @@ pub mod dcterms {
     pub const MODIFIED: N = n!("http://purl.org/dc/terms/modified");
     pub const RIGHTS: N = n!("http://purl.org/dc/terms/rights");
     pub const LICENSE: N = n!("http://purl.org/dc/terms/license");
+    pub const ACCRUAL_PERIODICITY: N = n!("http://purl.org/dc/terms/accrualPeriodicity");
+    pub const CONFORMS_TO: N = n!("http://purl.org/dc/terms/conformsTo");
+    pub const TITLE: N = n!("http://purl.org/dc/terms/title");
+    pub const DESCRIPTION: N = n!("http://purl.org/dc/terms/description");
+    pub const IDENTIFIER: N = n!("http://purl.org/dc/terms/identifier");
+    pub const IS_PART_OF: N = n!("http://purl.org/dc/terms/isPartOf");
 }
 
 pub mod dcat {
     use super::N;
 
+    pub const CATALOG_CLASS: N = n!("http://www.w3.org/ns/dcat#Catalog");
     pub const DATASET_CLASS: N = n!("http://www.w3.org/ns/dcat#Dataset");
+    pub const DATASET_SERIES_CLASS: N = n!("http://www.w3.org/ns/dcat#DatasetSeries");
+    pub const IN_SERIES: N = n!("http://www.w3.org/ns/dcat#inSeries");
+    pub const DATASET_PREDICATE: N = n!("http://www.w3.org/ns/dcat#dataset");
     pub const DISTRIBUTION: N = n!("http://www.w3.org/ns/dcat#distribution");
+    pub const DATA_SERVICE_CLASS: N = n!("http://www.w3.org/ns/dcat#DataService");
+    pub const ACCESS_SERVICE: N = n!("http://www.w3.org/ns/dcat#accessService");
+    pub const ENDPOINT_URL: N = n!("http://www.w3.org/ns/dcat#endpointURL");
+    pub const ENDPOINT_DESCRIPTION: N = n!("http://www.w3.org/ns/dcat#endpointDescription");
     pub const THEME: N = n!("http://www.w3.org/ns/dcat#theme");
     pub const CONTACT_POINT: N = n!("http://www.w3.org/ns/dcat#contactPoint");
     pub const KEYWORD: N = n!("http://www.w3.org/ns/dcat#keyword");
     pub const BYTE_SIZE: N = n!("http://www.w3.org/ns/dcat#byteSize");
     pub const DOWNLOAD_URL: N = n!("http://www.w3.org/ns/dcat#downloadURL");
+    pub const ACCESS_URL: N = n!("http://www.w3.org/ns/dcat#accessURL");
     pub const MEDIA_TYPE: N = n!("http://www.w3.org/ns/dcat#mediaType");
+    pub const START_DATE: N = n!("http://www.w3.org/ns/dcat#startDate");
+    pub const END_DATE: N = n!("http://www.w3.org/ns/dcat#endDate");
 }
 
 pub mod dqv {
@@ -43,6 +60,8 @@ pub mod dqv {
     pub const IS_MEASUREMENT_OF: N = n!("http://www.w3.org/ns/dqv#isMeasurementOf");
     pub const COMPUTED_ON: N = n!("http://www.w3.org/ns/dqv#computedOn");
     pub const VALUE: N = n!("http://www.w3.org/ns/dqv#value");
+    pub const IN_DIMENSION: N = n!("http://www.w3.org/ns/dqv#inDimension");
+    pub const EXPECTED_DATA_TYPE: N = n!("http://www.w3.org/ns/dqv#expectedDataType");
 }
 
 pub mod dcat_mqa {
@@ -57,10 +76,26 @@ pub mod dcat_mqa {
         n!("https://data.norge.no/vocabulary/dcatno-mqa#DistributionAssessment");
     pub const HAS_DISTRIBUTION_ASSESSMENT: N =
         n!("https://data.norge.no/vocabulary/dcatno-mqa#hasDistributionAssessment");
+    pub const HAS_MEMBER_ASSESSMENT: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#hasMemberAssessment");
     pub const CONTAINS_QUALITY_MEASUREMENT: N =
         n!("https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement");
     pub const CONTAINS_QUALITY_ANNOTATION: N =
         n!("https://data.norge.no/vocabulary/dcatno-mqa#containsQualityAnnotation");
+    pub const PROCESSING_DURATION_MILLIS: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#processingDurationMillis");
+    pub const OMITTED_DISTRIBUTION_ASSESSMENTS: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#omittedDistributionAssessments");
+    pub const ASSESSMENT_VERSION: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#assessmentVersion");
+
+    // Dimensions
+    pub const FINDABILITY: N = n!("https://data.norge.no/vocabulary/dcatno-mqa#findability");
+    pub const ACCESSIBILITY: N = n!("https://data.norge.no/vocabulary/dcatno-mqa#accessibility");
+    pub const INTEROPERABILITY: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#interoperability");
+    pub const REUSABILITY: N = n!("https://data.norge.no/vocabulary/dcatno-mqa#reusability");
+    pub const CONTEXTUALITY: N = n!("https://data.norge.no/vocabulary/dcatno-mqa#contextuality");
 
     // Stars
     pub const ZERO_STARS: N = n!("https://data.norge.no/vocabulary/dcatno-mqa#zeroStars");
@@ -79,10 +114,31 @@ pub mod dcat_mqa {
         n!("https://data.norge.no/vocabulary/dcatno-mqa#spatialAvailability");
     pub const TEMPORAL_AVAILABILITY: N =
         n!("https://data.norge.no/vocabulary/dcatno-mqa#temporalAvailability");
+    pub const SPATIAL_VOCABULARY_ALIGNMENT: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#spatialVocabularyAlignment");
+    pub const TEMPORAL_COMPLETENESS: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#temporalCompleteness");
+    pub const TITLE_AVAILABILITY: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#titleAvailability");
+    pub const DESCRIPTION_AVAILABILITY: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#descriptionAvailability");
+    pub const KEYWORD_LANGUAGE_TAGGED: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#keywordLanguageTagged");
+    pub const DESCRIPTION_LANGUAGE_TAGGED: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#descriptionLanguageTagged");
 
     // Accessibility
+    pub const DISTRIBUTION_AVAILABILITY: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#distributionAvailability");
+    pub const NO_DUPLICATE_DISTRIBUTIONS: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#noDuplicateDistributions");
     pub const DOWNLOAD_URL_AVAILABILITY: N =
         n!("https://data.norge.no/vocabulary/dcatno-mqa#downloadUrlAvailability");
+    pub const URL_HTTPS: N = n!("https://data.norge.no/vocabulary/dcatno-mqa#urlHttps");
+    pub const ENDPOINT_URL_AVAILABILITY: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#endpointUrlAvailability");
+    pub const ENDPOINT_DESCRIPTION_AVAILABILITY: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#endpointDescriptionAvailability");
 
     // Interoperability
     pub const FORMAT_AVAILABILITY: N =
@@ -95,8 +151,12 @@ pub mod dcat_mqa {
         n!("https://data.norge.no/vocabulary/dcatno-mqa#formatMediaTypeNonProprietary");
     pub const FORMAT_MEDIA_TYPE_MACHINE_INTERPRETABLE: N =
         n!("https://data.norge.no/vocabulary/dcatno-mqa#formatMediaTypeMachineInterpretable");
+    pub const FORMAT_MEDIA_TYPE_CONSISTENCY: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#formatMediaTypeConsistency");
     pub const AT_LEAST_FOUR_STARS: N =
         n!("https://data.norge.no/vocabulary/dcatno-mqa#atLeastFourStars");
+    pub const STRUCTURAL_COMPLIANCE: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#structuralCompliance");
 
     // Reusability
     pub const LICENSE_AVAILABILITY: N =
@@ -111,6 +171,8 @@ pub mod dcat_mqa {
         n!("https://data.norge.no/vocabulary/dcatno-mqa#contactPointAvailability");
     pub const PUBLISHER_AVAILABILITY: N =
         n!("https://data.norge.no/vocabulary/dcatno-mqa#publisherAvailability");
+    pub const PUBLISHER_VOCABULARY_ALIGNMENT: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#publisherVocabularyAlignment");
 
     // Contextuality
     pub const RIGHTS_AVAILABILITY: N =
@@ -121,18 +183,39 @@ pub mod dcat_mqa {
         n!("https://data.norge.no/vocabulary/dcatno-mqa#dateIssuedAvailability");
     pub const DATE_MODIFIED_AVAILABILITY: N =
         n!("https://data.norge.no/vocabulary/dcatno-mqa#dateModifiedAvailability");
+    pub const ACCRUAL_PERIODICITY_AVAILABILITY: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#accrualPeriodicityAvailability");
+    pub const CONFORMS_TO_AVAILABILITY: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#conformsToAvailability");
 }
 
 pub mod prov {
     use super::N;
 
     pub const WAS_DERIVED_FROM: N = n!("http://www.w3.org/ns/prov#wasDerivedFrom");
+    pub const WAS_INFLUENCED_BY: N = n!("http://www.w3.org/ns/prov#wasInfluencedBy");
 }
 
 pub mod oa {
     use super::N;
 
+    pub const ANNOTATION_CLASS: N = n!("http://www.w3.org/ns/oa#Annotation");
     pub const HAS_BODY: N = n!("http://www.w3.org/ns/oa#hasBody");
+    pub const BODY_VALUE: N = n!("http://www.w3.org/ns/oa#bodyValue");
+    pub const HAS_TARGET: N = n!("http://www.w3.org/ns/oa#hasTarget");
     pub const MOTIVATED_BY: N = n!("http://www.w3.org/ns/oa#motivatedBy");
     pub const CLASSIFYING: N = n!("http://www.w3.org/ns/oa#classifying");
+    pub const DESCRIBING: N = n!("http://www.w3.org/ns/oa#describing");
+    pub const ASSESSING: N = n!("http://www.w3.org/ns/oa#assessing");
+}
+
+pub mod access_right {
+    use super::N;
+
+    pub const PUBLIC: N =
+        n!("http://publications.europa.eu/resource/authority/access-right/PUBLIC");
+    pub const RESTRICTED: N =
+        n!("http://publications.europa.eu/resource/authority/access-right/RESTRICTED");
+    pub const NON_PUBLIC: N =
+        n!("http://publications.europa.eu/resource/authority/access-right/NON_PUBLIC");
 }
@@ -0,0 +1,152 @@
+use actix_web::{
+    http::{header, StatusCode},
+    test, App,
+};
+
+use fdk_mqa_property_checker::{assess_api::configure, AssessmentOptions, GraphLayout};
+
+const DATASET: &str = r#"
+    @prefix dcat: <http://www.w3.org/ns/dcat#> .
+    @prefix dct: <http://purl.org/dc/terms/> .
+    @prefix dcatnomqa: <https://data.norge.no/vocabulary/dcatno-mqa#> .
+
+    <https://dataset.foo> a dcat:Dataset ;
+        dcatnomqa:hasAssessment <https://dataset.assessment.foo> ;
+        dct:title "Dataset" .
+    "#;
+
+async fn post(
+    max_body_bytes: usize,
+    headers: &[(header::HeaderName, &str)],
+    graph: &str,
+) -> (StatusCode, Option<String>, String) {
+    post_with_options(max_body_bytes, AssessmentOptions::default(), headers, graph).await
+}
+
+async fn post_with_options(
+    max_body_bytes: usize,
+    options: AssessmentOptions,
+    headers: &[(header::HeaderName, &str)],
+    graph: &str,
+) -> (StatusCode, Option<String>, String) {
+    let app = test::init_service(
+        App::new().configure(|cfg| configure(cfg, max_body_bytes, options.clone())),
+    )
+    .await;
+    let mut request = test::TestRequest::post()
+        .uri("/assess")
+        .set_payload(graph.to_string());
+    for (name, value) in headers {
+        request = request.insert_header((name.clone(), *value));
+    }
+
+    let response = test::call_service(&app, request.to_request()).await;
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .map(|v| v.to_str().unwrap().to_string());
+    let body = test::read_body(response).await;
+    (
+        status,
+        content_type,
+        String::from_utf8(body.to_vec()).unwrap(),
+    )
+}
+
+#[actix_web::test]
+async fn test_assess_turtle() {
+    let (status, content_type, body) = post(
+        1024,
+        &[
+            (header::CONTENT_TYPE, "text/turtle"),
+            (header::ACCEPT, "text/turtle"),
+        ],
+        DATASET,
+    )
+    .await;
+
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(Some("text/turtle".to_string()), content_type);
+    assert!(body.contains("DatasetAssessment"));
+    assert!(body.contains("<https://dataset.assessment.foo>"));
+}
+
+#[actix_web::test]
+async fn test_assess_trig() {
+    let options = AssessmentOptions {
+        graph_layout: GraphLayout::PerAssessmentNamedGraphs,
+        ..Default::default()
+    };
+    let (status, content_type, body) = post_with_options(
+        1024,
+        options.clone(),
+        &[
+            (header::CONTENT_TYPE, "text/turtle"),
+            (header::ACCEPT, "application/trig"),
+        ],
+        DATASET,
+    )
+    .await;
+
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(Some("application/trig".to_string()), content_type);
+    assert!(body.contains("<https://dataset.assessment.foo>"));
+    assert!(body.contains('{'));
+
+    let (status, _, _) =
+        post_with_options(1024, options, &[(header::ACCEPT, "text/turtle")], DATASET).await;
+    assert_eq!(StatusCode::NOT_ACCEPTABLE, status);
+}
+
+#[actix_web::test]
+async fn test_assess_without_headers() {
+    let (status, _, _) = post(1024, &[], DATASET).await;
+
+    assert_eq!(StatusCode::OK, status);
+}
+
+#[actix_web::test]
+async fn test_invalid_turtle() {
+    let (status, _, _) = post(
+        1024,
+        &[(header::CONTENT_TYPE, "text/turtle")],
+        "<https://dataset.foo> a",
+    )
+    .await;
+
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+}
+
+#[actix_web::test]
+async fn test_no_dataset() {
+    let (status, _, _) = post(
+        1024,
+        &[(header::CONTENT_TYPE, "text/turtle")],
+        "<https://foo> <https://bar> <https://baz> .",
+    )
+    .await;
+
+    assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, status);
+}
+
+#[actix_web::test]
+async fn test_payload_too_large() {
+    let (status, _, _) = post(64, &[(header::CONTENT_TYPE, "text/turtle")], DATASET).await;
+
+    assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, status);
+}
+
+#[actix_web::test]
+async fn test_unsupported_formats() {
+    let (status, _, _) = post(
+        1024,
+        &[(header::CONTENT_TYPE, "application/ld+json")],
+        DATASET,
+    )
+    .await;
+    assert_eq!(StatusCode::UNSUPPORTED_MEDIA_TYPE, status);
+
+    let (status, _, _) = post(1024, &[(header::ACCEPT, "application/ld+json")], DATASET).await;
+    assert_eq!(StatusCode::NOT_ACCEPTABLE, status);
+}
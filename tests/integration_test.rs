@@ -2,15 +2,19 @@ use std::time::Duration;
 
 use fdk_mqa_property_checker::{
     kafka::{
-        create_consumer, create_producer, create_sr_settings, handle_message, BROKERS, INPUT_TOPIC,
-        OUTPUT_TOPIC, SCHEMA_REGISTRY,
+        create_sr_settings,
+        pipeline::{create_consumer, create_producer, MessageHandler},
+        producer::{send_and_confirm, DELIVERY_POLICY},
+        PropertyChecker, BROKERS, GROUP_ID, INPUT_TOPIC, OUTPUT_TOPIC, SCHEMA_REGISTRY,
     },
     schemas::{DatasetEvent, DatasetEventType, MqaEvent},
 };
 use kafka_utils::{consume_all_messages, receive_message, AvroProducer};
 use oxigraph::store::Store;
-use rdkafka::consumer::StreamConsumer;
-use schema_registry_converter::async_impl::avro::{AvroDecoder, AvroEncoder};
+use rdkafka::{
+    consumer::{ConsumerContext, StreamConsumer},
+    producer::FutureRecord,
+};
 use uuid::Uuid;
 use sophia_api::term::SimpleTerm;
 use sophia_api::source::TripleSource;
@@ -30,10 +34,9 @@ async fn test() {
     .await;
 }
 
-pub async fn process_single_message(consumer: StreamConsumer) {
-    let producer = create_producer().unwrap();
-    let mut encoder = AvroEncoder::new(create_sr_settings().unwrap());
-    let mut decoder = AvroDecoder::new(create_sr_settings().unwrap());
+pub async fn process_single_message<C: ConsumerContext + 'static>(consumer: StreamConsumer<C>) {
+    let producer = create_producer(&BROKERS).unwrap();
+    let mut handler = PropertyChecker::new(create_sr_settings().unwrap());
     let input_store = Store::new().unwrap();
     let output_store = Store::new().unwrap();
 
@@ -42,20 +45,26 @@ pub async fn process_single_message(consumer: StreamConsumer) {
         .await
         .expect("no message received within timeout duration");
 
-    handle_message(
-        &producer,
-        &mut decoder,
-        &mut encoder,
-        &input_store,
-        &output_store,
-        &message,
-    )
-    .await
-    .unwrap();
+    let handled = handler
+        .handle(&message.detach(), &input_store, &output_store)
+        .await
+        .unwrap();
+    for record in handled.records {
+        send_and_confirm(
+            &producer,
+            FutureRecord::to(&record.topic)
+                .key(&record.key)
+                .payload(&record.payload)
+                .headers(record.headers),
+            &DELIVERY_POLICY,
+        )
+        .await
+        .unwrap();
+    }
 }
 
 async fn assert_transformation(input: &str, output: &str) {
-    let consumer = create_consumer().unwrap();
+    let consumer = create_consumer(&BROKERS, &GROUP_ID, &INPUT_TOPIC, 0).unwrap();
     // Clear topic of all existing messages.
     consume_all_messages(&consumer).await.unwrap();
     // Start async url-checker process.
@@ -68,6 +77,8 @@ async fn assert_transformation(input: &str, output: &str) {
         timestamp: 1647698566000,
         fdk_id: uuid.to_string(),
         graph: input.to_string(),
+        previous_graph: None,
+        base_iri: None,
     };
 
     // Create consumer and consume all existing messages on output topic.
@@ -2,7 +2,7 @@ use std::time::Duration;
 
 use rdkafka::{
     config::RDKafkaLogLevel,
-    consumer::{CommitMode, Consumer, StreamConsumer},
+    consumer::{CommitMode, Consumer, ConsumerContext, StreamConsumer},
     error::KafkaError,
     message::BorrowedMessage,
     producer::{FutureProducer, FutureRecord},
@@ -43,7 +43,9 @@ pub fn create_sr_settings(schema_registry_urls: &str) -> Result<SrSettings, Erro
 }
 
 /// Consumes all messages until no more can be received within the timeout period.
-pub async fn consume_all_messages(consumer: &StreamConsumer) -> Result<(), Error> {
+pub async fn consume_all_messages<C: ConsumerContext + 'static>(
+    consumer: &StreamConsumer<C>,
+) -> Result<(), Error> {
     loop {
         // Loop untill no nessage can be received within timeout.
         let timeout_duration = Duration::from_millis(500);
@@ -56,10 +58,10 @@ pub async fn consume_all_messages(consumer: &StreamConsumer) -> Result<(), Error
 }
 
 /// Consumes and returns a single message, if received within the timeout period.
-pub async fn receive_message(
-    consumer: &StreamConsumer,
+pub async fn receive_message<C: ConsumerContext + 'static>(
+    consumer: &StreamConsumer<C>,
     timeout_duration: Duration,
-) -> Result<BorrowedMessage, Error> {
+) -> Result<BorrowedMessage<'_>, Error> {
     match tokio::time::timeout(timeout_duration, consumer.recv()).await {
         Ok(result) => {
             let message = result?;
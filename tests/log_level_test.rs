@@ -0,0 +1,117 @@
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+use actix_web::{http::StatusCode, test, App};
+use tracing_subscriber::{fmt, layer::SubscriberExt, reload, EnvFilter};
+
+use fdk_mqa_property_checker::log_level::{configure, LogLevelHandle, ADMIN_TOKEN_HEADER};
+
+/// Log output shared between the subscriber and the test.
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Buffer {
+    fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Subscriber logging to a buffer at info level, and its reload handle.
+fn subscriber(
+    buffer: Buffer,
+) -> (
+    impl tracing::Subscriber + Send + Sync + 'static,
+    LogLevelHandle,
+) {
+    let (filter, handle) = reload::Layer::new(EnvFilter::new("info"));
+    let subscriber = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(move || buffer.clone()));
+    (subscriber, handle)
+}
+
+async fn put(
+    handle: LogLevelHandle,
+    token: Option<&str>,
+    header: Option<&str>,
+    directives: &str,
+) -> StatusCode {
+    let token = token.map(str::to_string);
+    let app = test::init_service(App::new().configure(|cfg| configure(cfg, handle, token))).await;
+    let mut request = test::TestRequest::put()
+        .uri("/admin/log-level")
+        .set_payload(directives.to_string());
+    if let Some(header) = header {
+        request = request.insert_header((ADMIN_TOKEN_HEADER, header));
+    }
+    test::call_service(&app, request.to_request())
+        .await
+        .status()
+}
+
+#[actix_web::test]
+async fn test_change_log_level() {
+    let buffer = Buffer::default();
+    let (subscriber, handle) = subscriber(buffer.clone());
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    tracing::debug!("before change");
+    assert_eq!(StatusCode::OK, put(handle, None, None, "debug").await);
+    tracing::debug!("after change");
+
+    let logs = buffer.contents();
+    assert!(!logs.contains("before change"));
+    assert!(logs.contains("after change"));
+}
+
+#[actix_web::test]
+async fn test_invalid_directives() {
+    let buffer = Buffer::default();
+    let (subscriber, handle) = subscriber(buffer.clone());
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    assert_eq!(
+        StatusCode::BAD_REQUEST,
+        put(handle, None, None, "fdk_mqa_property_checker=loud").await
+    );
+    tracing::debug!("after rejected change");
+
+    assert!(!buffer.contents().contains("after rejected change"));
+}
+
+#[actix_web::test]
+async fn test_admin_token() {
+    let buffer = Buffer::default();
+    let (subscriber, handle) = subscriber(buffer.clone());
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    assert_eq!(
+        StatusCode::UNAUTHORIZED,
+        put(handle.clone(), Some("secret"), None, "debug").await
+    );
+    assert_eq!(
+        StatusCode::UNAUTHORIZED,
+        put(handle.clone(), Some("secret"), Some("guess"), "debug").await
+    );
+    tracing::debug!("unauthorized change");
+    assert!(!buffer.contents().contains("unauthorized change"));
+
+    assert_eq!(
+        StatusCode::OK,
+        put(handle, Some("secret"), Some("secret"), "debug").await
+    );
+    tracing::debug!("change with token");
+    assert!(buffer.contents().contains("change with token"));
+}
@@ -0,0 +1,55 @@
+use actix_web::{
+    http::{header, StatusCode},
+    test, App,
+};
+
+use fdk_mqa_property_checker::{
+    metrics_catalog::{configure, metrics_catalog},
+    metrics_registry::registry,
+    AssessmentOptions,
+};
+
+#[actix_web::test]
+async fn test_get_metrics_catalog() {
+    let app = test::init_service(
+        App::new().configure(|cfg| configure(cfg, metrics_catalog(&AssessmentOptions::default()))),
+    )
+    .await;
+    let request = test::TestRequest::get()
+        .uri("/metrics-catalog")
+        .to_request();
+
+    let response = test::call_service(&app, request).await;
+    assert_eq!(StatusCode::OK, response.status());
+    assert_eq!(
+        "application/json",
+        response.headers().get(header::CONTENT_TYPE).unwrap()
+    );
+
+    let catalog: Vec<serde_json::Value> = test::read_body_json(response).await;
+    assert_eq!(registry().len(), catalog.len());
+    let known_license = catalog
+        .iter()
+        .find(|entry| entry["metric"] == "https://data.norge.no/vocabulary/dcatno-mqa#knownLicense")
+        .unwrap();
+    assert_eq!(
+        serde_json::json!(["distribution"]),
+        known_license["targets"]
+    );
+    assert_eq!(
+        "https://data.norge.no/vocabulary/dcatno-mqa#reusability",
+        known_license["dimension"]
+    );
+    assert_eq!(Some(true), known_license["enabled"].as_bool());
+}
+
+#[actix_web::test]
+async fn test_post_metrics_catalog_not_allowed() {
+    let app = test::init_service(App::new().configure(|cfg| configure(cfg, Vec::new()))).await;
+    let request = test::TestRequest::post()
+        .uri("/metrics-catalog")
+        .to_request();
+
+    let response = test::call_service(&app, request).await;
+    assert_eq!(StatusCode::METHOD_NOT_ALLOWED, response.status());
+}
@@ -0,0 +1,73 @@
+use std::env;
+
+use fdk_mqa_property_checker::assess_with_summary;
+use httpmock::MockServer;
+
+#[tokio::test]
+async fn test_reference_data_fetched_once_per_assessment() {
+    let server = MockServer::start_async().await;
+    let mut mocks = Vec::new();
+    for (path, body) in [
+        (
+            "/reference-data/iana/media-types",
+            r#"{"mediaTypes":[{"uri":"https://www.iana.org/assignments/media-types/text/csv","name":"csv","type":"text","subType":"csv"}]}"#,
+        ),
+        (
+            "/reference-data/eu/file-types",
+            r#"{"fileTypes":[{"uri":"http://publications.europa.eu/resource/authority/file-type/CSV","code":"CSV","mediaType":"text/csv"}]}"#,
+        ),
+        (
+            "/reference-data/open-licenses",
+            r#"{"openLicenses":[{"uri":"http://data.norge.no/nlod/no/2.0","code":"NLOD20"}]}"#,
+        ),
+        (
+            "/reference-data/eu/countries",
+            r#"{"countries":[{"uri":"http://publications.europa.eu/resource/authority/country/NOR"}]}"#,
+        ),
+    ] {
+        mocks.push(
+            server
+                .mock_async(|when, then| {
+                    when.path(path);
+                    then.status(200)
+                        .header("content-type", "application/json")
+                        .body(body);
+                })
+                .await,
+        );
+    }
+    // Reference data lookups are cached for the lifetime of the process, so
+    // this must be the only test in this file.
+    env::set_var("REFERENCE_DATA_BASE_URL", server.base_url());
+
+    let mut graph = r#"
+        @prefix dcat: <http://www.w3.org/ns/dcat#> .
+        @prefix dct: <http://purl.org/dc/terms/> .
+        @prefix dcatnomqa: <https://data.norge.no/vocabulary/dcatno-mqa#> .
+
+        <https://dataset.foo> a dcat:Dataset ;
+            dcatnomqa:hasAssessment <https://dataset.assessment.foo> ;
+            dct:spatial <http://publications.europa.eu/resource/authority/country/NOR> .
+        "#
+    .to_string();
+    for i in 0..50 {
+        graph.push_str(&format!(
+            r#"
+            <https://dataset.foo> dcat:distribution <https://distribution.foo/{i}> .
+            <https://distribution.foo/{i}> a dcat:Distribution ;
+                dcatnomqa:hasAssessment <https://distribution.assessment.foo/{i}> ;
+                dct:format <https://www.iana.org/assignments/media-types/text/csv> ,
+                    <http://publications.europa.eu/resource/authority/file-type/CSV> ,
+                    <https://www.iana.org/assignments/media-types/application/json> ;
+                dct:license <http://data.norge.no/nlod/no/2.0> .
+            "#
+        ));
+    }
+
+    let (_, summary) = assess_with_summary(&graph).await.unwrap();
+
+    assert_eq!(51, summary.assessments.len());
+    for mock in mocks {
+        mock.assert_hits_async(1).await;
+    }
+}